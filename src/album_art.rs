@@ -0,0 +1,568 @@
+use crossterm::{cursor::MoveTo, queue};
+use failure::err_msg;
+use image::{imageops::FilterType, RgbImage};
+use rspotify::spotify::model::image::Image;
+use std::{
+    cell::{Ref, RefCell},
+    cmp::min,
+    collections::{HashMap, HashSet, VecDeque},
+    env, fs,
+    io::{self, Write},
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::Duration,
+};
+use tui::layout::Rect;
+
+const CACHE_DIR: &str = "spotify-tui";
+const ALBUM_ART_DIR: &str = "album_art";
+// Spotify usually offers 640, 300 and 64 pixel wide covers
+const PREFERRED_IMAGE_WIDTH: u32 = 300;
+// Decoded covers kept in memory, the disk cache holds everything else
+const MAX_LOADED_IMAGES: usize = 32;
+// Covers scaled for half-blocks, enough for the playbar and an album at a couple of sizes
+const MAX_RESIZED_IMAGES: usize = 4;
+const DOWNLOAD_TIMEOUT_SECONDS: u64 = 5;
+// The kitty protocol limits the payload of a single escape sequence
+const KITTY_CHUNK_SIZE: usize = 4096;
+// Used when the terminal does not report its size in pixels
+const DEFAULT_CELL_SIZE: (u16, u16) = (8, 16);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Protocol {
+    Auto,
+    HalfBlocks,
+    Kitty,
+    Sixel,
+}
+
+impl Protocol {
+    pub fn parse(protocol: &str) -> Result<Protocol, failure::Error> {
+        match protocol.to_lowercase().as_str() {
+            "auto" => Ok(Protocol::Auto),
+            "halfblocks" => Ok(Protocol::HalfBlocks),
+            "kitty" => Ok(Protocol::Kitty),
+            "sixel" => Ok(Protocol::Sixel),
+            _ => Err(failure::format_err!(
+                "Album art protocol must be one of auto, halfblocks, kitty or sixel, is {}",
+                protocol
+            )),
+        }
+    }
+
+    // Querying the terminal would mean reading its reply from stdin, so rely on the variables
+    // terminals set instead and fall back to half-blocks which work everywhere with true color
+    pub fn detect(self) -> Protocol {
+        if self != Protocol::Auto {
+            return self;
+        }
+
+        let term = env::var("TERM").unwrap_or_default();
+        let term_program = env::var("TERM_PROGRAM").unwrap_or_default();
+
+        if env::var("KITTY_WINDOW_ID").is_ok() || term.contains("kitty") {
+            Protocol::Kitty
+        } else if term.contains("sixel")
+            || term.starts_with("mlterm")
+            || term.starts_with("foot")
+            || term_program == "WezTerm"
+        {
+            Protocol::Sixel
+        } else {
+            Protocol::HalfBlocks
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Placement {
+    url: String,
+    area: Rect,
+}
+
+type FetchedImage = (String, Option<RgbImage>);
+
+#[derive(Clone, Debug, PartialEq)]
+struct ResizedKey {
+    url: String,
+    bounds: (u32, u32),
+}
+
+pub struct AlbumArt {
+    protocol: Protocol,
+    // `None` marks a cover that could not be fetched so it is not retried on every tick
+    images: HashMap<String, Option<RgbImage>>,
+    // Urls of `images` from the oldest to the newest, for evicting the oldest cover
+    loaded_urls: VecDeque<String>,
+    // Covers are fetched on their own threads so a slow download never stalls the UI
+    pending_urls: HashSet<String>,
+    fetched_tx: Sender<FetchedImage>,
+    fetched_rx: Receiver<FetchedImage>,
+    // Scaling a cover takes longer than the rest of a frame, so it is only done when its url or
+    // area changes. The newest is last
+    resized: RefCell<VecDeque<(ResizedKey, RgbImage)>>,
+    // Filled while drawing, the graphics protocols are written after the frame is flushed
+    placements: RefCell<Vec<Placement>>,
+    drawn_placements: Vec<Placement>,
+    drawn_size: Rect,
+    needs_full_redraw: bool,
+}
+
+impl Default for AlbumArt {
+    fn default() -> Self {
+        AlbumArt::new(Protocol::HalfBlocks)
+    }
+}
+
+impl AlbumArt {
+    pub fn new(protocol: Protocol) -> AlbumArt {
+        let (fetched_tx, fetched_rx) = mpsc::channel();
+        AlbumArt {
+            protocol: protocol.detect(),
+            images: HashMap::new(),
+            loaded_urls: VecDeque::new(),
+            pending_urls: HashSet::new(),
+            fetched_tx,
+            fetched_rx,
+            resized: RefCell::new(VecDeque::new()),
+            placements: RefCell::new(vec![]),
+            drawn_placements: vec![],
+            drawn_size: Rect::default(),
+            needs_full_redraw: false,
+        }
+    }
+
+    pub fn protocol(&self) -> Protocol {
+        self.protocol
+    }
+
+    pub fn get(&self, url: &str) -> Option<&RgbImage> {
+        self.images.get(url).and_then(|image| image.as_ref())
+    }
+
+    /// The cover scaled to fit in `bounds` pixels
+    pub fn get_resized(&self, url: &str, bounds: (u32, u32)) -> Option<Ref<'_, RgbImage>> {
+        let image = self.get(url)?;
+        let (width, height) = fit(image.dimensions(), bounds);
+        if width == 0 || height == 0 {
+            return None;
+        }
+
+        let key = ResizedKey {
+            url: url.to_string(),
+            bounds,
+        };
+        let position = self
+            .resized
+            .borrow()
+            .iter()
+            .position(|(resized_key, _)| *resized_key == key);
+        let index = match position {
+            Some(index) => index,
+            None => {
+                let mut resized = self.resized.borrow_mut();
+                if resized.len() >= MAX_RESIZED_IMAGES {
+                    resized.pop_front();
+                }
+                let image = image::imageops::resize(image, width, height, FilterType::Triangle);
+                resized.push_back((key, image));
+                resized.len() - 1
+            }
+        };
+        Some(Ref::map(self.resized.borrow(), |resized| &resized[index].1))
+    }
+
+    pub fn is_loaded(&self, url: &str) -> bool {
+        self.images.contains_key(url)
+    }
+
+    // Starts fetching a cover in the background, it shows up once `receive` picks it up
+    pub fn load(&mut self, url: &str) {
+        if self.is_loaded(url) || !self.pending_urls.insert(url.to_string()) {
+            return;
+        }
+
+        let url = url.to_string();
+        let fetched_tx = self.fetched_tx.clone();
+        thread::spawn(move || {
+            let image = fetch_image(&url).ok();
+            // The receiver is only gone when the app is exiting
+            fetched_tx.send((url, image)).ok();
+        });
+    }
+
+    pub fn receive(&mut self) {
+        while let Ok((url, image)) = self.fetched_rx.try_recv() {
+            self.pending_urls.remove(&url);
+            self.insert(url, image);
+        }
+    }
+
+    fn insert(&mut self, url: String, image: Option<RgbImage>) {
+        if self.images.contains_key(&url) {
+            return;
+        }
+        while self.images.len() >= MAX_LOADED_IMAGES {
+            match self.loaded_urls.pop_front() {
+                Some(oldest_url) => {
+                    self.images.remove(&oldest_url);
+                }
+                None => break,
+            }
+        }
+        self.loaded_urls.push_back(url.clone());
+        self.images.insert(url, image);
+    }
+
+    // Reserve an area for a graphics protocol image, see `draw_graphics`
+    pub fn place(&self, url: &str, area: Rect) {
+        self.placements.borrow_mut().push(Placement {
+            url: url.to_string(),
+            area,
+        });
+    }
+
+    // Sixel images stay on screen until the cells under them are redrawn
+    pub fn take_full_redraw(&mut self) -> bool {
+        let needs_full_redraw = self.needs_full_redraw;
+        self.needs_full_redraw = false;
+        needs_full_redraw
+    }
+
+    pub fn draw_graphics<W: Write>(&mut self, w: &mut W, size: Rect) -> io::Result<()> {
+        let placements = self.placements.replace(vec![]);
+        if placements == self.drawn_placements && size == self.drawn_size {
+            return Ok(());
+        }
+
+        match self.protocol {
+            Protocol::Kitty => write!(w, "\x1b_Ga=d,q=2\x1b\\")?,
+            Protocol::Sixel => self.needs_full_redraw = !self.drawn_placements.is_empty(),
+            _ => {}
+        }
+
+        let cell_size = cell_size();
+        for placement in &placements {
+            if let Some(image) = self.get(&placement.url) {
+                let (width, height) = fit(
+                    image.dimensions(),
+                    (
+                        u32::from(placement.area.width) * u32::from(cell_size.0),
+                        u32::from(placement.area.height) * u32::from(cell_size.1),
+                    ),
+                );
+                queue!(w, MoveTo(placement.area.x, placement.area.y))
+                    .map_err(|e| io::Error::other(e.to_string()))?;
+
+                match self.protocol {
+                    Protocol::Kitty => {
+                        let columns = width.div_ceil(u32::from(cell_size.0));
+                        let rows = height.div_ceil(u32::from(cell_size.1));
+                        write_kitty(w, image, columns, rows)?;
+                    }
+                    Protocol::Sixel => {
+                        let image =
+                            image::imageops::resize(image, width, height, FilterType::Triangle);
+                        write_sixel(w, &image)?;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        w.flush()?;
+
+        self.drawn_placements = placements;
+        self.drawn_size = size;
+        Ok(())
+    }
+}
+
+// Picks the smallest cover that is still large enough to look sharp
+pub fn select_image_url(images: &[Image]) -> Option<&str> {
+    images
+        .iter()
+        .filter(|image| {
+            image
+                .width
+                .is_some_and(|width| width >= PREFERRED_IMAGE_WIDTH)
+        })
+        .min_by_key(|image| image.width)
+        .or_else(|| images.first())
+        .map(|image| image.url.as_str())
+}
+
+// Scales `size` down to fit in `bounds`, keeping the aspect ratio
+pub fn fit(size: (u32, u32), bounds: (u32, u32)) -> (u32, u32) {
+    let (width, height) = size;
+    let (max_width, max_height) = bounds;
+    if width == 0 || height == 0 {
+        return (0, 0);
+    }
+
+    if u64::from(width) * u64::from(max_height) > u64::from(height) * u64::from(max_width) {
+        (max_width, (height * max_width / width).max(1))
+    } else {
+        ((width * max_height / height).max(1), max_height)
+    }
+}
+
+fn get_cache_path(url: &str) -> Option<PathBuf> {
+    // Spotify image urls end with a unique id, e.g. https://i.scdn.co/image/ab67616d...
+    let name = url
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>();
+    if name.is_empty() {
+        return None;
+    }
+
+    dirs::cache_dir().map(|cache_dir| cache_dir.join(CACHE_DIR).join(ALBUM_ART_DIR).join(name))
+}
+
+fn fetch_image(url: &str) -> Result<RgbImage, failure::Error> {
+    let cache_path = get_cache_path(url);
+
+    if let Some(cache_path) = &cache_path {
+        if let Ok(bytes) = fs::read(cache_path) {
+            if let Ok(image) = image::load_from_memory(&bytes) {
+                return Ok(image.to_rgb8());
+            }
+        }
+    }
+
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(DOWNLOAD_TIMEOUT_SECONDS))
+        .build()?;
+    let mut response = client.get(url).send()?;
+    if !response.status().is_success() {
+        return Err(err_msg("Failed to download album art"));
+    }
+    let mut bytes = vec![];
+    response.copy_to(&mut bytes)?;
+    let image = image::load_from_memory(&bytes)?.to_rgb8();
+
+    // Caching is best effort, a failure only means downloading the cover again next time
+    if let Some(cache_path) = &cache_path {
+        if let Some(cache_dir) = cache_path.parent() {
+            if fs::create_dir_all(cache_dir).is_ok() {
+                fs::write(cache_path, &bytes).ok();
+            }
+        }
+    }
+
+    Ok(image)
+}
+
+#[cfg(unix)]
+fn cell_size() -> (u16, u16) {
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    let result = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+    if result == 0 && size.ws_col > 0 && size.ws_row > 0 && size.ws_xpixel > 0 && size.ws_ypixel > 0
+    {
+        (size.ws_xpixel / size.ws_col, size.ws_ypixel / size.ws_row)
+    } else {
+        DEFAULT_CELL_SIZE
+    }
+}
+
+#[cfg(not(unix))]
+fn cell_size() -> (u16, u16) {
+    DEFAULT_CELL_SIZE
+}
+
+// https://sw.kovidgoyal.net/kitty/graphics-protocol.html
+fn write_kitty<W: Write>(w: &mut W, image: &RgbImage, columns: u32, rows: u32) -> io::Result<()> {
+    let data = base64::encode(image.as_raw());
+    let chunks = data
+        .as_bytes()
+        .chunks(KITTY_CHUNK_SIZE)
+        .collect::<Vec<&[u8]>>();
+
+    for (index, chunk) in chunks.iter().enumerate() {
+        let more = if index + 1 < chunks.len() { 1 } else { 0 };
+        if index == 0 {
+            // `q=2` keeps kitty from replying on stdin, `C=1` from moving the cursor
+            write!(
+                w,
+                "\x1b_Ga=T,f=24,s={},v={},c={},r={},C=1,q=2,m={};",
+                image.width(),
+                image.height(),
+                columns,
+                rows,
+                more
+            )?;
+        } else {
+            write!(w, "\x1b_Gm={};", more)?;
+        }
+        w.write_all(chunk)?;
+        write!(w, "\x1b\\")?;
+    }
+    Ok(())
+}
+
+// Colours are reduced to a 6x6x6 cube, which all sixel terminals can hold in their registers
+fn sixel_color_index(pixel: &image::Rgb<u8>) -> usize {
+    let level = |value: u8| (usize::from(value) * 5 + 127) / 255;
+    level(pixel[0]) * 36 + level(pixel[1]) * 6 + level(pixel[2])
+}
+
+fn write_sixel_run(out: &mut String, sixel: char, count: usize) {
+    match count {
+        0 => {}
+        1..=3 => out.extend((0..count).map(|_| sixel)),
+        _ => out.push_str(&format!("!{}{}", count, sixel)),
+    }
+}
+
+pub fn encode_sixel(image: &RgbImage) -> String {
+    let (width, height) = image.dimensions();
+    let mut out = format!("\x1bPq\"1;1;{};{}", width, height);
+
+    for index in 0..216 {
+        out.push_str(&format!(
+            "#{};2;{};{};{}",
+            index,
+            index / 36 * 20,
+            index / 6 % 6 * 20,
+            index % 6 * 20
+        ));
+    }
+
+    let indices = image
+        .pixels()
+        .map(sixel_color_index)
+        .collect::<Vec<usize>>();
+    let pixel_index = |x: u32, y: u32| indices[(y * width + x) as usize];
+
+    for band_top in (0..height).step_by(6) {
+        let band_height = min(6, height - band_top);
+        let mut used_colors = [false; 216];
+        for y in band_top..band_top + band_height {
+            for x in 0..width {
+                used_colors[pixel_index(x, y)] = true;
+            }
+        }
+
+        for color in (0..216).filter(|color| used_colors[*color]) {
+            out.push_str(&format!("#{}", color));
+
+            let mut run_sixel = '?';
+            let mut run_count = 0;
+            for x in 0..width {
+                let bits = (0..band_height)
+                    .filter(|dy| pixel_index(x, band_top + dy) == color)
+                    .fold(0, |bits, dy| bits | (1 << dy));
+                let sixel = (63 + bits as u8) as char;
+
+                if sixel == run_sixel {
+                    run_count += 1;
+                } else {
+                    write_sixel_run(&mut out, run_sixel, run_count);
+                    run_sixel = sixel;
+                    run_count = 1;
+                }
+            }
+            write_sixel_run(&mut out, run_sixel, run_count);
+            // Return to the start of the band for the next colour
+            out.push('$');
+        }
+        out.push('-');
+    }
+
+    out.push_str("\x1b\\");
+    out
+}
+
+fn write_sixel<W: Write>(w: &mut W, image: &RgbImage) -> io::Result<()> {
+    w.write_all(encode_sixel(image).as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: u32, url: &str) -> Image {
+        Image {
+            height: Some(width),
+            url: url.to_string(),
+            width: Some(width),
+        }
+    }
+
+    #[test]
+    fn select_image_url_test() {
+        let images = vec![
+            image(640, "large"),
+            image(300, "medium"),
+            image(64, "small"),
+        ];
+        assert_eq!(select_image_url(&images), Some("medium"));
+        assert_eq!(select_image_url(&images[2..]), Some("small"));
+        assert_eq!(select_image_url(&[]), None);
+    }
+
+    #[test]
+    fn evicts_oldest_image_test() {
+        let mut album_art = AlbumArt::default();
+        for index in 0..=MAX_LOADED_IMAGES {
+            album_art.insert(index.to_string(), None);
+        }
+
+        assert!(!album_art.is_loaded("0"));
+        assert!(album_art.is_loaded("1"));
+        assert!(album_art.is_loaded(&MAX_LOADED_IMAGES.to_string()));
+        assert_eq!(album_art.images.len(), MAX_LOADED_IMAGES);
+    }
+
+    #[test]
+    fn resized_image_test() {
+        let mut album_art = AlbumArt::default();
+        album_art.insert(
+            "cover".to_string(),
+            Some(RgbImage::from_pixel(4, 4, image::Rgb([255, 0, 0]))),
+        );
+
+        assert_eq!(
+            album_art.get_resized("cover", (2, 2)).unwrap().dimensions(),
+            (2, 2)
+        );
+        album_art.get_resized("cover", (2, 2)).unwrap();
+        assert_eq!(album_art.resized.borrow().len(), 1);
+
+        assert_eq!(
+            album_art.get_resized("cover", (3, 2)).unwrap().dimensions(),
+            (2, 2)
+        );
+        assert_eq!(album_art.resized.borrow().len(), 2);
+        assert!(album_art.get_resized("missing", (2, 2)).is_none());
+    }
+
+    #[test]
+    fn fit_test() {
+        assert_eq!(fit((300, 300), (12, 12)), (12, 12));
+        assert_eq!(fit((300, 300), (20, 12)), (12, 12));
+        assert_eq!(fit((600, 300), (20, 20)), (20, 10));
+        assert_eq!(fit((0, 300), (20, 20)), (0, 0));
+    }
+
+    #[test]
+    fn encode_sixel_test() {
+        let image = RgbImage::from_pixel(4, 2, image::Rgb([255, 0, 0]));
+        let sixel = encode_sixel(&image);
+
+        assert!(sixel.starts_with("\x1bPq\"1;1;4;2"));
+        // Red is colour 180 in the cube, both rows of the band are set
+        assert!(sixel.ends_with("#180!4B$-\x1b\\"));
+    }
+
+    #[test]
+    fn protocol_parse_test() {
+        assert_eq!(Protocol::parse("Kitty").unwrap(), Protocol::Kitty);
+        assert_eq!(Protocol::parse("halfblocks").unwrap(), Protocol::HalfBlocks);
+        assert!(Protocol::parse("ascii").is_err());
+    }
+}
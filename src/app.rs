@@ -1,11 +1,26 @@
-use super::{config::ClientConfig, user_config::UserConfig};
+use super::{
+    album_art::{self, AlbumArt},
+    config::{self, ClientConfig},
+    export::{self, Export},
+    history::{self, History, PlayTracker, Stats, StatsPeriod},
+    import::Import,
+    lyrics::{self, Lyrics, LyricsLoader, LyricsQuery},
+    playback::PlaybackController,
+    recommendations::{RecommendationSeed, RecommendationsBuilder, MAX_SEEDS},
+    scrobbler::Scrobbler,
+    spotify_api::SpotifyApi,
+    uri::{SpotifyUri, UriKind},
+    user_config::UserConfig,
+};
 use failure::{err_msg, format_err};
+use reqwest::Method;
 use rspotify::spotify::{
-    client::Spotify,
+    client::ApiError,
     model::{
         album::{FullAlbum, SavedAlbum, SimplifiedAlbum},
         artist::FullArtist,
-        audio::AudioAnalysis,
+        audio::{AudioAnalysis, AudioFeatures},
+        category::Category,
         context::FullPlayingContext,
         device::DevicePayload,
         offset::{for_position, Offset},
@@ -17,26 +32,32 @@ use rspotify::spotify::{
         track::{FullTrack, SavedTrack, SimplifiedTrack},
         user::PrivateUser,
     },
-    senum::{Country, RepeatState},
+    senum::{Country, RepeatState, TimeRange},
 };
+use serde::Deserialize;
 use serde_json::{map::Map, Value};
 use std::str::FromStr;
 use std::{
     cmp::{max, min},
     collections::HashSet,
+    fs,
+    rc::Rc,
     time::Instant,
 };
 use tui::layout::Rect;
 
 use clipboard::{ClipboardContext, ClipboardProvider};
 
-pub const LIBRARY_OPTIONS: [&str; 6] = [
+pub const LIBRARY_OPTIONS: [&str; 9] = [
     "Made For You",
+    "Browse",
     "Recently Played",
     "Liked Songs",
     "Albums",
     "Artists",
     "Podcasts",
+    "Top",
+    "Stats",
 ];
 
 const DEFAULT_ROUTE: Route = Route {
@@ -45,12 +66,21 @@ const DEFAULT_ROUTE: Route = Route {
     hovered_block: ActiveBlock::Library,
 };
 
+// The radio queues more recommendations once fewer than this many are left to play
+const RADIO_REFILL_THRESHOLD: usize = 2;
+
 #[derive(Clone)]
 pub struct ScrollableResultPages<T> {
     index: usize,
     pages: Vec<T>,
 }
 
+impl<T> Default for ScrollableResultPages<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T> ScrollableResultPages<T> {
     pub fn new() -> ScrollableResultPages<T> {
         ScrollableResultPages {
@@ -66,13 +96,6 @@ impl<T> ScrollableResultPages<T> {
         }
     }
 
-    pub fn get_mut_results(&mut self, at_index: Option<usize>) -> Option<&mut T> {
-        match at_index {
-            Some(index) => self.pages.get_mut(index),
-            None => self.pages.get_mut(self.index),
-        }
-    }
-
     pub fn add_pages(&mut self, new_pages: T) {
         self.pages.push(new_pages);
         // Whenever a new page is added, set the active index to the end of the vector
@@ -80,6 +103,26 @@ impl<T> ScrollableResultPages<T> {
     }
 }
 
+impl<T> ScrollableResultPages<Page<T>> {
+    // Moves to the next page when it was fetched before, otherwise returns the offset to fetch
+    // it from, or `None` when there are no more pages
+    pub fn next_page_offset(&mut self) -> Option<u32> {
+        if self.index + 1 < self.pages.len() {
+            self.index += 1;
+            return None;
+        }
+        self.get_results(None)
+            .filter(|page| page.next.is_some())
+            .map(|page| page.offset + page.limit)
+    }
+
+    pub fn previous_page(&mut self) {
+        if self.index > 0 {
+            self.index -= 1;
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct SpotifyResultAndSelectedIndex<T> {
     pub index: usize,
@@ -122,6 +165,11 @@ pub enum ArtistBlock {
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum ActiveBlock {
     Analysis,
+    TrackInfo,
+    Lyrics,
+    RecommendationsBuilder,
+    Browse,
+    CategoryPlaylists,
     PlayBar,
     AlbumTracks,
     AlbumList,
@@ -137,9 +185,13 @@ pub enum ActiveBlock {
     RecentlyPlayed,
     SearchResultBlock,
     SelectDevice,
+    SelectProfile,
     TrackTable,
     MadeForYou,
     Artists,
+    Stats,
+    Top,
+    Import,
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -153,11 +205,19 @@ pub enum RouteId {
     RecentlyPlayed,
     Search,
     SelectedDevice,
+    SelectProfile,
     TrackTable,
     MadeForYou,
     Artists,
     Podcasts,
     Recommendations,
+    RecommendationsBuilder,
+    Browse,
+    CategoryPlaylists,
+    Lyrics,
+    Stats,
+    Top,
+    Import,
 }
 
 #[derive(Debug)]
@@ -176,6 +236,142 @@ pub enum TrackTableContext {
     SavedTracks,
     RecommendedTracks,
     MadeForYou,
+    BrowsePlaylist,
+}
+
+#[derive(Clone, PartialEq, Debug, Copy)]
+pub enum AnalysisViewMode {
+    Pitches,
+    Timbre,
+    Loudness,
+    Sections,
+    BeatPulse,
+}
+
+pub const ANALYSIS_VIEW_MODES: [AnalysisViewMode; 5] = [
+    AnalysisViewMode::Pitches,
+    AnalysisViewMode::Timbre,
+    AnalysisViewMode::Loudness,
+    AnalysisViewMode::Sections,
+    AnalysisViewMode::BeatPulse,
+];
+
+impl AnalysisViewMode {
+    pub fn title(self) -> &'static str {
+        match self {
+            AnalysisViewMode::Pitches => "Pitches",
+            AnalysisViewMode::Timbre => "Timbre",
+            AnalysisViewMode::Loudness => "Loudness",
+            AnalysisViewMode::Sections => "Sections",
+            AnalysisViewMode::BeatPulse => "Beat",
+        }
+    }
+
+    fn index(self) -> usize {
+        ANALYSIS_VIEW_MODES
+            .iter()
+            .position(|mode| *mode == self)
+            .unwrap_or(0)
+    }
+
+    pub fn next(self) -> AnalysisViewMode {
+        ANALYSIS_VIEW_MODES[(self.index() + 1) % ANALYSIS_VIEW_MODES.len()]
+    }
+
+    pub fn previous(self) -> AnalysisViewMode {
+        ANALYSIS_VIEW_MODES
+            [(self.index() + ANALYSIS_VIEW_MODES.len() - 1) % ANALYSIS_VIEW_MODES.len()]
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Copy)]
+pub enum BrowseSection {
+    Categories,
+    NewReleases,
+    FeaturedPlaylists,
+}
+
+impl BrowseSection {
+    pub fn title(self) -> &'static str {
+        match self {
+            BrowseSection::Categories => "Categories",
+            BrowseSection::NewReleases => "New Releases",
+            BrowseSection::FeaturedPlaylists => "Featured Playlists",
+        }
+    }
+
+    pub fn next(self) -> BrowseSection {
+        match self {
+            BrowseSection::Categories => BrowseSection::NewReleases,
+            BrowseSection::NewReleases => BrowseSection::FeaturedPlaylists,
+            BrowseSection::FeaturedPlaylists => BrowseSection::Categories,
+        }
+    }
+}
+
+pub struct Browse {
+    pub section: BrowseSection,
+    pub selected_index: usize,
+    pub categories: ScrollableResultPages<Page<Category>>,
+    pub new_releases: ScrollableResultPages<Page<SimplifiedAlbum>>,
+    pub featured_playlists: ScrollableResultPages<Page<SimplifiedPlaylist>>,
+    // Spotify's headline for the featured playlists, e.g. "Monday morning music"
+    pub featured_message: Option<String>,
+    pub selected_category: Option<Category>,
+    pub category_playlists: ScrollableResultPages<Page<SimplifiedPlaylist>>,
+    pub category_playlist_index: usize,
+    // The featured or category playlist shown in the track table
+    pub selected_playlist: Option<SimplifiedPlaylist>,
+}
+
+// Spotify's windows for the top artists and tracks, roughly 4 weeks, 6 months and several years
+#[derive(Clone, PartialEq, Debug, Copy)]
+pub enum TopTimeRange {
+    ShortTerm,
+    MediumTerm,
+    LongTerm,
+}
+
+impl TopTimeRange {
+    pub fn title(self) -> &'static str {
+        match self {
+            TopTimeRange::ShortTerm => "Last 4 weeks",
+            TopTimeRange::MediumTerm => "Last 6 months",
+            TopTimeRange::LongTerm => "All time",
+        }
+    }
+
+    pub fn next(self) -> TopTimeRange {
+        match self {
+            TopTimeRange::ShortTerm => TopTimeRange::MediumTerm,
+            TopTimeRange::MediumTerm => TopTimeRange::LongTerm,
+            TopTimeRange::LongTerm => TopTimeRange::ShortTerm,
+        }
+    }
+
+    fn time_range(self) -> TimeRange {
+        match self {
+            TopTimeRange::ShortTerm => TimeRange::ShortTerm,
+            TopTimeRange::MediumTerm => TimeRange::MediumTerm,
+            TopTimeRange::LongTerm => TimeRange::LongTerm,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Copy)]
+pub enum TopSection {
+    Artists,
+    Tracks,
+}
+
+pub struct Top {
+    pub time_range: TopTimeRange,
+    // The list that has the focus, artists on the left and tracks on the right
+    pub section: TopSection,
+    pub artists: Vec<FullArtist>,
+    pub tracks: Vec<FullTrack>,
+    pub selected_artist_index: usize,
+    pub selected_track_index: usize,
 }
 
 #[derive(Clone, PartialEq, Debug, Copy)]
@@ -188,6 +384,7 @@ pub enum AlbumTableContext {
 pub enum RecommendationsContext {
     Artist,
     Song,
+    Builder,
 }
 
 pub struct SearchResult {
@@ -223,6 +420,15 @@ pub struct SelectedFullAlbum {
     pub selected_index: usize,
 }
 
+#[derive(Clone)]
+pub struct TrackInfo {
+    pub track: FullTrack,
+    // Local files and podcast episodes have no audio features
+    pub audio_features: Option<AudioFeatures>,
+    // Set when the track is one of the current recommendations
+    pub recommended_from: Option<String>,
+}
+
 #[derive(Clone)]
 pub struct Artist {
     pub artist_name: String,
@@ -236,10 +442,15 @@ pub struct Artist {
     pub artist_selected_block: ArtistBlock,
 }
 
+/// Everything on screen and everything the handlers need, changed by key presses and ticks
 pub struct App {
     instant_since_last_current_playback_poll: Instant,
     navigation_stack: Vec<Route>,
+    pub album_art: AlbumArt,
     pub audio_analysis: Option<AudioAnalysis>,
+    // The track the analysis belongs to, used to fetch it again when the track changes
+    pub audio_analysis_uri: Option<String>,
+    pub analysis_view_mode: AnalysisViewMode,
     pub home_scroll: u16,
     pub client_config: ClientConfig,
     pub user_config: UserConfig,
@@ -248,6 +459,8 @@ pub struct App {
     pub album_table_context: AlbumTableContext,
     pub saved_album_tracks_index: usize,
     pub api_error: String,
+    // A note shown in the search box until the next key press, e.g. where an export was saved
+    pub status_message: Option<String>,
     pub current_playback_context: Option<FullPlayingContext>,
     pub devices: Option<DevicePayload>,
     // Inputs:
@@ -272,15 +485,30 @@ pub struct App {
     pub recommended_tracks: Vec<FullTrack>,
     pub recommendations_seed: String,
     pub recommendations_context: Option<RecommendationsContext>,
+    pub recommendations_builder: RecommendationsBuilder,
+    pub browse: Browse,
+    pub radio: bool,
+    // Uris played or queued by the radio this session, so it does not repeat itself
+    pub radio_heard_uris: HashSet<String>,
+    // The track that was playing when a refill found nothing new. The radio waits for the next
+    // one instead of asking for recommendations on every poll
+    radio_stalled_uri: Option<String>,
     pub search_results: SearchResult,
     pub selected_album_simplified: Option<SelectedAlbum>,
     pub selected_album_full: Option<SelectedFullAlbum>,
     pub selected_device_index: Option<usize>,
+    // Set when the device_id saved in client.yml could not be found, so we can prompt for a new
+    // device and reconnect once it shows up again
+    pub missing_device_id: Option<String>,
+    pub profiles: Vec<String>,
+    pub selected_profile_index: usize,
+    // The main loop rebuilds the Spotify client for this profile on the next tick
+    pub pending_profile: Option<String>,
     pub selected_playlist_index: Option<usize>,
     pub size: Rect,
     pub small_search_limit: u32,
     pub song_progress_ms: u128,
-    pub spotify: Option<Spotify>,
+    pub spotify: Option<Rc<dyn SpotifyApi>>,
     pub track_table: TrackTable,
     pub user: Option<PrivateUser>,
     pub album_list_index: usize,
@@ -291,12 +519,37 @@ pub struct App {
     pub help_menu_page: u32,
     pub help_menu_max_lines: u32,
     pub help_menu_offset: u32,
+    pub lyrics: Option<Lyrics>,
+    // The track the lyrics were looked up for, whether or not any were found
+    pub lyrics_track_id: Option<String>,
+    pub lyrics_loader: LyricsLoader,
+    pub lyrics_scroll: u16,
+    pub track_info: Option<TrackInfo>,
+    // `None` when the listening history is turned off or could not be opened
+    pub history: Option<History>,
+    pub play_tracker: PlayTracker,
+    // `None` unless a scrobbler is set up in client.yml
+    pub scrobbler: Option<Scrobbler>,
+    pub stats: Option<Stats>,
+    pub stats_period: StatsPeriod,
+    pub top: Top,
+    // The playlist `spt import` is creating, while its uncertain matches are reviewed
+    pub import: Option<Import>,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl App {
     pub fn new() -> App {
         App {
+            album_art: Default::default(),
             audio_analysis: None,
+            audio_analysis_uri: None,
+            analysis_view_mode: AnalysisViewMode::Pitches,
             album_table_context: AlbumTableContext::Full,
             album_list_index: 0,
             made_for_you_index: 0,
@@ -323,6 +576,7 @@ impl App {
             large_search_limit: 20,
             small_search_limit: 4,
             api_error: String::new(),
+            status_message: None,
             current_playback_context: None,
             devices: None,
             input: vec![],
@@ -336,6 +590,22 @@ impl App {
             recommended_tracks: vec![],
             recommendations_context: None,
             recommendations_seed: "".to_string(),
+            recommendations_builder: Default::default(),
+            browse: Browse {
+                section: BrowseSection::Categories,
+                selected_index: 0,
+                categories: ScrollableResultPages::new(),
+                new_releases: ScrollableResultPages::new(),
+                featured_playlists: ScrollableResultPages::new(),
+                featured_message: None,
+                selected_category: None,
+                category_playlists: ScrollableResultPages::new(),
+                category_playlist_index: 0,
+                selected_playlist: None,
+            },
+            radio: false,
+            radio_heard_uris: HashSet::new(),
+            radio_stalled_uri: None,
             search_results: SearchResult {
                 hovered_block: SearchResultBlock::SongSearch,
                 selected_block: SearchResultBlock::Empty,
@@ -350,6 +620,10 @@ impl App {
             },
             song_progress_ms: 0,
             selected_device_index: None,
+            missing_device_id: None,
+            profiles: vec![],
+            selected_profile_index: 0,
+            pending_profile: None,
             selected_playlist_index: None,
             spotify: None,
             track_table: Default::default(),
@@ -365,6 +639,25 @@ impl App {
             help_menu_page: 0,
             help_menu_max_lines: 0,
             help_menu_offset: 0,
+            lyrics: None,
+            lyrics_track_id: None,
+            lyrics_loader: Default::default(),
+            lyrics_scroll: 0,
+            track_info: None,
+            history: None,
+            play_tracker: Default::default(),
+            scrobbler: None,
+            stats: None,
+            stats_period: StatsPeriod::Week,
+            import: None,
+            top: Top {
+                time_range: TopTimeRange::ShortTerm,
+                section: TopSection::Artists,
+                artists: vec![],
+                tracks: vec![],
+                selected_artist_index: 0,
+                selected_track_index: 0,
+            },
         }
     }
 
@@ -386,29 +679,195 @@ impl App {
             if let Ok(result) = spotify.device() {
                 self.push_navigation_stack(RouteId::SelectedDevice, ActiveBlock::SelectDevice);
                 if !result.devices.is_empty() {
+                    // Start on the device we are playing on, falling back to the first one
+                    let selected_index = result
+                        .devices
+                        .iter()
+                        .position(|device| {
+                            Some(&device.id) == self.client_config.device_id.as_ref()
+                        })
+                        .or_else(|| result.devices.iter().position(|device| device.is_active))
+                        .unwrap_or(0);
                     self.devices = Some(result);
-                    // Select the first device in the list
-                    self.selected_device_index = Some(0);
+                    self.selected_device_index = Some(selected_index);
+                }
+            }
+        }
+    }
+
+    pub fn handle_get_profiles(&mut self) {
+        match config::get_profiles() {
+            Ok(profiles) => {
+                let current_profile = self.client_config.get_profile_name();
+                self.selected_profile_index = profiles
+                    .iter()
+                    .position(|profile| profile == current_profile)
+                    .unwrap_or(0);
+                self.profiles = profiles;
+                self.push_navigation_stack(RouteId::SelectProfile, ActiveBlock::SelectProfile);
+            }
+            Err(e) => self.handle_error(e),
+        }
+    }
+
+    pub fn select_device(&mut self, device_id: String) {
+        // Only transfer when something is playing, otherwise the next playback starts there
+        let is_playing = self
+            .current_playback_context
+            .as_ref()
+            .is_some_and(|context| context.is_playing);
+        let should_transfer = self
+            .current_playback_context
+            .as_ref()
+            .is_some_and(|context| context.device.id != device_id);
+
+        if should_transfer {
+            if let Err(e) = self.transfer_playback(&device_id, is_playing) {
+                self.handle_error(e);
+                return;
+            }
+        }
+
+        match self.client_config.set_device_id(device_id) {
+            Ok(()) => {
+                self.missing_device_id = None;
+                self.pop_navigation_stack();
+                if should_transfer {
+                    self.get_current_playback();
                 }
             }
+            Err(e) => {
+                self.handle_error(e);
+            }
+        };
+    }
+
+    fn transfer_playback(&self, device_id: &str, play: bool) -> Result<(), failure::Error> {
+        match self.playback() {
+            Some(playback) => playback.transfer(device_id, play),
+            None => Err(err_msg("Spotify is not ready to be used")),
+        }
+    }
+
+    /// Playback commands for the device saved in client.yml
+    pub fn playback(&self) -> Option<PlaybackController> {
+        let spotify = self.spotify.clone()?;
+        Some(PlaybackController::new(
+            spotify,
+            self.client_config.device_id.clone(),
+        ))
+    }
+
+    // When the saved device has gone away, prompt for a new one rather than failing silently
+    fn handle_missing_device(&mut self) -> bool {
+        let device_id = match &self.client_config.device_id {
+            Some(device_id) => device_id.clone(),
+            None => return false,
+        };
+        let devices = match &self.spotify {
+            Some(spotify) => match spotify.device() {
+                Ok(devices) => devices,
+                Err(_) => return false,
+            },
+            None => return false,
+        };
+
+        if devices.devices.iter().any(|device| device.id == device_id) {
+            return false;
+        }
+
+        self.missing_device_id = Some(device_id);
+        self.handle_get_devices();
+        true
+    }
+
+    fn reconnect_missing_device(&mut self) {
+        let device_id = match &self.missing_device_id {
+            Some(device_id) => device_id.clone(),
+            None => return,
+        };
+        let has_reappeared = match &self.spotify {
+            Some(spotify) => spotify
+                .device()
+                .is_ok_and(|devices| devices.devices.iter().any(|device| device.id == device_id)),
+            None => false,
+        };
+
+        if has_reappeared && self.transfer_playback(&device_id, false).is_ok() {
+            self.missing_device_id = None;
+            if self.get_current_route().active_block == ActiveBlock::SelectDevice {
+                self.pop_navigation_stack();
+            }
+            self.get_current_playback();
         }
     }
 
     pub fn get_current_playback(&mut self) {
-        if let Some(spotify) = &self.spotify {
-            let context = spotify.current_playback(None);
-            if let Ok(ctx) = context {
-                if let Some(c) = ctx {
-                    self.current_playback_context = Some(c.clone());
-                    self.instant_since_last_current_playback_poll = Instant::now();
-
-                    if let Some(track) = c.item {
-                        if let Some(track_id) = track.id {
-                            self.current_user_saved_tracks_contains(vec![track_id]);
-                        }
+        let context = match &self.spotify {
+            Some(spotify) => spotify.current_playback(None),
+            None => return,
+        };
+        if let Ok(ctx) = context {
+            self.record_play(ctx.as_ref());
+            if let Some(c) = ctx {
+                self.current_playback_context = Some(c.clone());
+                self.instant_since_last_current_playback_poll = Instant::now();
+
+                if let Some(track) = c.item {
+                    if let Some(track_id) = track.id {
+                        self.current_user_saved_tracks_contains(vec![track_id]);
                     }
                 }
-            };
+            }
+        };
+    }
+
+    // Saves the play that just ended, if any, to the listening history
+    fn record_play(&mut self, context: Option<&FullPlayingContext>) {
+        let play = self
+            .play_tracker
+            .observe(context, Instant::now(), history::unix_now());
+        let (history, play) = match (&self.history, play) {
+            (Some(history), Some(play)) => (history, play),
+            _ => return,
+        };
+        match history.record(&play) {
+            Ok(()) => {
+                if self.get_current_route().id == RouteId::Stats {
+                    self.load_stats();
+                }
+            }
+            Err(e) => self.handle_error(e),
+        }
+    }
+
+    // Scrobbles the play in progress once it is due, and retries what could not be sent
+    fn update_scrobbler(&mut self) {
+        if let Some(scrobbler) = &mut self.scrobbler {
+            if let Err(e) = scrobbler.update(self.play_tracker.current(), Instant::now()) {
+                self.handle_error(e);
+            }
+        }
+    }
+
+    pub fn get_stats(&mut self) {
+        self.load_stats();
+        self.push_navigation_stack(RouteId::Stats, ActiveBlock::Stats);
+    }
+
+    pub fn next_stats_period(&mut self) {
+        self.stats_period = self.stats_period.next();
+        self.load_stats();
+    }
+
+    fn load_stats(&mut self) {
+        let stats = match &self.history {
+            Some(history) => history.stats(self.stats_period, history::unix_now(), 10),
+            None => return,
+        };
+        match stats {
+            Ok(stats) => self.stats = Some(stats),
+            Err(e) => self.handle_error(e),
         }
     }
 
@@ -447,6 +906,8 @@ impl App {
 
         if elapsed >= poll_interval_ms {
             self.get_current_playback();
+            self.update_radio();
+            self.reconnect_missing_device();
         }
     }
 
@@ -472,11 +933,66 @@ impl App {
                 }
             }
         }
+        self.update_scrobbler();
+        self.load_album_art();
+        match self.get_current_route().id {
+            RouteId::Lyrics => self.load_lyrics(),
+            RouteId::Analysis => self.update_audio_analysis(),
+            _ => {}
+        }
+    }
+
+    pub fn get_playing_album_art_url(&self) -> Option<&str> {
+        self.current_playback_context
+            .as_ref()
+            .and_then(|context| context.item.as_ref())
+            .and_then(|track| album_art::select_image_url(&track.album.images))
+    }
+
+    pub fn get_selected_album_art_url(&self) -> Option<&str> {
+        match self.album_table_context {
+            AlbumTableContext::Simplified => {
+                self.selected_album_simplified
+                    .as_ref()
+                    .and_then(|selected_album| {
+                        album_art::select_image_url(&selected_album.album.images)
+                    })
+            }
+            AlbumTableContext::Full => {
+                self.selected_album_full
+                    .as_ref()
+                    .and_then(|selected_album| {
+                        album_art::select_image_url(&selected_album.album.images)
+                    })
+            }
+        }
+    }
+
+    // Covers are downloaded (or read from the disk cache) once and then kept in memory
+    fn load_album_art(&mut self) {
+        if !self.user_config.behavior.show_album_art {
+            return;
+        }
+        self.album_art.receive();
+
+        let mut urls = vec![];
+        if let Some(url) = self.get_playing_album_art_url() {
+            urls.push(url.to_string());
+        }
+        if self.get_current_route().id == RouteId::AlbumTracks {
+            if let Some(url) = self.get_selected_album_art_url() {
+                urls.push(url.to_string());
+            }
+        }
+
+        for url in urls {
+            self.album_art.load(&url);
+        }
     }
 
     fn seek(&mut self, position_ms: u32) {
-        if let (Some(spotify), Some(device_id)) = (&self.spotify, &self.client_config.device_id) {
-            match spotify.seek_track(position_ms, Some(device_id.to_string())) {
+        if let (Some(playback), Some(_)) = (self.playback(), &self.client_config.device_id) {
+            match playback.seek(position_ms) {
                 Ok(()) => {
                     self.get_current_playback();
                 }
@@ -514,8 +1030,8 @@ impl App {
     }
 
     pub fn pause_playback(&mut self) {
-        if let (Some(spotify), Some(device_id)) = (&self.spotify, &self.client_config.device_id) {
-            match spotify.pause_playback(Some(device_id.to_string())) {
+        if let (Some(playback), Some(_)) = (self.playback(), &self.client_config.device_id) {
+            match playback.pause() {
                 Ok(()) => {
                     self.get_current_playback();
                 }
@@ -538,12 +1054,12 @@ impl App {
             let empty_payload: Map<String, Value> = Map::new();
 
             match spotify.recommendations(
-                seed_artists,            // artists
-                None,                    // genres
-                seed_tracks,             // tracks
-                self.large_search_limit, // adjust playlist to screen size
-                user_country,            // country
-                &empty_payload,          // payload
+                seed_artists,                  // artists
+                None,                          // genres
+                seed_tracks,                   // tracks
+                Some(self.large_search_limit), // adjust playlist to screen size
+                user_country,                  // country
+                &empty_payload,                // payload
             ) {
                 Ok(result) => {
                     if let Some(mut recommended_tracks) = self.extract_recommended_tracks(&result) {
@@ -571,6 +1087,233 @@ impl App {
         }
     }
 
+    pub fn open_recommendations_builder(&mut self) {
+        if self.recommendations_builder.genres.is_empty() {
+            self.get_genre_seeds();
+        }
+        self.push_navigation_stack(
+            RouteId::RecommendationsBuilder,
+            ActiveBlock::RecommendationsBuilder,
+        );
+    }
+
+    // Calls endpoints that rspotify does not cover, using the client's access token
+    fn web_api_call(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&Value>,
+    ) -> Result<String, failure::Error> {
+        match &self.spotify {
+            Some(spotify) => spotify.web_api_call(method, path, body),
+            None => Err(err_msg("Not authenticated with Spotify")),
+        }
+    }
+
+    fn get_genre_seeds(&mut self) {
+        #[derive(Deserialize)]
+        struct GenreSeeds {
+            genres: Vec<String>,
+        }
+
+        let result = self
+            .web_api_call(Method::GET, "recommendations/available-genre-seeds", None)
+            .and_then(|text| {
+                serde_json::from_str::<GenreSeeds>(&text).map_err(failure::Error::from)
+            });
+
+        match result {
+            Ok(genre_seeds) => self.recommendations_builder.genres = genre_seeds.genres,
+            Err(e) => self.handle_error(e),
+        }
+    }
+
+    pub fn add_recommendation_seed(&mut self, seed: RecommendationSeed) {
+        if self.recommendations_builder.is_full() {
+            self.handle_error(format_err!(
+                "Recommendations can use at most {} seeds, remove one in the builder first",
+                MAX_SEEDS
+            ));
+        } else {
+            self.recommendations_builder.add_seed(seed);
+        }
+    }
+
+    // Shows the tracks in the recommendations table without starting playback
+    pub fn get_builder_recommendations(&mut self) {
+        if self.recommendations_builder.seeds.is_empty() {
+            self.handle_error(err_msg(
+                "Add at least one artist, track or genre seed to get recommendations",
+            ));
+            return;
+        }
+
+        if let (Some(spotify), Some(user)) = (&self.spotify, &self.user.to_owned()) {
+            let user_country =
+                Country::from_str(&user.country.to_owned().unwrap_or_else(|| "".to_string())).ok();
+            let builder = &self.recommendations_builder;
+
+            match spotify.recommendations(
+                builder.seed_artists(),
+                builder.seed_genres(),
+                builder.seed_tracks(),
+                Some(self.large_search_limit),
+                user_country,
+                &builder.payload(),
+            ) {
+                Ok(result) => {
+                    if let Some(recommended_tracks) = self.extract_recommended_tracks(&result) {
+                        self.recommendations_context = Some(RecommendationsContext::Builder);
+                        self.recommendations_seed = self.recommendations_builder.seed_names();
+                        self.recommended_tracks = recommended_tracks.clone();
+                        self.set_tracks_to_table(recommended_tracks);
+                        self.track_table.context = Some(TrackTableContext::RecommendedTracks);
+                        self.push_navigation_stack(
+                            RouteId::Recommendations,
+                            ActiveBlock::TrackTable,
+                        );
+                    }
+                }
+                Err(e) => {
+                    self.handle_error(e);
+                }
+            }
+        }
+    }
+
+    pub fn save_recommendations_as_playlist(&mut self) {
+        if let (Some(spotify), Some(user)) = (&self.spotify, &self.user) {
+            let name = match &self.recommendations_context {
+                Some(_) => format!("Recommendations based on {}", self.recommendations_seed),
+                None => "Recommendations".to_string(),
+            };
+            let track_ids = self
+                .recommended_tracks
+                .iter()
+                .filter_map(|track| track.id.clone())
+                .collect::<Vec<String>>();
+
+            let result = spotify
+                .user_playlist_create(&user.id, &name, Some(false), None)
+                .and_then(|playlist| {
+                    spotify.user_playlist_add_tracks(&user.id, &playlist.id, &track_ids, None)
+                })
+                .and_then(|_| spotify.current_user_playlists(Some(self.large_search_limit), None));
+
+            match result {
+                Ok(playlists) => self.playlists = Some(playlists),
+                Err(e) => self.handle_error(e),
+            }
+        }
+    }
+
+    pub fn toggle_radio(&mut self) {
+        self.radio = !self.radio;
+        if self.radio {
+            self.radio_heard_uris.extend(
+                self.recommended_tracks
+                    .iter()
+                    .map(|track| track.uri.clone()),
+            );
+        }
+    }
+
+    // Keeps recommendations playback going by queueing more tracks before the list runs out
+    fn update_radio(&mut self) {
+        if !self.radio {
+            return;
+        }
+
+        let current_uri = match self
+            .current_playback_context
+            .as_ref()
+            .and_then(|context| context.item.as_ref())
+        {
+            Some(track) => track.uri.clone(),
+            None => return,
+        };
+        self.radio_heard_uris.insert(current_uri.clone());
+
+        // Only refill while one of the recommendations is playing
+        let position = match self
+            .recommended_tracks
+            .iter()
+            .position(|track| track.uri == current_uri)
+        {
+            Some(position) => position,
+            None => return,
+        };
+
+        if self.recommended_tracks.len() - position - 1 < RADIO_REFILL_THRESHOLD
+            && self.radio_stalled_uri.as_ref() != Some(&current_uri)
+        {
+            match self.refill_radio(&current_uri) {
+                Ok(0) => self.radio_stalled_uri = Some(current_uri),
+                Ok(_) => self.radio_stalled_uri = None,
+                Err(e) => {
+                    // Turn the radio off so the error is not shown again on every poll
+                    self.radio = false;
+                    self.handle_error(e);
+                }
+            }
+        }
+    }
+
+    // Queues recommendations that weren't heard yet, returning how many
+    fn refill_radio(&mut self, current_uri: &str) -> Result<usize, failure::Error> {
+        let (spotify, user) = match (self.spotify.clone(), &self.user) {
+            (Some(spotify), Some(user)) => (spotify, user),
+            _ => return Ok(0),
+        };
+
+        let mut seed_tracks: Vec<String> = vec![];
+        for item in spotify
+            .current_user_recently_played(Some(MAX_SEEDS as u32))?
+            .items
+        {
+            if let Some(id) = item.track.id {
+                if !seed_tracks.contains(&id) {
+                    seed_tracks.push(id);
+                }
+            }
+        }
+        if seed_tracks.is_empty() {
+            seed_tracks.push(current_uri.to_string());
+        }
+
+        let user_country =
+            Country::from_str(&user.country.to_owned().unwrap_or_else(|| "".to_string())).ok();
+        let recommendations = spotify.recommendations(
+            None,
+            None,
+            Some(seed_tracks),
+            Some(self.large_search_limit),
+            user_country,
+            &Map::new(),
+        )?;
+
+        let new_tracks = self
+            .extract_recommended_tracks(&recommendations)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|track| !self.radio_heard_uris.contains(&track.uri))
+            .collect::<Vec<FullTrack>>();
+
+        let queued = new_tracks.len();
+        for track in new_tracks {
+            spotify.add_item_to_queue(&track.uri, self.client_config.device_id.clone())?;
+
+            self.radio_heard_uris.insert(track.uri.clone());
+            // Keep the table in step so playing from it still lines up with the queue
+            if self.track_table.context == Some(TrackTableContext::RecommendedTracks) {
+                self.track_table.tracks.push(track.clone());
+            }
+            self.recommended_tracks.push(track);
+        }
+
+        Ok(queued)
+    }
+
     pub fn get_recommendations_for_trackid(&mut self, id: &str) {
         if let Some(track) = self.get_fulltrack_from_id(id) {
             let track_id_list: Option<Vec<String>> = match &track.id {
@@ -582,12 +1325,12 @@ impl App {
     }
 
     fn change_volume(&mut self, volume_percent: u8) {
-        if let (Some(spotify), Some(device_id), Some(context)) = (
-            &self.spotify,
+        if let (Some(playback), Some(_), Some(context)) = (
+            self.playback(),
             &self.client_config.device_id,
             &mut self.current_playback_context,
         ) {
-            match spotify.volume(volume_percent, Some(device_id.to_string())) {
+            match playback.set_volume(volume_percent) {
                 Ok(()) => {
                     context.device.volume_percent = volume_percent.into();
                 }
@@ -643,8 +1386,8 @@ impl App {
     }
 
     pub fn next_track(&mut self) {
-        if let (Some(spotify), Some(device_id)) = (&self.spotify, &self.client_config.device_id) {
-            match spotify.next_track(Some(device_id.to_string())) {
+        if let (Some(playback), Some(_)) = (self.playback(), &self.client_config.device_id) {
+            match playback.next() {
                 Ok(()) => {
                     self.get_current_playback();
                 }
@@ -656,11 +1399,11 @@ impl App {
     }
 
     pub fn previous_track(&mut self) {
-        if let (Some(spotify), Some(device_id)) = (&self.spotify, &self.client_config.device_id) {
+        if let (Some(playback), Some(_)) = (self.playback(), &self.client_config.device_id) {
             if self.song_progress_ms >= 3_000 {
                 self.seek(0);
             } else {
-                match spotify.previous_track(Some(device_id.to_string())) {
+                match playback.previous() {
                     Ok(()) => {
                         self.get_current_playback();
                     }
@@ -699,21 +1442,12 @@ impl App {
             (None, None)
         };
 
-        let offset = offset.and_then(|o| for_position(o as u32));
-
-        let result = match &self.client_config.device_id {
-            Some(device_id) => match &self.spotify {
-                Some(spotify) => spotify.start_playback(
-                    Some(device_id.to_string()),
-                    context_uri.clone(),
-                    uris.clone(),
-                    offset.clone(),
-                    None,
-                ),
-                None => Err(err_msg("Spotify is not ready to be used".to_string())),
-            },
-            None => Err(err_msg("No device_id selected")),
+        let result = match (&self.client_config.device_id, self.playback()) {
+            (Some(_), Some(playback)) => playback.play(context_uri.clone(), uris.clone(), offset),
+            (Some(_), None) => Err(err_msg("Spotify is not ready to be used".to_string())),
+            (None, _) => Err(err_msg("No device_id selected")),
         };
+        let offset = offset.and_then(|o| for_position(o as u32));
 
         match result {
             Ok(()) => {
@@ -726,7 +1460,9 @@ impl App {
                 }
             }
             Err(e) => {
-                self.handle_error(e);
+                if !self.handle_missing_device() {
+                    self.handle_error(e);
+                }
             }
         }
     }
@@ -832,8 +1568,7 @@ impl App {
             ..
         }) = &self.current_playback_context
         {
-            if let Err(e) = clipboard.set_contents(format!("https://open.spotify.com/track/{}", id))
-            {
+            if let Err(e) = clipboard.set_contents(SpotifyUri::new(UriKind::Track, id).url()) {
                 self.handle_error(format_err!("failed to set clipboard content: {}", e));
             }
         }
@@ -854,8 +1589,7 @@ impl App {
             ..
         }) = &self.current_playback_context
         {
-            if let Err(e) = clipboard.set_contents(format!("https://open.spotify.com/album/{}", id))
-            {
+            if let Err(e) = clipboard.set_contents(SpotifyUri::new(UriKind::Album, id).url()) {
                 self.handle_error(format_err!("failed to set clipboard content: {}", e));
             }
         }
@@ -932,7 +1666,7 @@ impl App {
 
     pub fn get_current_user_saved_tracks(&mut self, offset: Option<u32>) {
         if let Some(spotify) = &self.spotify {
-            match spotify.current_user_saved_tracks(self.large_search_limit, offset) {
+            match spotify.current_user_saved_tracks(Some(self.large_search_limit), offset) {
                 Ok(saved_tracks) => {
                     self.set_saved_tracks_to_table(&saved_tracks);
 
@@ -980,7 +1714,8 @@ impl App {
     pub fn get_album_tracks(&mut self, album: SimplifiedAlbum) {
         if let Some(album_id) = &album.id {
             if let Some(spotify) = &self.spotify {
-                match spotify.album_track(&album_id.clone(), self.large_search_limit, 0) {
+                match spotify.album_track(&album_id.clone(), Some(self.large_search_limit), Some(0))
+                {
                     Ok(tracks) => {
                         self.selected_album_simplified = Some(SelectedAlbum {
                             album,
@@ -1040,9 +1775,10 @@ impl App {
     }
 
     pub fn shuffle(&mut self) {
-        if let (Some(spotify), Some(context)) = (&self.spotify, &mut self.current_playback_context)
+        if let (Some(playback), Some(context)) =
+            (self.playback(), &mut self.current_playback_context)
         {
-            match spotify.shuffle(!context.shuffle_state, self.client_config.device_id.clone()) {
+            match playback.set_shuffle(!context.shuffle_state) {
                 Ok(()) => {
                     // Update the UI eagerly (otherwise the UI will wait until the next 5 second interval
                     // due to polling playback context)
@@ -1056,14 +1792,15 @@ impl App {
     }
 
     pub fn repeat(&mut self) {
-        if let (Some(spotify), Some(context)) = (&self.spotify, &mut self.current_playback_context)
+        if let (Some(playback), Some(context)) =
+            (self.playback(), &mut self.current_playback_context)
         {
             let next_repeat_state = match context.repeat_state {
                 RepeatState::Off => RepeatState::Context,
                 RepeatState::Context => RepeatState::Track,
                 RepeatState::Track => RepeatState::Off,
             };
-            match spotify.repeat(next_repeat_state, self.client_config.device_id.clone()) {
+            match playback.set_repeat(next_repeat_state) {
                 Ok(()) => {
                     // Update the UI eagerly (otherwise the UI will wait until the next 5 second interval
                     // due to polling playback context)
@@ -1118,7 +1855,7 @@ impl App {
 
     pub fn get_artists(&mut self, offset: Option<String>) {
         if let Some(spotify) = &self.spotify {
-            match spotify.current_user_followed_artists(self.large_search_limit, offset) {
+            match spotify.current_user_followed_artists(Some(self.large_search_limit), offset) {
                 Ok(saved_artists) => {
                     self.artists = saved_artists.artists.items.to_owned();
                     self.library.saved_artists.add_pages(saved_artists.artists);
@@ -1132,7 +1869,7 @@ impl App {
 
     pub fn get_current_user_saved_albums(&mut self, offset: Option<u32>) {
         if let Some(spotify) = &self.spotify {
-            match spotify.current_user_saved_albums(self.large_search_limit, offset) {
+            match spotify.current_user_saved_albums(Some(self.large_search_limit), offset) {
                 Ok(saved_albums) => {
                     // not to show a blank page
                     if !saved_albums.items.is_empty() {
@@ -1248,6 +1985,81 @@ impl App {
         }
     }
 
+    pub fn export_selected_playlist(&mut self) {
+        if let (Some(playlists), Some(selected_index)) =
+            (&self.playlists, self.selected_playlist_index)
+        {
+            if let Some(playlist) = playlists.items.get(selected_index) {
+                let (id, name) = (playlist.id.clone(), playlist.name.clone());
+                self.save_export(|spotify| export::playlist(spotify, &id, &name));
+            }
+        }
+    }
+
+    pub fn export_saved_tracks(&mut self) {
+        self.save_export(export::saved_tracks);
+    }
+
+    pub fn export_saved_albums(&mut self) {
+        self.save_export(export::saved_albums);
+    }
+
+    // Writes the export to `export_dir` in `export_format`, as set in config.yml
+    fn save_export(
+        &mut self,
+        fetch: impl FnOnce(&dyn SpotifyApi) -> Result<Export, failure::Error>,
+    ) {
+        let spotify = match &self.spotify {
+            Some(spotify) => spotify.clone(),
+            None => return,
+        };
+        let format = self.user_config.behavior.export_format;
+        let dir = match &self.user_config.behavior.export_dir {
+            Some(dir) => lyrics::expand_home(dir),
+            None => dirs::download_dir()
+                .or_else(dirs::home_dir)
+                .unwrap_or_default(),
+        };
+
+        let result = fetch(spotify.as_ref()).and_then(|export| {
+            let path = dir.join(export.file_name(format));
+            fs::create_dir_all(&dir)?;
+            fs::write(&path, export.render(format)?)?;
+            Ok((export.tracks.len(), path))
+        });
+        match result {
+            Ok((count, path)) => {
+                self.status_message =
+                    Some(format!("Exported {} tracks to {}", count, path.display()))
+            }
+            Err(e) => self.handle_error(e),
+        }
+    }
+
+    /// Create the playlist once every match of the import is reviewed, leaving the report on
+    /// the Import screen
+    pub fn finish_import(&mut self) {
+        let (spotify, import) = match (&self.spotify, &mut self.import) {
+            (Some(spotify), Some(import)) if import.report.is_none() => (spotify.clone(), import),
+            _ => return,
+        };
+        let result = match &self.user {
+            Some(user) => Ok(user.id.clone()),
+            None => spotify.current_user().map(|user| user.id),
+        }
+        .and_then(|user_id| {
+            import
+                .create_playlist(spotify.as_ref(), &user_id)
+                .map(|_| ())
+        })
+        .and_then(|_| spotify.current_user_playlists(Some(self.large_search_limit), None));
+
+        match result {
+            Ok(playlists) => self.playlists = Some(playlists),
+            Err(e) => self.handle_error(e),
+        }
+    }
+
     pub fn user_unfollow_playlists(&mut self) {
         if let (Some(playlists), Some(selected_index), Some(user), Some(spotify)) = (
             &self.playlists,
@@ -1264,67 +2076,292 @@ impl App {
     }
 
     pub fn get_made_for_you(&mut self) {
-        // TODO: replace searches when relevant endpoint is added
-        const DISCOVER_WEEKLY: &str = "Discover Weekly";
-        const RELEASE_RADAR: &str = "Release Radar";
-        const ON_REPEAT: &str = "On Repeat";
-        const REPEAT_REWIND: &str = "Repeat Rewind";
-
         if self.library.made_for_you_playlists.pages.is_empty() {
-            self.made_for_you_search_and_add(DISCOVER_WEEKLY);
-            self.made_for_you_search_and_add(RELEASE_RADAR);
-            self.made_for_you_search_and_add(ON_REPEAT);
-            self.made_for_you_search_and_add(REPEAT_REWIND);
+            match self.fetch_made_for_you() {
+                Ok(Some(playlists)) => self.library.made_for_you_playlists.add_pages(playlists),
+                Ok(None) => {}
+                Err(e) => self.handle_error(e),
+            }
         }
     }
 
-    fn made_for_you_search_and_add(&mut self, search_string: &str) {
+    // Combines the personalised mixes from the "Made For You" browse category (Daily Mixes,
+    // Discover Weekly and so on, named in the user's language) with the Spotify owned playlists
+    // the user follows
+    fn fetch_made_for_you(&self) -> Result<Option<Page<SimplifiedPlaylist>>, failure::Error> {
         const SPOTIFY_ID: &str = "spotify";
+        const MADE_FOR_YOU_CATEGORY_ID: &str = "0JQ5DAt0tbjZptfcdMSKl3";
+        const PLAYLISTS_LIMIT: u32 = 50;
 
-        if let (Some(spotify), Some(user)) = (self.spotify.clone(), self.user.clone()) {
-            let user_country =
-                Country::from_str(&user.country.unwrap_or_else(|| "".to_string())).ok();
-            match spotify.search_playlist(search_string, self.large_search_limit, 0, user_country) {
-                Ok(mut search_playlists) => {
-                    let mut filtered_playlists = search_playlists
-                        .playlists
-                        .items
-                        .iter()
-                        .filter(|playlist| {
-                            playlist.owner.id == SPOTIFY_ID && playlist.name == search_string
-                        })
-                        .map(|playlist| playlist.to_owned())
-                        .collect::<Vec<SimplifiedPlaylist>>();
-
-                    if !self.library.made_for_you_playlists.pages.is_empty() {
-                        self.library
-                            .made_for_you_playlists
-                            .get_mut_results(None)
-                            .unwrap()
-                            .items
-                            .append(&mut filtered_playlists);
-                    } else {
-                        search_playlists.playlists.items = filtered_playlists;
-                        self.library
-                            .made_for_you_playlists
-                            .add_pages(search_playlists.playlists);
-                    }
+        let spotify = match &self.spotify {
+            Some(spotify) => spotify,
+            None => return Ok(None),
+        };
+
+        let mut page = spotify.current_user_playlists(Some(PLAYLISTS_LIMIT), Some(0))?;
+        let mut playlists = page.items.clone();
+        while page.next.is_some() {
+            page = spotify
+                .current_user_playlists(Some(PLAYLISTS_LIMIT), Some(page.offset + page.limit))?;
+            playlists.extend(page.items.clone());
+        }
+        playlists.retain(|playlist| playlist.owner.id == SPOTIFY_ID);
+
+        // The category is not available in every market, the followed playlists are still shown
+        if let Ok(category_playlists) =
+            self.fetch_category_playlists(MADE_FOR_YOU_CATEGORY_ID, PLAYLISTS_LIMIT, 0)
+        {
+            for playlist in category_playlists.items.into_iter().rev() {
+                if !playlists.iter().any(|followed| followed.id == playlist.id) {
+                    playlists.insert(0, playlist);
                 }
-                Err(e) => {
-                    self.handle_error(e);
+            }
+        }
+
+        page.total = playlists.len() as u32;
+        page.offset = 0;
+        page.next = None;
+        page.previous = None;
+        page.items = playlists;
+        Ok(Some(page))
+    }
+
+    // rspotify has no method for a category's playlists
+    fn fetch_category_playlists(
+        &self,
+        category_id: &str,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Page<SimplifiedPlaylist>, failure::Error> {
+        #[derive(Deserialize)]
+        struct CategoryPlaylists {
+            playlists: Page<SimplifiedPlaylist>,
+        }
+
+        let mut path = format!(
+            "browse/categories/{}/playlists?limit={}&offset={}",
+            category_id, limit, offset
+        );
+        if let Some(country) = self.get_user_country() {
+            path.push_str(&format!("&country={}", country.as_str()));
+        }
+
+        let text = self.web_api_call(Method::GET, &path, None)?;
+        Ok(serde_json::from_str::<CategoryPlaylists>(&text)?.playlists)
+    }
+
+    fn get_user_country(&self) -> Option<Country> {
+        self.user
+            .as_ref()
+            .and_then(|user| user.country.as_ref())
+            .and_then(|country| Country::from_str(country).ok())
+    }
+
+    pub fn get_top(&mut self) {
+        self.load_top();
+        self.push_navigation_stack(RouteId::Top, ActiveBlock::Top);
+    }
+
+    pub fn next_top_time_range(&mut self) {
+        self.top.time_range = self.top.time_range.next();
+        self.load_top();
+    }
+
+    fn load_top(&mut self) {
+        let spotify = match &self.spotify {
+            Some(spotify) => spotify,
+            None => return,
+        };
+        let limit = Some(self.large_search_limit);
+        let time_range = Some(self.top.time_range.time_range());
+        let result = spotify
+            .current_user_top_artists(limit, None, time_range)
+            .and_then(|artists| {
+                spotify
+                    .current_user_top_tracks(limit, None, time_range)
+                    .map(|tracks| (artists.items, tracks.items))
+            });
+
+        match result {
+            Ok((artists, tracks)) => {
+                self.top.artists = artists;
+                self.top.tracks = tracks;
+                self.top.selected_artist_index = 0;
+                self.top.selected_track_index = 0;
+            }
+            // Logins from before spt asked for `user-top-read` are not allowed to read them
+            Err(e) => match e.downcast_ref::<ApiError>() {
+                Some(ApiError::Other(403)) => self.handle_error(err_msg(
+                    "Your login does not allow reading your top artists and tracks yet. \
+                     Quit and run `spt auth` to log in again",
+                )),
+                _ => self.handle_error(e),
+            },
+        }
+    }
+
+    // Plays the top tracks from the selected one on
+    pub fn play_top_track(&mut self) {
+        let uris = self
+            .top
+            .tracks
+            .iter()
+            .map(|track| track.uri.clone())
+            .collect::<Vec<String>>();
+        if !uris.is_empty() {
+            self.start_playback(None, Some(uris), Some(self.top.selected_track_index));
+        }
+    }
+
+    pub fn get_browse(&mut self) {
+        self.load_browse_section();
+        self.push_navigation_stack(RouteId::Browse, ActiveBlock::Browse);
+    }
+
+    // Fetches the first page of the current browse section unless it was loaded before
+    pub fn load_browse_section(&mut self) {
+        let is_loaded = match self.browse.section {
+            BrowseSection::Categories => !self.browse.categories.pages.is_empty(),
+            BrowseSection::NewReleases => !self.browse.new_releases.pages.is_empty(),
+            BrowseSection::FeaturedPlaylists => !self.browse.featured_playlists.pages.is_empty(),
+        };
+        if !is_loaded {
+            self.get_browse_page(0);
+        }
+    }
+
+    fn get_browse_page(&mut self, offset: u32) {
+        if let Some(spotify) = &self.spotify {
+            let user_country = self.get_user_country();
+            let locale = get_locale();
+            let limit = self.large_search_limit;
+
+            let browse = &mut self.browse;
+            let result = match browse.section {
+                BrowseSection::Categories => spotify
+                    .categories(locale, user_country, Some(limit), Some(offset))
+                    .map(|result| browse.categories.add_pages(result.categories)),
+                BrowseSection::NewReleases => spotify
+                    .new_releases(user_country, Some(limit), Some(offset))
+                    .map(|result| browse.new_releases.add_pages(result.albums)),
+                BrowseSection::FeaturedPlaylists => spotify
+                    .featured_playlists(locale, user_country, Some(limit), Some(offset))
+                    .map(|result| {
+                        browse.featured_message = Some(result.message);
+                        browse.featured_playlists.add_pages(result.playlists);
+                    }),
+            };
+
+            match result {
+                Ok(()) => self.browse.selected_index = 0,
+                Err(e) => self.handle_error(e),
+            }
+        }
+    }
+
+    pub fn get_browse_next_page(&mut self) {
+        let offset = match self.browse.section {
+            BrowseSection::Categories => self.browse.categories.next_page_offset(),
+            BrowseSection::NewReleases => self.browse.new_releases.next_page_offset(),
+            BrowseSection::FeaturedPlaylists => self.browse.featured_playlists.next_page_offset(),
+        };
+        match offset {
+            Some(offset) => self.get_browse_page(offset),
+            None => self.browse.selected_index = 0,
+        }
+    }
+
+    pub fn get_browse_previous_page(&mut self) {
+        match self.browse.section {
+            BrowseSection::Categories => self.browse.categories.previous_page(),
+            BrowseSection::NewReleases => self.browse.new_releases.previous_page(),
+            BrowseSection::FeaturedPlaylists => self.browse.featured_playlists.previous_page(),
+        };
+        self.browse.selected_index = 0;
+    }
+
+    pub fn get_category_playlists(&mut self, category: Category, offset: u32) {
+        match self.fetch_category_playlists(&category.id, self.large_search_limit, offset) {
+            Ok(playlists) => {
+                if offset == 0 {
+                    self.browse.category_playlists = ScrollableResultPages::new();
+                }
+                self.browse.category_playlists.add_pages(playlists);
+                self.browse.category_playlist_index = 0;
+                self.browse.selected_category = Some(category);
+                if self.get_current_route().id != RouteId::CategoryPlaylists {
+                    self.push_navigation_stack(
+                        RouteId::CategoryPlaylists,
+                        ActiveBlock::CategoryPlaylists,
+                    );
                 }
             }
+            Err(e) => self.handle_error(e),
+        }
+    }
+
+    pub fn get_category_playlists_next_page(&mut self) {
+        if let (Some(offset), Some(category)) = (
+            self.browse.category_playlists.next_page_offset(),
+            self.browse.selected_category.clone(),
+        ) {
+            self.get_category_playlists(category, offset);
+        }
+        self.browse.category_playlist_index = 0;
+    }
+
+    pub fn get_category_playlists_previous_page(&mut self) {
+        self.browse.category_playlists.previous_page();
+        self.browse.category_playlist_index = 0;
+    }
+
+    // Opens a featured or category playlist in the track table
+    pub fn get_browse_playlist_tracks(&mut self, playlist: SimplifiedPlaylist) {
+        self.playlist_offset = 0;
+        self.track_table.context = Some(TrackTableContext::BrowsePlaylist);
+        self.get_playlist_tracks(playlist.id.clone());
+        self.browse.selected_playlist = Some(playlist);
+    }
+
+    // Display names of the items on the current page of the current browse section
+    pub fn get_browse_items(&self) -> Vec<String> {
+        match self.browse.section {
+            BrowseSection::Categories => self
+                .browse
+                .categories
+                .get_results(None)
+                .map(|page| page.items.iter().map(|item| item.name.clone()).collect()),
+            BrowseSection::NewReleases => self.browse.new_releases.get_results(None).map(|page| {
+                page.items
+                    .iter()
+                    .map(|album| {
+                        let artists = album
+                            .artists
+                            .iter()
+                            .map(|artist| artist.name.as_str())
+                            .collect::<Vec<&str>>();
+                        format!("{} - {}", album.name, artists.join(", "))
+                    })
+                    .collect()
+            }),
+            BrowseSection::FeaturedPlaylists => self
+                .browse
+                .featured_playlists
+                .get_results(None)
+                .map(|page| page.items.iter().map(|item| item.name.clone()).collect()),
         }
+        .unwrap_or_default()
     }
 
     pub fn get_audio_analysis(&mut self) {
         if let (Some(spotify), Some(context)) = (&self.spotify, &self.current_playback_context) {
             if let Some(track) = &context.item {
-                let uri = &track.uri;
+                let uri = track.uri.clone();
 
-                match spotify.audio_analysis(uri) {
+                match spotify.audio_analysis(&uri) {
                     Ok(result) => {
                         self.audio_analysis = Some(result);
+                        self.audio_analysis_uri = Some(uri);
                         self.push_navigation_stack(RouteId::Analysis, ActiveBlock::Analysis);
                     }
                     Err(e) => {
@@ -1335,6 +2372,139 @@ impl App {
         }
     }
 
+    // Keeps the analysis in step with playback while the visualizer is open
+    fn update_audio_analysis(&mut self) {
+        if let (Some(spotify), Some(context)) = (&self.spotify, &self.current_playback_context) {
+            if let Some(track) = &context.item {
+                if self.audio_analysis_uri.as_ref() == Some(&track.uri) {
+                    return;
+                }
+
+                let uri = track.uri.clone();
+                let result = spotify.audio_analysis(&uri);
+                // Remember the track even on failure so the request is not retried every tick
+                self.audio_analysis_uri = Some(uri);
+                match result {
+                    Ok(result) => {
+                        self.audio_analysis = Some(result);
+                    }
+                    Err(e) => {
+                        self.audio_analysis = None;
+                        self.handle_error(e);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn get_track_info(&mut self, track_id: &str) {
+        if let Some(spotify) = &self.spotify {
+            match spotify.track(track_id) {
+                Ok(track) => {
+                    let audio_features = spotify.audio_features(track_id).ok();
+
+                    let is_recommended = self.track_table.context
+                        == Some(TrackTableContext::RecommendedTracks)
+                        && self
+                            .recommended_tracks
+                            .iter()
+                            .any(|recommended| recommended.id == track.id);
+                    let recommended_from = match &self.recommendations_context {
+                        Some(RecommendationsContext::Song) if is_recommended => {
+                            Some(format!("Song '{}'", self.recommendations_seed))
+                        }
+                        Some(RecommendationsContext::Artist) if is_recommended => {
+                            Some(format!("Artist '{}'", self.recommendations_seed))
+                        }
+                        Some(RecommendationsContext::Builder) if is_recommended => {
+                            Some(self.recommendations_seed.clone())
+                        }
+                        _ => None,
+                    };
+
+                    self.track_info = Some(TrackInfo {
+                        track,
+                        audio_features,
+                        recommended_from,
+                    });
+                    self.set_current_route_state(Some(ActiveBlock::TrackInfo), None);
+                }
+                Err(e) => {
+                    self.handle_error(e);
+                }
+            }
+        }
+    }
+
+    pub fn get_lyrics(&mut self) {
+        // Look the lyrics up again in case they were added since the last time
+        self.lyrics_track_id = None;
+        self.load_lyrics();
+        self.push_navigation_stack(RouteId::Lyrics, ActiveBlock::Lyrics);
+    }
+
+    pub fn is_loading_lyrics(&self) -> bool {
+        self.lyrics_track_id
+            .as_ref()
+            .is_some_and(|track_id| self.lyrics_loader.is_pending(track_id))
+    }
+
+    // Only looks up when the playing track changed, so lyrics follow along with playback
+    fn load_lyrics(&mut self) {
+        for (track_id, result) in self.lyrics_loader.receive() {
+            // Lyrics of a track that stopped playing while they were looked up are dropped
+            if self.lyrics_track_id.as_ref() != Some(&track_id) {
+                continue;
+            }
+            self.lyrics_scroll = 0;
+            match result {
+                Ok(lyrics) => {
+                    self.lyrics = lyrics;
+                }
+                Err(e) => {
+                    self.lyrics = None;
+                    self.handle_error(e);
+                }
+            }
+        }
+
+        let track = match self
+            .current_playback_context
+            .as_ref()
+            .and_then(|context| context.item.as_ref())
+        {
+            Some(track) => track,
+            None => return,
+        };
+
+        let track_id = track.id.clone().unwrap_or_else(|| track.uri.clone());
+        if self.lyrics_track_id.as_ref() == Some(&track_id) {
+            return;
+        }
+
+        let query = LyricsQuery {
+            artist: track
+                .artists
+                .iter()
+                .map(|artist| artist.name.as_str())
+                .collect::<Vec<&str>>()
+                .join(", "),
+            title: track.name.clone(),
+            album: track.album.name.clone(),
+            duration_ms: track.duration_ms,
+        };
+        self.lyrics_loader.load(
+            &track_id,
+            query,
+            self.user_config.behavior.lyrics_dir.clone(),
+            self.user_config.behavior.lyrics_command.clone(),
+        );
+
+        self.lyrics_track_id = Some(track_id);
+        self.lyrics = None;
+        self.lyrics_scroll = 0;
+    }
+
     pub fn calculate_help_menu_offset(&mut self) {
         let old_offset = self.help_menu_offset;
 
@@ -1347,3 +2517,103 @@ impl App {
         }
     }
 }
+
+// Spotify localises category and featured playlist names with an ISO 639 language and ISO 3166
+// country code such as "es_MX", taken here from the environment's locale (e.g. "es_MX.UTF-8")
+fn get_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .and_then(|value| parse_locale(&value))
+}
+
+fn parse_locale(value: &str) -> Option<String> {
+    let locale = value.split(['.', '@']).next()?;
+    let mut parts = locale.split('_');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(language), Some(country), None) if language.len() == 2 && country.len() == 2 => Some(
+            format!("{}_{}", language.to_lowercase(), country.to_uppercase()),
+        ),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spotify_api::fake::{self, FakeSpotify};
+    use serde_json::json;
+    use std::rc::Rc;
+
+    // Plays `playing` from the recommendations with the radio on, and recommends `recommended`
+    fn radio_app(playing: Value, recommended: Vec<Value>) -> (App, Rc<FakeSpotify>) {
+        let (mut app, spotify) = fake::app_with_fake();
+        spotify
+            .respond(
+                "current_user_recently_played",
+                json!({
+                    "href": "",
+                    "items": [{ "track": playing, "played_at": "2020-01-01T00:00:00Z", "context": null }],
+                    "limit": 50,
+                    "next": null,
+                    "cursors": { "after": null },
+                }),
+            )
+            .respond(
+                "recommendations",
+                json!({ "seeds": [], "tracks": recommended }),
+            )
+            .respond("tracks", json!({ "tracks": recommended }));
+
+        app.user = serde_json::from_value(fake::user("alice")).ok();
+        app.client_config.device_id = Some("d1".to_string());
+        app.current_playback_context = serde_json::from_value(fake::playing_context(
+            playing.clone(),
+            fake::device("d1", "Desk", true),
+            true,
+        ))
+        .ok();
+        app.recommended_tracks = vec![serde_json::from_value(playing).unwrap()];
+        app.toggle_radio();
+        (app, spotify)
+    }
+
+    #[test]
+    fn test_radio_queues_new_recommendations() {
+        let playing = fake::track("t1", "One", "Band");
+        let (mut app, spotify) = radio_app(
+            playing.clone(),
+            vec![playing, fake::track("t2", "Two", "Band")],
+        );
+
+        app.update_radio();
+
+        assert_eq!(
+            spotify.calls_to("add_item_to_queue"),
+            vec!["\"spotify:track:t2\", Some(\"d1\")"]
+        );
+        assert_eq!(app.recommended_tracks.len(), 2);
+    }
+
+    #[test]
+    fn test_radio_waits_when_nothing_new_is_recommended() {
+        let playing = fake::track("t1", "One", "Band");
+        let (mut app, spotify) = radio_app(playing.clone(), vec![playing]);
+
+        app.update_radio();
+        app.update_radio();
+
+        assert!(app.radio);
+        assert_eq!(spotify.calls_to("recommendations").len(), 1);
+        assert!(spotify.calls_to("add_item_to_queue").is_empty());
+    }
+
+    #[test]
+    fn test_parse_locale() {
+        assert_eq!(parse_locale("es_MX.UTF-8"), Some("es_MX".to_string()));
+        assert_eq!(parse_locale("de_DE@euro"), Some("de_DE".to_string()));
+        assert_eq!(parse_locale("C"), None);
+        assert_eq!(parse_locale("POSIX"), None);
+    }
+}
@@ -0,0 +1,207 @@
+//! Logging in to Spotify and building the Web API client from the token.
+use crate::{
+    config::ClientConfig,
+    qr,
+    redirect_uri::listen_for_redirect,
+    spotify_api::{traffic::Traffic, WebApiClient},
+};
+use failure::err_msg;
+use rspotify::spotify::oauth2::TokenInfo;
+use std::{
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+pub use crate::oauth::OAuth;
+
+/// The scopes spt asks for when logging in
+pub const SCOPES: [&str; 14] = [
+    "playlist-read-collaborative",
+    "playlist-read-private",
+    "playlist-modify-private",
+    "playlist-modify-public",
+    "user-follow-read",
+    "user-follow-modify",
+    "user-library-modify",
+    "user-library-read",
+    "user-modify-playback-state",
+    "user-read-currently-playing",
+    "user-read-playback-state",
+    "user-read-private",
+    "user-read-recently-played",
+    "user-top-read",
+];
+
+/// A logged in session, which hands out a new client once the token has expired
+pub struct Login {
+    pub oauth: OAuth,
+    pub token_expiry: Instant,
+}
+
+impl Login {
+    /// A client with a fresh token, or `None` while the current one is still valid
+    pub fn refresh(
+        &mut self,
+        api_url: &str,
+        traffic: &Traffic,
+    ) -> Result<Option<WebApiClient>, failure::Error> {
+        if Instant::now() <= self.token_expiry {
+            return Ok(None);
+        }
+        let token_info = self
+            .oauth
+            .get_cached_token()?
+            .ok_or_else(|| err_msg("Failed to refresh authentication token"))?;
+        let (spotify, token_expiry) = get_spotify(token_info, api_url, traffic);
+        self.token_expiry = token_expiry;
+        Ok(Some(spotify))
+    }
+}
+
+/// The client for a token, along with when the token needs refreshing
+pub fn get_spotify(
+    token_info: TokenInfo,
+    api_url: &str,
+    traffic: &Traffic,
+) -> (WebApiClient, Instant) {
+    let token_expiry = Instant::now()
+        + Duration::from_secs(token_info.expires_in.into())
+        // Set 10 seconds early
+        - Duration::from_secs(10);
+
+    let spotify =
+        WebApiClient::new(api_url, &token_info.access_token).with_traffic(traffic.clone());
+
+    (spotify, token_expiry)
+}
+
+/// OAuth for the profile's client id, secret (unless PKCE is used) and token cache
+pub fn build_oauth(client_config: &ClientConfig) -> Result<OAuth, failure::Error> {
+    let token_cache = client_config.get_token_cache()?;
+    token_cache.migrate()?;
+
+    let client_secret = if client_config.uses_pkce() {
+        None
+    } else {
+        Some(client_config.client_secret.as_str())
+    };
+
+    let mut oauth = OAuth::new(
+        &client_config.client_id,
+        client_secret,
+        &client_config.get_redirect_uri(),
+        &SCOPES.join(" "),
+        token_cache,
+    );
+    oauth.accounts_url = client_config.get_accounts_url().to_string();
+    Ok(oauth)
+}
+
+/// The saved login, or a new one in the browser. A saved login missing some of `SCOPES` is
+/// kept unless the user chooses to log in again
+pub fn get_token_auto(oauth: &OAuth, port: u16) -> Result<TokenInfo, failure::Error> {
+    if let Some(token_info) = oauth.get_cached_token()? {
+        let missing_scopes = missing_scopes(&token_info);
+        if missing_scopes.is_empty() || !ask_to_log_in_again(&missing_scopes)? {
+            return Ok(token_info);
+        }
+    }
+    log_in(oauth, port)
+}
+
+/// The scopes in `SCOPES` that `token_info` was not granted, e.g. for a login saved before spt
+/// asked for them. A token that does not list its scopes is taken to have them all
+pub fn missing_scopes(token_info: &TokenInfo) -> Vec<&'static str> {
+    let granted: Vec<&str> = token_info.scope.split_whitespace().collect();
+    if granted.is_empty() {
+        return vec![];
+    }
+    SCOPES
+        .iter()
+        .filter(|scope| !granted.contains(scope))
+        .cloned()
+        .collect()
+}
+
+// Yes unless the answer is no, so pressing enter logs in again
+fn ask_to_log_in_again(missing_scopes: &[&str]) -> Result<bool, failure::Error> {
+    println!(
+        "Your saved login is from before spt asked for these permissions: {}",
+        missing_scopes.join(", ")
+    );
+    print!("Log in again to grant them? The features needing them show an error otherwise [Y/n] ");
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let answer = input.trim().to_lowercase();
+    Ok(answer != "n" && answer != "no")
+}
+
+/// Log in in the browser, with a local webserver catching the redirect
+pub fn log_in(oauth: &OAuth, port: u16) -> Result<TokenInfo, failure::Error> {
+    let auth_url = oauth.get_authorize_url();
+    let open_browser = || match webbrowser::open(&auth_url) {
+        Ok(_) => println!("Opened {} in your browser", auth_url),
+        Err(why) => eprintln!("Error {:?};Please navigate here [{:?}] ", why, auth_url),
+    };
+
+    match listen_for_redirect(port, open_browser) {
+        Ok(url) => oauth.process_redirect(&url),
+        Err(()) => {
+            println!("Starting webserver failed. Continuing with manual authentication");
+            log_in_headless(oauth)
+        }
+    }
+}
+
+/// Log in on another device, e.g. when connected over SSH: the authorize url is printed along
+/// with a QR code and the url Spotify redirects to is pasted back in
+pub fn log_in_headless(oauth: &OAuth) -> Result<TokenInfo, failure::Error> {
+    let auth_url = oauth.get_authorize_url();
+    println!(
+        "Open this URL on any device to log in to Spotify:\n\n{}\n",
+        auth_url
+    );
+    if let Some(qr_code) = qr::to_terminal_string(auth_url.as_bytes()) {
+        println!("Or scan it with your phone:\n\n{}", qr_code);
+    }
+    println!(
+        "After accepting, the browser is sent to {} which will probably fail to load. That is expected.",
+        oauth.redirect_uri
+    );
+    println!(
+        "Copy the URL from the address bar (or just its `code` parameter) and paste it here: "
+    );
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    oauth.process_redirect(&input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token_with_scope(scope: &str) -> TokenInfo {
+        TokenInfo {
+            access_token: "token".to_string(),
+            token_type: "Bearer".to_string(),
+            expires_in: 3600,
+            expires_at: None,
+            refresh_token: None,
+            scope: scope.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_missing_scopes() {
+        let old_scopes = SCOPES[..SCOPES.len() - 1].join(" ");
+        assert_eq!(
+            missing_scopes(&token_with_scope(&old_scopes)),
+            vec!["user-top-read"]
+        );
+        assert!(missing_scopes(&token_with_scope(&SCOPES.join(" "))).is_empty());
+        assert!(missing_scopes(&token_with_scope("")).is_empty());
+    }
+}
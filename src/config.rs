@@ -1,9 +1,18 @@
-use super::banner::BANNER;
+use super::{
+    banner::BANNER,
+    credentials::{
+        self, CredentialStorage, EncryptedFile, Keyring, SecretStore, TokenCache,
+        CLIENT_SECRET_KEY, PASSPHRASE_ENV,
+    },
+    oauth::DEFAULT_ACCOUNTS_URL,
+    scrobbler::ScrobblerConfig,
+    spotify_api::DEFAULT_API_URL,
+};
 use dirs;
-use failure::err_msg;
+use failure::{err_msg, format_err};
 use serde::{Deserialize, Serialize};
 use std::{
-    fs,
+    env, fs,
     io::{stdin, Write},
     path::{Path, PathBuf},
 };
@@ -13,19 +22,108 @@ const FILE_NAME: &str = "client.yml";
 const CONFIG_DIR: &str = ".config";
 const APP_CONFIG_DIR: &str = "spotify-tui";
 const TOKEN_CACHE_FILE: &str = ".spotify_token_cache.json";
+const CREDENTIALS_FILE: &str = "credentials.enc";
+const HISTORY_FILE: &str = "history.db";
+const SCROBBLE_QUEUE_FILE: &str = "scrobble_queue.json";
+const PROFILES_DIR: &str = "profiles";
+pub const DEFAULT_PROFILE: &str = "default";
 
+/// A profile's `client.yml`: the Spotify app credentials, the device to play on and where to
+/// find the Web API
 #[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ClientConfig {
     pub client_id: String,
+    // Left empty to log in with PKCE, which only needs the client ID
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub client_secret: String,
     pub device_id: Option<String>,
     // FIXME: port should be defined in `user_config` not in here
     pub port: Option<u16>,
+    // Named profiles keep their own client.yml, token cache and config.yml overrides in
+    // `profiles/<name>`. `None` is the default profile at the top of the config directory
+    #[serde(skip)]
+    pub profile: Option<String>,
+    // Set to `keyring` to keep the client secret and the login token in the system keyring, or
+    // to `encrypted` to keep them in a file encrypted with a passphrase
+    #[serde(default, skip_serializing_if = "CredentialStorage::is_plaintext")]
+    pub credential_storage: CredentialStorage,
+    // Opened by `load_config`, so a passphrase is only asked for once
+    #[serde(skip)]
+    secret_store: Option<SecretStore>,
+    // Point the app at another Web API and accounts server, e.g. the mock server in src/mock_api
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accounts_url: Option<String>,
+    // Scrobble plays to Last.fm or ListenBrainz, see src/scrobbler.rs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scrobbler: Option<ScrobblerConfig>,
 }
 
+/// Where a profile keeps its files
 pub struct ConfigPaths {
+    pub config_dir: PathBuf,
     pub config_file_path: PathBuf,
     pub token_cache_path: PathBuf,
+    // The secrets of `credential_storage: encrypted`
+    pub credentials_path: PathBuf,
+    // The local listening history, kept per profile like the token
+    pub history_path: PathBuf,
+    // Scrobbles waiting to be sent
+    pub scrobble_queue_path: PathBuf,
+}
+
+fn get_app_config_dir() -> Result<PathBuf, failure::Error> {
+    match dirs::home_dir() {
+        Some(home) => {
+            let path = Path::new(&home);
+            let home_config_dir = path.join(CONFIG_DIR);
+            let app_config_dir = home_config_dir.join(APP_CONFIG_DIR);
+
+            if !home_config_dir.exists() {
+                fs::create_dir(&home_config_dir)?;
+            }
+
+            if !app_config_dir.exists() {
+                fs::create_dir(&app_config_dir)?;
+            }
+
+            Ok(app_config_dir)
+        }
+        None => Err(err_msg("No $HOME directory found for client config")),
+    }
+}
+
+pub fn validate_profile_name(name: &str) -> Result<(), failure::Error> {
+    let is_valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    if is_valid {
+        Ok(())
+    } else {
+        Err(format_err!(
+            "Invalid profile name `{}`, use letters, numbers, `-` and `_`",
+            name
+        ))
+    }
+}
+
+/// The default profile followed by every named profile that has been set up
+pub fn get_profiles() -> Result<Vec<String>, failure::Error> {
+    let mut profiles = vec![];
+    let profiles_dir = get_app_config_dir()?.join(PROFILES_DIR);
+    if profiles_dir.exists() {
+        for entry in fs::read_dir(profiles_dir)? {
+            let entry = entry?;
+            if entry.path().join(FILE_NAME).exists() {
+                profiles.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+    }
+    profiles.sort();
+    profiles.insert(0, DEFAULT_PROFILE.to_string());
+    Ok(profiles)
 }
 
 impl ClientConfig {
@@ -35,75 +133,198 @@ impl ClientConfig {
             client_secret: "".to_string(),
             device_id: None,
             port: None,
+            profile: None,
+            credential_storage: CredentialStorage::Plaintext,
+            secret_store: None,
+            api_url: None,
+            accounts_url: None,
+            scrobbler: None,
         }
     }
 
-    pub fn get_redirect_uri(&self) -> String {
-        format!("http://localhost:{}/callback", self.get_port())
+    pub fn with_profile(profile: Option<String>) -> Result<ClientConfig, failure::Error> {
+        let profile = match profile {
+            Some(name) if name == DEFAULT_PROFILE => None,
+            Some(name) => {
+                validate_profile_name(&name)?;
+                Some(name)
+            }
+            None => None,
+        };
+        Ok(ClientConfig {
+            profile,
+            ..ClientConfig::new()
+        })
     }
 
-    pub fn get_port(&self) -> u16 {
-        self.port.unwrap_or(DEFAULT_PORT)
+    pub fn get_profile_name(&self) -> &str {
+        self.profile.as_deref().unwrap_or(DEFAULT_PROFILE)
     }
 
-    pub fn get_or_build_paths(&self) -> Result<ConfigPaths, failure::Error> {
-        match dirs::home_dir() {
-            Some(home) => {
-                let path = Path::new(&home);
-                let home_config_dir = path.join(CONFIG_DIR);
-                let app_config_dir = home_config_dir.join(APP_CONFIG_DIR);
-
-                if !home_config_dir.exists() {
-                    fs::create_dir(&home_config_dir)?;
+    fn get_keyring(&self) -> Keyring {
+        Keyring::new(self.get_profile_name())
+    }
+
+    pub fn get_token_cache(&self) -> Result<TokenCache, failure::Error> {
+        let token_cache_path = self.get_or_build_paths()?.token_cache_path;
+        Ok(match &self.secret_store {
+            Some(store) => TokenCache::Store(store.clone(), token_cache_path),
+            None => TokenCache::File(token_cache_path),
+        })
+    }
+
+    // Open the keyring or encrypted file holding the secrets, moving the client secret there
+    // first if client.yml still has it. When the keyring can't be used this carries on with
+    // client.yml and the token cache file, as before keyring support
+    fn load_secret_store(&mut self, config_yml: &mut ClientConfig) -> Result<(), failure::Error> {
+        let store = match self.credential_storage {
+            CredentialStorage::Plaintext => return Ok(()),
+            CredentialStorage::Keyring => {
+                if !Keyring::is_available() {
+                    self.credential_storage = CredentialStorage::Plaintext;
+                    return Ok(());
                 }
+                SecretStore::Keyring(self.get_keyring())
+            }
+            CredentialStorage::Encrypted => {
+                let credentials_path = self.get_or_build_paths()?.credentials_path;
+                let passphrase = credentials::read_passphrase(&credentials_path)?;
+                SecretStore::EncryptedFile(EncryptedFile::open(&credentials_path, &passphrase)?)
+            }
+        };
 
-                if !app_config_dir.exists() {
-                    fs::create_dir(&app_config_dir)?;
+        if !config_yml.client_secret.is_empty() {
+            match store.set(CLIENT_SECRET_KEY, &config_yml.client_secret) {
+                Ok(()) => {
+                    config_yml.client_secret.clear();
+                    self.save(config_yml)?;
                 }
+                // A keyring can still refuse to save, e.g. when it is locked
+                Err(_) if self.credential_storage == CredentialStorage::Keyring => {
+                    self.credential_storage = CredentialStorage::Plaintext;
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            }
+        } else if let Some(client_secret) = store.get(CLIENT_SECRET_KEY)? {
+            self.client_secret = client_secret;
+        }
+        self.secret_store = Some(store);
+        Ok(())
+    }
 
-                let config_file_path = &app_config_dir.join(FILE_NAME);
-                let token_cache_path = &app_config_dir.join(TOKEN_CACHE_FILE);
+    /// Whether `load_config` would have to ask for the passphrase of encrypted credentials
+    pub fn needs_passphrase(&self) -> Result<bool, failure::Error> {
+        let paths = self.get_or_build_paths()?;
+        if env::var(PASSPHRASE_ENV).is_ok() || !paths.config_file_path.exists() {
+            return Ok(false);
+        }
+        let config_string = fs::read_to_string(paths.config_file_path)?;
+        let config_yml: ClientConfig = serde_yaml::from_str(&config_string)?;
+        Ok(config_yml.credential_storage == CredentialStorage::Encrypted)
+    }
 
-                let paths = ConfigPaths {
-                    config_file_path: config_file_path.to_path_buf(),
-                    token_cache_path: token_cache_path.to_path_buf(),
-                };
+    fn save(&self, config_yml: &ClientConfig) -> Result<(), failure::Error> {
+        let paths = self.get_or_build_paths()?;
+        let new_config = serde_yaml::to_string(config_yml)?;
+        let mut config_file = fs::File::create(paths.config_file_path)?;
+        write!(config_file, "{}", new_config)?;
+        Ok(())
+    }
+
+    pub fn uses_pkce(&self) -> bool {
+        self.client_secret.is_empty()
+    }
+
+    pub fn get_redirect_uri(&self) -> String {
+        format!("http://localhost:{}/callback", self.get_port())
+    }
+
+    pub fn get_port(&self) -> u16 {
+        self.port.unwrap_or(DEFAULT_PORT)
+    }
+
+    pub fn get_api_url(&self) -> &str {
+        self.api_url.as_deref().unwrap_or(DEFAULT_API_URL)
+    }
+
+    pub fn get_accounts_url(&self) -> &str {
+        self.accounts_url.as_deref().unwrap_or(DEFAULT_ACCOUNTS_URL)
+    }
 
-                Ok(paths)
+    pub fn get_or_build_paths(&self) -> Result<ConfigPaths, failure::Error> {
+        let app_config_dir = get_app_config_dir()?;
+        let config_dir = match &self.profile {
+            Some(profile) => {
+                let profile_dir = app_config_dir.join(PROFILES_DIR).join(profile);
+                if !profile_dir.exists() {
+                    fs::create_dir_all(&profile_dir)?;
+                }
+                profile_dir
             }
-            None => Err(err_msg("No $HOME directory found for client config")),
-        }
+            None => app_config_dir,
+        };
+
+        Ok(ConfigPaths {
+            config_file_path: config_dir.join(FILE_NAME),
+            token_cache_path: config_dir.join(TOKEN_CACHE_FILE),
+            credentials_path: config_dir.join(CREDENTIALS_FILE),
+            history_path: config_dir.join(HISTORY_FILE),
+            scrobble_queue_path: config_dir.join(SCROBBLE_QUEUE_FILE),
+            config_dir,
+        })
     }
 
     pub fn set_device_id(&mut self, device_id: String) -> Result<(), failure::Error> {
         let paths = self.get_or_build_paths()?;
-        let config_string = fs::read_to_string(&paths.config_file_path)?;
+        let config_string = fs::read_to_string(paths.config_file_path)?;
         let mut config_yml: ClientConfig = serde_yaml::from_str(&config_string)?;
 
         self.device_id = Some(device_id.clone());
         config_yml.device_id = Some(device_id);
 
-        let new_config = serde_yaml::to_string(&config_yml)?;
-        let mut config_file = fs::File::create(&paths.config_file_path)?;
-        write!(config_file, "{}", new_config)?;
-        Ok(())
+        self.save(&config_yml)
+    }
+
+    /// Save the Last.fm session from `spt lastfm-auth` to the `scrobbler` section
+    pub fn set_scrobbler_session_key(&mut self, session_key: String) -> Result<(), failure::Error> {
+        let paths = self.get_or_build_paths()?;
+        let config_string = fs::read_to_string(paths.config_file_path)?;
+        let mut config_yml: ClientConfig = serde_yaml::from_str(&config_string)?;
+
+        let scrobbler = config_yml
+            .scrobbler
+            .as_mut()
+            .ok_or_else(|| err_msg("No `scrobbler` section in client.yml"))?;
+        scrobbler.session_key = Some(session_key);
+        self.scrobbler = config_yml.scrobbler.clone();
+
+        self.save(&config_yml)
     }
 
     pub fn load_config(&mut self) -> Result<(), failure::Error> {
         let paths = self.get_or_build_paths()?;
         if paths.config_file_path.exists() {
             let config_string = fs::read_to_string(&paths.config_file_path)?;
-            let config_yml: ClientConfig = serde_yaml::from_str(&config_string)?;
+            let mut config_yml: ClientConfig = serde_yaml::from_str(&config_string)?;
 
-            self.client_id = config_yml.client_id;
-            self.client_secret = config_yml.client_secret;
-            self.device_id = config_yml.device_id;
+            self.client_id = config_yml.client_id.clone();
+            self.client_secret = config_yml.client_secret.clone();
+            self.device_id = config_yml.device_id.clone();
             self.port = config_yml.port;
+            self.credential_storage = config_yml.credential_storage;
+            self.api_url = config_yml.api_url.clone();
+            self.accounts_url = config_yml.accounts_url.clone();
+            self.scrobbler = config_yml.scrobbler.clone();
 
-            Ok(())
+            self.load_secret_store(&mut config_yml)
         } else {
             println!("{}", BANNER);
 
+            if let Some(profile) = &self.profile {
+                println!("Setting up the `{}` profile", profile);
+            }
+
             println!(
                 "Config will be saved to {}",
                 paths.config_file_path.display()
@@ -131,7 +352,7 @@ impl ClientConfig {
             stdin().read_line(&mut client_id)?;
 
             let mut client_secret = String::new();
-            println!("\nEnter your Client Secret: ");
+            println!("\nEnter your Client Secret (leave empty to log in without one using PKCE): ");
             stdin().read_line(&mut client_secret)?;
 
             let mut port = String::new();
@@ -139,22 +360,47 @@ impl ClientConfig {
             stdin().read_line(&mut port)?;
             let port = port.trim().parse::<u16>().unwrap_or(DEFAULT_PORT);
 
-            let config_yml = ClientConfig {
+            let storage_choices = if Keyring::is_available() {
+                "plaintext/keyring/encrypted"
+            } else {
+                "plaintext/encrypted"
+            };
+            let mut credential_storage = String::new();
+            println!(
+                "\nStore your Client Secret and login in plaintext files, the system keyring or a file encrypted with a passphrase? ({}, default plaintext): ",
+                storage_choices
+            );
+            stdin().read_line(&mut credential_storage)?;
+            let credential_storage = match credential_storage.trim().to_lowercase().as_str() {
+                "keyring" => CredentialStorage::Keyring,
+                "encrypted" => CredentialStorage::Encrypted,
+                _ => CredentialStorage::Plaintext,
+            };
+
+            let mut config_yml = ClientConfig {
                 client_id: client_id.trim().to_string(),
                 client_secret: client_secret.trim().to_string(),
                 device_id: None,
                 port: Some(port),
+                profile: None,
+                credential_storage,
+                secret_store: None,
+                api_url: None,
+                accounts_url: None,
+                scrobbler: None,
             };
 
-            let content_yml = serde_yaml::to_string(&config_yml)?;
-
-            let mut new_config = fs::File::create(&paths.config_file_path)?;
-            write!(new_config, "{}", content_yml)?;
-
-            self.client_id = config_yml.client_id;
-            self.client_secret = config_yml.client_secret;
-            self.device_id = config_yml.device_id;
+            self.client_id = config_yml.client_id.clone();
+            self.client_secret = config_yml.client_secret.clone();
+            self.device_id = config_yml.device_id.clone();
             self.port = config_yml.port;
+            self.credential_storage = config_yml.credential_storage;
+
+            // Writes client.yml, without the secret when it went to the keyring or encrypted file
+            self.load_secret_store(&mut config_yml)?;
+            if !paths.config_file_path.exists() {
+                self.save(&config_yml)?;
+            }
 
             Ok(())
         }
@@ -0,0 +1,492 @@
+//! Where the client secret and the login token are kept. By default they are plain files next to
+//! client.yml. With `credential_storage: keyring` they go to the system keyring instead: the
+//! Secret Service through `secret-tool` on Linux and the login keychain through `security` on
+//! macOS. With `credential_storage: encrypted` they go to a file encrypted with a passphrase,
+//! for machines without a keyring.
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use failure::{err_msg, format_err};
+use rspotify::spotify::oauth2::TokenInfo;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    env, fmt, fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+};
+
+const SERVICE: &str = "spotify-tui";
+const TOKEN_KEY: &str = "token";
+pub const CLIENT_SECRET_KEY: &str = "client_secret";
+// Looked up to check that the keyring answers, it is never written
+const AVAILABILITY_KEY: &str = "availability-check";
+// `security` exits with errSecItemNotFound when there is no such entry
+const MACOS_ITEM_NOT_FOUND: i32 = 44;
+
+pub const PASSPHRASE_ENV: &str = "SPT_CREDENTIALS_PASSPHRASE";
+// The file starts with these bytes, then the salt, the nonce and the sealed entries
+const ENCRYPTED_FILE_MAGIC: &[u8] = b"SPTC1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CredentialStorage {
+    #[default]
+    Plaintext,
+    Keyring,
+    Encrypted,
+}
+
+impl CredentialStorage {
+    pub fn is_plaintext(&self) -> bool {
+        *self == CredentialStorage::Plaintext
+    }
+}
+
+/// Entries in the system keyring, one set per profile
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keyring {
+    account: String,
+}
+
+impl Keyring {
+    pub fn new(profile: &str) -> Keyring {
+        Keyring {
+            account: profile.to_string(),
+        }
+    }
+
+    // Having `secret-tool` installed is not enough, e.g. over SSH there is often no Secret
+    // Service or D-Bus session to talk to, so look an entry up for real
+    pub fn is_available() -> bool {
+        Keyring::new(SERVICE).get(AVAILABILITY_KEY).is_ok()
+    }
+
+    pub fn get(&self, key: &str) -> Result<Option<String>, failure::Error> {
+        let output = if cfg!(target_os = "macos") {
+            Command::new("security")
+                .args(["find-generic-password", "-s", SERVICE, "-w", "-a"])
+                .arg(self.macos_account(key))
+                .output()?
+        } else {
+            Command::new("secret-tool")
+                .args([
+                    "lookup",
+                    "service",
+                    SERVICE,
+                    "account",
+                    &self.account,
+                    "key",
+                    key,
+                ])
+                .output()?
+        };
+
+        if !output.status.success() {
+            return if is_missing_entry(&output) {
+                Ok(None)
+            } else {
+                Err(format_err!(
+                    "Failed to read the {} from the keyring: {}",
+                    key,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ))
+            };
+        }
+
+        let value = String::from_utf8(output.stdout)?.trim_end().to_string();
+        Ok(if value.is_empty() { None } else { Some(value) })
+    }
+
+    pub fn set(&self, key: &str, value: &str) -> Result<(), failure::Error> {
+        // The secret is written to stdin so it never shows up in the process list
+        let (mut command, input) = if cfg!(target_os = "macos") {
+            let mut command = Command::new("security");
+            command.arg("-i");
+            let input = format!(
+                "add-generic-password -U -s {} -a {} -w {}\n",
+                SERVICE,
+                quote_macos_argument(&self.macos_account(key)),
+                quote_macos_argument(value)
+            );
+            (command, input)
+        } else {
+            let mut command = Command::new("secret-tool");
+            command
+                .args(["store", "--label"])
+                .arg(format!("{} {} ({})", SERVICE, key, self.account))
+                .args(["service", SERVICE, "account", &self.account, "key", key]);
+            (command, value.to_string())
+        };
+
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(input.as_bytes())?;
+        }
+        let status = child.wait()?;
+
+        // `security -i` exits successfully even when one of its commands fails
+        if status.success() && self.get(key)?.as_deref() == Some(value) {
+            Ok(())
+        } else {
+            Err(format_err!("Failed to save the {} in the keyring", key))
+        }
+    }
+
+    fn macos_account(&self, key: &str) -> String {
+        format!("{}/{}", self.account, key)
+    }
+}
+
+fn is_missing_entry(output: &Output) -> bool {
+    if cfg!(target_os = "macos") {
+        output.status.code() == Some(MACOS_ITEM_NOT_FOUND)
+    } else {
+        // `secret-tool` fails silently when there is no such entry, and says why otherwise
+        output.stderr.iter().all(|byte| byte.is_ascii_whitespace())
+    }
+}
+
+// `security -i` splits its input like a shell, so quote and escape every argument
+fn quote_macos_argument(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Entries in a file encrypted with a passphrase, one file per profile. The key is derived from
+/// the passphrase with Argon2id and the entries are sealed with ChaCha20-Poly1305
+#[derive(Clone)]
+pub struct EncryptedFile {
+    path: PathBuf,
+    salt: [u8; SALT_LEN],
+    key: [u8; 32],
+}
+
+impl EncryptedFile {
+    /// Open the file at `path`, or prepare a new one when there is none yet. Fails when the
+    /// passphrase does not decrypt an existing file
+    pub fn open(path: &Path, passphrase: &str) -> Result<EncryptedFile, failure::Error> {
+        let mut salt = [0; SALT_LEN];
+        let existing = if path.exists() {
+            let bytes = fs::read(path)?;
+            salt.copy_from_slice(split_encrypted_file(&bytes)?.salt);
+            Some(bytes)
+        } else {
+            OsRng.fill_bytes(&mut salt);
+            None
+        };
+
+        let mut key = [0; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|e| format_err!("Failed to derive the credentials key: {}", e))?;
+
+        let file = EncryptedFile {
+            path: path.to_path_buf(),
+            salt,
+            key,
+        };
+        if let Some(bytes) = existing {
+            file.decrypt(&bytes)?;
+        }
+        Ok(file)
+    }
+
+    pub fn get(&self, key: &str) -> Result<Option<String>, failure::Error> {
+        Ok(self.read_entries()?.remove(key))
+    }
+
+    pub fn set(&self, key: &str, value: &str) -> Result<(), failure::Error> {
+        let mut entries = self.read_entries()?;
+        entries.insert(key.to_string(), value.to_string());
+
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&self.key));
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let plaintext = serde_json::to_vec(&entries)?;
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext.as_slice())
+            .map_err(|_| err_msg("Failed to encrypt the credentials"))?;
+
+        let mut bytes = ENCRYPTED_FILE_MAGIC.to_vec();
+        bytes.extend_from_slice(&self.salt);
+        bytes.extend_from_slice(&nonce);
+        bytes.extend_from_slice(&ciphertext);
+        write_private_file(&self.path, &bytes)
+    }
+
+    fn read_entries(&self) -> Result<BTreeMap<String, String>, failure::Error> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        self.decrypt(&fs::read(&self.path)?)
+    }
+
+    fn decrypt(&self, bytes: &[u8]) -> Result<BTreeMap<String, String>, failure::Error> {
+        let parts = split_encrypted_file(bytes)?;
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&self.key));
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(parts.nonce), parts.ciphertext)
+            .map_err(|_| format_err!("Wrong passphrase for {}", self.path.display()))?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+}
+
+// Keep the key out of debug output
+impl fmt::Debug for EncryptedFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EncryptedFile")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl PartialEq for EncryptedFile {
+    fn eq(&self, other: &EncryptedFile) -> bool {
+        self.path == other.path && self.salt == other.salt
+    }
+}
+
+struct EncryptedFileParts<'a> {
+    salt: &'a [u8],
+    nonce: &'a [u8],
+    ciphertext: &'a [u8],
+}
+
+fn split_encrypted_file(bytes: &[u8]) -> Result<EncryptedFileParts<'_>, failure::Error> {
+    let header_len = ENCRYPTED_FILE_MAGIC.len() + SALT_LEN + NONCE_LEN;
+    if bytes.len() < header_len || !bytes.starts_with(ENCRYPTED_FILE_MAGIC) {
+        return Err(err_msg("Not a spotify-tui credentials file"));
+    }
+    let (salt, rest) = bytes[ENCRYPTED_FILE_MAGIC.len()..].split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    Ok(EncryptedFileParts {
+        salt,
+        nonce,
+        ciphertext,
+    })
+}
+
+#[cfg(unix)]
+fn write_private_file(path: &Path, bytes: &[u8]) -> Result<(), failure::Error> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    file.write_all(bytes)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn write_private_file(path: &Path, bytes: &[u8]) -> Result<(), failure::Error> {
+    fs::write(path, bytes)?;
+    Ok(())
+}
+
+/// The passphrase for an encrypted credentials file, from `SPT_CREDENTIALS_PASSPHRASE` or else
+/// asked for on the terminal. A new file asks for it twice
+pub fn read_passphrase(path: &Path) -> Result<String, failure::Error> {
+    if let Ok(passphrase) = env::var(PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+
+    let passphrase = rpassword::prompt_password(format!("Passphrase for {}: ", path.display()))?;
+    if !path.exists() {
+        let repeated = rpassword::prompt_password("Repeat the passphrase: ")?;
+        if repeated != passphrase {
+            return Err(err_msg("The passphrases don't match"));
+        }
+    }
+    if passphrase.is_empty() {
+        return Err(err_msg("The passphrase can't be empty"));
+    }
+    Ok(passphrase)
+}
+
+/// Somewhere other than plaintext files to keep secrets
+#[derive(Clone, Debug, PartialEq)]
+pub enum SecretStore {
+    Keyring(Keyring),
+    EncryptedFile(EncryptedFile),
+}
+
+impl SecretStore {
+    pub fn get(&self, key: &str) -> Result<Option<String>, failure::Error> {
+        match self {
+            SecretStore::Keyring(keyring) => keyring.get(key),
+            SecretStore::EncryptedFile(file) => file.get(key),
+        }
+    }
+
+    pub fn set(&self, key: &str, value: &str) -> Result<(), failure::Error> {
+        match self {
+            SecretStore::Keyring(keyring) => keyring.set(key, value),
+            SecretStore::EncryptedFile(file) => file.set(key, value),
+        }
+    }
+
+    fn is_keyring(&self) -> bool {
+        matches!(self, SecretStore::Keyring(_))
+    }
+}
+
+/// Where a login token is cached between runs
+pub enum TokenCache {
+    File(PathBuf),
+    // The file is where the token goes when the keyring refuses it, e.g. once it got locked, so
+    // logging in still works
+    Store(SecretStore, PathBuf),
+}
+
+impl TokenCache {
+    /// Move a token cached in a plaintext file into the secret store
+    pub fn migrate(&self) -> Result<(), failure::Error> {
+        if let TokenCache::Store(store, path) = self {
+            if path.exists() {
+                let token = fs::read_to_string(path)?;
+                match store.set(TOKEN_KEY, token.trim()) {
+                    Ok(()) => fs::remove_file(path)?,
+                    // Keep using the file until the keyring works again
+                    Err(_) if store.is_keyring() => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn load(&self) -> Option<TokenInfo> {
+        let token = match self {
+            TokenCache::File(path) => fs::read_to_string(path).ok(),
+            // A file left over after `migrate` holds a token the keyring refused
+            TokenCache::Store(store, path) => fs::read_to_string(path)
+                .ok()
+                .or_else(|| store.get(TOKEN_KEY).ok().flatten()),
+        }?;
+        serde_json::from_str(&token).ok()
+    }
+
+    pub fn save(&self, token_info: &TokenInfo) -> Result<(), failure::Error> {
+        let token = serde_json::to_string(token_info)?;
+        match self {
+            TokenCache::File(path) => write_private_file(path, token.as_bytes())?,
+            TokenCache::Store(store, path) => match store.set(TOKEN_KEY, &token) {
+                Ok(()) => {
+                    if path.exists() {
+                        fs::remove_file(path)?;
+                    }
+                }
+                // An encrypted file that can't be written is not worth leaking the token for
+                Err(e) if store.is_keyring() => {
+                    eprintln!(
+                        "Failed to save the login in the keyring, keeping it in {} instead: {}",
+                        path.display(),
+                        e
+                    );
+                    write_private_file(path, token.as_bytes())?;
+                }
+                Err(e) => return Err(e),
+            },
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn token_info() -> TokenInfo {
+        TokenInfo {
+            access_token: "access".to_string(),
+            token_type: "Bearer".to_string(),
+            expires_in: 3600,
+            expires_at: Some(1),
+            refresh_token: Some("refresh".to_string()),
+            scope: "".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_file_token_cache() {
+        let path = env::temp_dir().join(format!(
+            "spotify-tui-token-cache-test-{}.json",
+            std::process::id()
+        ));
+        let cache = TokenCache::File(path.clone());
+
+        cache.save(&token_info()).unwrap();
+        let loaded = cache.load().unwrap();
+        assert_eq!(loaded.access_token, "access");
+        assert_eq!(loaded.refresh_token, Some("refresh".to_string()));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_encrypted_file() {
+        let dir = env::temp_dir();
+        let path = dir.join(format!(
+            "spotify-tui-encrypted-credentials-test-{}",
+            std::process::id()
+        ));
+        let token_cache_path = dir.join(format!(
+            "spotify-tui-encrypted-token-cache-test-{}.json",
+            std::process::id()
+        ));
+
+        let file = EncryptedFile::open(&path, "correct horse").unwrap();
+        assert_eq!(file.get(CLIENT_SECRET_KEY).unwrap(), None);
+        file.set(CLIENT_SECRET_KEY, "secret").unwrap();
+        let cache = TokenCache::Store(SecretStore::EncryptedFile(file), token_cache_path.clone());
+        cache.save(&token_info()).unwrap();
+
+        let contents = fs::read(&path).unwrap();
+        assert!(!String::from_utf8_lossy(&contents).contains("secret"));
+        assert!(!String::from_utf8_lossy(&contents).contains("refresh"));
+
+        let reopened = EncryptedFile::open(&path, "correct horse").unwrap();
+        assert_eq!(
+            reopened.get(CLIENT_SECRET_KEY).unwrap(),
+            Some("secret".to_string())
+        );
+        let cache = TokenCache::Store(
+            SecretStore::EncryptedFile(reopened),
+            token_cache_path.clone(),
+        );
+        assert_eq!(
+            cache.load().unwrap().refresh_token,
+            Some("refresh".to_string())
+        );
+
+        assert!(!token_cache_path.exists());
+
+        assert!(EncryptedFile::open(&path, "wrong horse").is_err());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_quote_macos_argument() {
+        assert_eq!(
+            quote_macos_argument(r#"{"a":"b\c"}"#),
+            r#""{\"a\":\"b\\c\"}""#
+        );
+    }
+}
@@ -0,0 +1,531 @@
+//! Exporting a playlist, the liked songs or the saved albums to CSV, JSON, M3U or XSPF, from
+//! `spt export` or with `E` in the app. Every page is fetched, not only what the app shows.
+//!
+//! CSV and JSON have every field. M3U and XSPF have what their formats have room for, with the
+//! Spotify URI as the location so players that understand Spotify URIs can open them.
+use crate::{
+    spotify_api::SpotifyApi,
+    uri::{SpotifyUri, UriKind},
+};
+use failure::format_err;
+use rspotify::spotify::model::{page::Page, playlist::SimplifiedPlaylist, track::FullTrack};
+use serde::Serialize;
+use serde_json::json;
+use std::{fs, path::Path};
+
+// The most any of the paged endpoints used here returns at once
+const PAGE_LIMIT: u32 = 50;
+// The most tracks `tracks` looks up at once
+const TRACKS_LIMIT: usize = 50;
+const ADDED_AT_FORMAT: &str = "%Y-%m-%dT%H:%M:%SZ";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    M3u,
+    Xspf,
+}
+
+impl ExportFormat {
+    pub fn parse(format: &str) -> Result<ExportFormat, failure::Error> {
+        match format.to_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            "m3u" => Ok(ExportFormat::M3u),
+            "xspf" => Ok(ExportFormat::Xspf),
+            _ => Err(format_err!(
+                "Export format must be one of csv, json, m3u or xspf, is {}",
+                format
+            )),
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::M3u => "m3u",
+            ExportFormat::Xspf => "xspf",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ExportTrack {
+    pub title: String,
+    pub artists: Vec<String>,
+    pub album: String,
+    pub duration_ms: u32,
+    pub isrc: Option<String>,
+    pub uri: String,
+    // When it was added to the playlist or saved, in UTC
+    pub added_at: Option<String>,
+}
+
+impl ExportTrack {
+    fn from_track(track: &FullTrack, added_at: Option<String>) -> ExportTrack {
+        ExportTrack {
+            title: track.name.clone(),
+            artists: track
+                .artists
+                .iter()
+                .map(|artist| artist.name.clone())
+                .collect(),
+            album: track.album.name.clone(),
+            duration_ms: track.duration_ms,
+            isrc: track.external_ids.get("isrc").cloned(),
+            uri: track.uri.clone(),
+            added_at,
+        }
+    }
+}
+
+/// A named list of tracks, ready to be written in any format
+#[derive(Clone, Debug, PartialEq)]
+pub struct Export {
+    pub name: String,
+    pub tracks: Vec<ExportTrack>,
+}
+
+impl Export {
+    pub fn render(&self, format: ExportFormat) -> Result<String, failure::Error> {
+        Ok(match format {
+            ExportFormat::Csv => self.to_csv(),
+            ExportFormat::Json => serde_json::to_string_pretty(&json!({
+                "name": self.name,
+                "tracks": self.tracks,
+            }))?,
+            ExportFormat::M3u => self.to_m3u(),
+            ExportFormat::Xspf => self.to_xspf(),
+        })
+    }
+
+    /// A file name for the export, without characters file systems disallow
+    pub fn file_name(&self, format: ExportFormat) -> String {
+        let name = self
+            .name
+            .chars()
+            .map(|c| match c {
+                '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+                c if c.is_control() => '_',
+                c => c,
+            })
+            .collect::<String>();
+        let name = name.trim().trim_start_matches('.');
+        let name = if name.is_empty() { "export" } else { name };
+        format!("{}.{}", name, format.extension())
+    }
+
+    fn to_csv(&self) -> String {
+        let mut csv = String::from("title,artists,album,duration_ms,isrc,uri,added_at\r\n");
+        for track in &self.tracks {
+            let fields = [
+                csv_field(&track.title),
+                csv_field(&track.artists.join(", ")),
+                csv_field(&track.album),
+                track.duration_ms.to_string(),
+                csv_field(track.isrc.as_deref().unwrap_or_default()),
+                csv_field(&track.uri),
+                csv_field(track.added_at.as_deref().unwrap_or_default()),
+            ];
+            csv.push_str(&fields.join(","));
+            csv.push_str("\r\n");
+        }
+        csv
+    }
+
+    fn to_m3u(&self) -> String {
+        let mut m3u = format!("#EXTM3U\n#PLAYLIST:{}\n", one_line(&self.name));
+        for track in &self.tracks {
+            m3u.push_str(&format!(
+                "#EXTINF:{},{} - {}\n#EXTALB:{}\n{}\n",
+                track.duration_ms / 1000,
+                one_line(&track.artists.join(", ")),
+                one_line(&track.title),
+                one_line(&track.album),
+                track.uri
+            ));
+        }
+        m3u
+    }
+
+    fn to_xspf(&self) -> String {
+        let mut xspf = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n  \
+             <title>{}</title>\n  <trackList>\n",
+            xml_escape(&self.name)
+        );
+        for track in &self.tracks {
+            xspf.push_str("    <track>\n");
+            xspf.push_str(&format!(
+                "      <location>{}</location>\n",
+                xml_escape(&track.uri)
+            ));
+            if let Some(isrc) = &track.isrc {
+                xspf.push_str(&format!(
+                    "      <identifier>urn:isrc:{}</identifier>\n",
+                    xml_escape(isrc)
+                ));
+            }
+            xspf.push_str(&format!(
+                "      <title>{}</title>\n      <creator>{}</creator>\n      \
+                 <album>{}</album>\n      <duration>{}</duration>\n",
+                xml_escape(&track.title),
+                xml_escape(&track.artists.join(", ")),
+                xml_escape(&track.album),
+                track.duration_ms
+            ));
+            xspf.push_str("    </track>\n");
+        }
+        xspf.push_str("  </trackList>\n</playlist>\n");
+        xspf
+    }
+}
+
+// Quoted when it has a separator, a quote or a line break in it, as in RFC 4180
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// M3U has one entry per line
+fn one_line(value: &str) -> String {
+    value.replace(['\n', '\r'], " ")
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Fetch every page from `fetch`, which is given the limit and offset
+fn all_pages<T>(
+    mut fetch: impl FnMut(u32, u32) -> Result<Page<T>, failure::Error>,
+) -> Result<Vec<T>, failure::Error> {
+    let mut items = vec![];
+    loop {
+        let page = fetch(PAGE_LIMIT, items.len() as u32)?;
+        let is_last = page.next.is_none() || page.items.is_empty();
+        let total = page.total as usize;
+        items.extend(page.items);
+        if is_last || items.len() >= total {
+            return Ok(items);
+        }
+    }
+}
+
+/// All of the current user's playlists, followed ones included
+pub fn all_playlists(spotify: &dyn SpotifyApi) -> Result<Vec<SimplifiedPlaylist>, failure::Error> {
+    all_pages(|limit, offset| spotify.current_user_playlists(Some(limit), Some(offset)))
+}
+
+/// What `spt export` exports
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExportSource {
+    /// A playlist by name, ID, URI or link, see `find_playlist`
+    Playlist(String),
+    SavedTracks,
+    SavedAlbums,
+}
+
+/// `spt export`: write `source` to `output`, or to standard output without one
+pub fn run(
+    spotify: &dyn SpotifyApi,
+    source: &ExportSource,
+    format: ExportFormat,
+    output: Option<&Path>,
+) -> Result<(), failure::Error> {
+    let export = match source {
+        ExportSource::Playlist(id_or_name) => {
+            let (id, name) = find_playlist(spotify, id_or_name)?;
+            playlist(spotify, &id, &name)?
+        }
+        ExportSource::SavedTracks => saved_tracks(spotify)?,
+        ExportSource::SavedAlbums => saved_albums(spotify)?,
+    };
+    let rendered = export.render(format)?;
+    match output {
+        Some(path) => {
+            fs::write(path, rendered)?;
+            eprintln!(
+                "Exported {} tracks to {}",
+                export.tracks.len(),
+                path.display()
+            );
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+/// Find a playlist by name, ID, URI or link. Names are matched without case among the user's
+/// playlists, IDs of other playlists work too
+pub fn find_playlist(
+    spotify: &dyn SpotifyApi,
+    id_or_name: &str,
+) -> Result<(String, String), failure::Error> {
+    let id = match SpotifyUri::parse(id_or_name) {
+        Some(uri) if uri.kind == UriKind::Playlist => uri.id,
+        Some(_) => return Err(format_err!("{} is not a playlist", id_or_name)),
+        None => id_or_name.trim().to_string(),
+    };
+    let playlists = all_playlists(spotify)?;
+    if let Some(playlist) = playlists
+        .iter()
+        .find(|playlist| playlist.id == id)
+        .or_else(|| {
+            playlists
+                .iter()
+                .find(|playlist| playlist.name.to_lowercase() == id.to_lowercase())
+        })
+    {
+        return Ok((playlist.id.clone(), playlist.name.clone()));
+    }
+
+    // Spotify IDs are 22 characters of base 62
+    if id.len() == 22 && id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        Ok((id.clone(), id))
+    } else {
+        Err(format_err!(
+            "No playlist named {} in your library",
+            id_or_name
+        ))
+    }
+}
+
+pub fn playlist(
+    spotify: &dyn SpotifyApi,
+    playlist_id: &str,
+    name: &str,
+) -> Result<Export, failure::Error> {
+    let items = all_pages(|limit, offset| {
+        spotify.user_playlist_tracks(
+            "spotify",
+            playlist_id,
+            None,
+            Some(limit),
+            Some(offset),
+            None,
+        )
+    })?;
+    Ok(Export {
+        name: name.to_string(),
+        tracks: items
+            .iter()
+            .map(|item| {
+                ExportTrack::from_track(
+                    &item.track,
+                    Some(item.added_at.format(ADDED_AT_FORMAT).to_string()),
+                )
+            })
+            .collect(),
+    })
+}
+
+pub fn saved_tracks(spotify: &dyn SpotifyApi) -> Result<Export, failure::Error> {
+    let items =
+        all_pages(|limit, offset| spotify.current_user_saved_tracks(Some(limit), Some(offset)))?;
+    Ok(Export {
+        name: "Liked Songs".to_string(),
+        tracks: items
+            .iter()
+            .map(|item| {
+                ExportTrack::from_track(
+                    &item.track,
+                    Some(item.added_at.format(ADDED_AT_FORMAT).to_string()),
+                )
+            })
+            .collect(),
+    })
+}
+
+/// Every track of every saved album, with when the album was saved. Album tracks come without
+/// their ISRC, so they are looked up in full
+pub fn saved_albums(spotify: &dyn SpotifyApi) -> Result<Export, failure::Error> {
+    let albums =
+        all_pages(|limit, offset| spotify.current_user_saved_albums(Some(limit), Some(offset)))?;
+
+    let mut tracks = vec![];
+    for saved_album in albums {
+        let album = &saved_album.album;
+        let added_at = saved_album.added_at.format(ADDED_AT_FORMAT).to_string();
+        // The album comes with its first page of tracks
+        let mut album_tracks = album.tracks.items.clone();
+        if album.tracks.next.is_some() && album_tracks.len() < album.tracks.total as usize {
+            album_tracks.extend(all_pages(|limit, offset| {
+                spotify.album_track(
+                    &album.id,
+                    Some(limit),
+                    Some(offset + album.tracks.items.len() as u32),
+                )
+            })?);
+        }
+
+        let ids = album_tracks
+            .iter()
+            .filter_map(|track| track.id.as_deref())
+            .collect::<Vec<&str>>();
+        for chunk in ids.chunks(TRACKS_LIMIT) {
+            let full_tracks = spotify.tracks(chunk.to_vec(), None)?;
+            tracks.extend(
+                full_tracks
+                    .tracks
+                    .iter()
+                    .map(|track| ExportTrack::from_track(track, Some(added_at.clone()))),
+            );
+        }
+    }
+    Ok(Export {
+        name: "Saved Albums".to_string(),
+        tracks,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spotify_api::fake::{self, FakeSpotify};
+
+    fn export() -> Export {
+        Export {
+            name: "Road \"trip\"".to_string(),
+            tracks: vec![
+                ExportTrack {
+                    title: "Song, with a comma".to_string(),
+                    artists: vec!["Band".to_string(), "Singer".to_string()],
+                    album: "Album & more".to_string(),
+                    duration_ms: 181_500,
+                    isrc: Some("USRC17607839".to_string()),
+                    uri: "spotify:track:1".to_string(),
+                    added_at: Some("2020-01-01T00:00:00Z".to_string()),
+                },
+                ExportTrack {
+                    title: "Local".to_string(),
+                    artists: vec![],
+                    album: String::new(),
+                    duration_ms: 60_000,
+                    isrc: None,
+                    uri: "spotify:local:::Local:60".to_string(),
+                    added_at: None,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let export = export();
+        assert_eq!(
+            export.render(ExportFormat::Csv).unwrap(),
+            "title,artists,album,duration_ms,isrc,uri,added_at\r\n\
+             \"Song, with a comma\",\"Band, Singer\",Album & more,181500,USRC17607839,\
+             spotify:track:1,2020-01-01T00:00:00Z\r\n\
+             Local,,,60000,,spotify:local:::Local:60,\r\n"
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&export.render(ExportFormat::Json).unwrap()).unwrap();
+        assert_eq!(json["name"], "Road \"trip\"");
+        assert_eq!(json["tracks"][0]["artists"][1], "Singer");
+        assert_eq!(json["tracks"][1]["isrc"], serde_json::Value::Null);
+
+        assert_eq!(
+            export.render(ExportFormat::M3u).unwrap(),
+            "#EXTM3U\n#PLAYLIST:Road \"trip\"\n\
+             #EXTINF:181,Band, Singer - Song, with a comma\n#EXTALB:Album & more\nspotify:track:1\n\
+             #EXTINF:60, - Local\n#EXTALB:\nspotify:local:::Local:60\n"
+        );
+
+        let xspf = export.render(ExportFormat::Xspf).unwrap();
+        assert!(xspf.contains("<title>Road &quot;trip&quot;</title>"));
+        assert!(xspf.contains("<identifier>urn:isrc:USRC17607839</identifier>"));
+        assert!(xspf.contains("<album>Album &amp; more</album>"));
+        assert_eq!(xspf.matches("<track>").count(), 2);
+
+        assert_eq!(export.file_name(ExportFormat::Csv), "Road _trip_.csv");
+    }
+
+    #[test]
+    fn test_fetches_every_page() {
+        let spotify = FakeSpotify::new();
+        let mut page = fake::page(vec![
+            fake::saved_track(fake::track("t1", "One", "Band")),
+            fake::saved_track(fake::track("t2", "Two", "Band")),
+        ]);
+        // The fake returns the same page each time, four tracks in all
+        page["total"] = json!(4);
+        page["next"] = json!("next page");
+        spotify.respond("current_user_saved_tracks", page);
+
+        let export = saved_tracks(&spotify).unwrap();
+        assert_eq!(export.tracks.len(), 4);
+        assert_eq!(
+            export.tracks[0].added_at.as_deref(),
+            Some("2020-01-01T00:00:00Z")
+        );
+        assert_eq!(
+            spotify.calls_to("current_user_saved_tracks"),
+            vec!["Some(50), Some(0)", "Some(50), Some(2)"]
+        );
+    }
+
+    #[test]
+    fn test_find_playlist() {
+        let spotify = FakeSpotify::new();
+        spotify.respond(
+            "current_user_playlists",
+            fake::page(vec![
+                fake::playlist("p1", "Road Trip", "alice"),
+                fake::playlist("p2", "Focus", "alice"),
+            ]),
+        );
+        assert_eq!(
+            find_playlist(&spotify, "road trip").unwrap(),
+            ("p1".to_string(), "Road Trip".to_string())
+        );
+        assert_eq!(
+            find_playlist(&spotify, "spotify:playlist:p2").unwrap().1,
+            "Focus"
+        );
+        // Playlists outside the library by ID
+        assert_eq!(
+            find_playlist(&spotify, "37i9dQZF1DXcBWIGoYBM5M").unwrap().0,
+            "37i9dQZF1DXcBWIGoYBM5M"
+        );
+        assert!(find_playlist(&spotify, "Nothing like it").is_err());
+        assert!(find_playlist(&spotify, "spotify:album:a1").is_err());
+    }
+
+    #[test]
+    fn test_run() {
+        let spotify = FakeSpotify::new();
+        spotify.respond(
+            "current_user_saved_tracks",
+            fake::page(vec![fake::saved_track(fake::track("t1", "One", "Band"))]),
+        );
+        let path = std::env::temp_dir().join(format!(
+            "spotify-tui-export-test-{}.m3u",
+            std::process::id()
+        ));
+
+        run(
+            &spotify,
+            &ExportSource::SavedTracks,
+            ExportFormat::M3u,
+            Some(&path),
+        )
+        .unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        assert!(written.starts_with("#EXTM3U\n#PLAYLIST:Liked Songs\n"));
+        assert!(written.contains("spotify:track:t1"));
+        fs::remove_file(path).unwrap();
+    }
+}
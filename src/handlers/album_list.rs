@@ -58,6 +58,7 @@ pub fn handler(key: Key, app: &mut App) {
         Key::Ctrl('d') => app.get_current_user_saved_albums_next(),
         Key::Ctrl('u') => app.get_current_user_saved_albums_previous(),
         Key::Char('D') => app.current_user_saved_album_delete(),
+        k if k == app.user_config.keys.export => app.export_saved_albums(),
         _ => {}
     };
 }
@@ -65,6 +66,9 @@ pub fn handler(key: Key, app: &mut App) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::spotify_api::fake;
+    use serde_json::json;
+    use std::{env, fs};
 
     #[test]
     fn on_left_press() {
@@ -89,4 +93,43 @@ mod tests {
         let current_route = app.get_current_route();
         assert_eq!(current_route.active_block, ActiveBlock::Empty);
     }
+
+    #[test]
+    fn on_export() {
+        let album = fake::full_album(
+            "a1",
+            "Green",
+            "Band",
+            vec![fake::TrackBuilder::new("t1", "Leaf", "Band").build_simplified()],
+        );
+        let track = fake::TrackBuilder::new("t1", "Leaf", "Band")
+            .isrc("USRC17607839")
+            .build();
+        let (mut app, spotify) = fake::app_with_fake();
+        spotify
+            .respond(
+                "current_user_saved_albums",
+                fake::page(vec![
+                    json!({ "added_at": "2020-01-01T00:00:00Z", "album": album }),
+                ]),
+            )
+            .respond("tracks", json!({ "tracks": [track] }));
+
+        let dir = env::temp_dir().join(format!("spt-export-{}", std::process::id()));
+        app.user_config.behavior.export_dir = Some(dir.to_string_lossy().into_owned());
+        handler(Key::Char('E'), &mut app);
+
+        let path = dir.join("Saved Albums.csv");
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "title,artists,album,duration_ms,isrc,uri,added_at\r\n\
+             Leaf,Band,Leaf album,180000,USRC17607839,spotify:track:t1,2020-01-01T00:00:00Z\r\n"
+        );
+        assert_eq!(
+            app.status_message,
+            Some(format!("Exported 1 tracks to {}", path.display()))
+        );
+        assert_eq!(spotify.calls_to("tracks"), vec!["[\"t1\"], None"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
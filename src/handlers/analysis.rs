@@ -1,7 +1,60 @@
-use crate::{app::App, event::Key};
+use super::common_key_events;
+use crate::{
+    app::{App, ANALYSIS_VIEW_MODES},
+    event::Key,
+};
 
-pub fn handler(key: Key, _app: &mut App) {
+pub fn handler(key: Key, app: &mut App) {
     match key {
+        k if common_key_events::right_event(k) || k == Key::Tab => {
+            app.analysis_view_mode = app.analysis_view_mode.next();
+        }
+        k if common_key_events::left_event(k) => {
+            app.analysis_view_mode = app.analysis_view_mode.previous();
+        }
+        Key::Char(c) => {
+            // Number keys jump straight to a mode
+            if let Some(mode) = c
+                .to_digit(10)
+                .and_then(|digit| ANALYSIS_VIEW_MODES.get((digit as usize).wrapping_sub(1)))
+            {
+                app.analysis_view_mode = *mode;
+            }
+        }
         _ => {}
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::AnalysisViewMode;
+
+    #[test]
+    fn on_cycle_modes() {
+        let mut app = App::new();
+
+        handler(Key::Right, &mut app);
+        assert_eq!(app.analysis_view_mode, AnalysisViewMode::Timbre);
+
+        handler(Key::Left, &mut app);
+        handler(Key::Left, &mut app);
+        assert_eq!(app.analysis_view_mode, AnalysisViewMode::BeatPulse);
+
+        handler(Key::Tab, &mut app);
+        assert_eq!(app.analysis_view_mode, AnalysisViewMode::Pitches);
+    }
+
+    #[test]
+    fn on_number_press() {
+        let mut app = App::new();
+
+        handler(Key::Char('4'), &mut app);
+        assert_eq!(app.analysis_view_mode, AnalysisViewMode::Sections);
+
+        // Out of range numbers keep the current mode
+        handler(Key::Char('0'), &mut app);
+        handler(Key::Char('9'), &mut app);
+        assert_eq!(app.analysis_view_mode, AnalysisViewMode::Sections);
+    }
+}
@@ -0,0 +1,97 @@
+use super::common_key_events;
+use crate::{
+    app::{App, BrowseSection},
+    event::Key,
+};
+
+pub fn handler(key: Key, app: &mut App) {
+    let items = app.get_browse_items();
+
+    match key {
+        k if common_key_events::left_event(k) => common_key_events::handle_left_event(app),
+        Key::Tab => {
+            app.browse.section = app.browse.section.next();
+            app.browse.selected_index = 0;
+            app.load_browse_section();
+        }
+        k if common_key_events::down_event(k) => {
+            app.browse.selected_index =
+                common_key_events::on_down_press_handler(&items, Some(app.browse.selected_index));
+        }
+        k if common_key_events::up_event(k) => {
+            app.browse.selected_index =
+                common_key_events::on_up_press_handler(&items, Some(app.browse.selected_index));
+        }
+        k if common_key_events::high_event(k) => {
+            app.browse.selected_index = common_key_events::on_high_press_handler();
+        }
+        k if common_key_events::middle_event(k) && !items.is_empty() => {
+            app.browse.selected_index = common_key_events::on_middle_press_handler(&items);
+        }
+        k if common_key_events::low_event(k) && !items.is_empty() => {
+            app.browse.selected_index = common_key_events::on_low_press_handler(&items);
+        }
+        Key::Enter => on_enter(app),
+        Key::Ctrl('d') => app.get_browse_next_page(),
+        Key::Ctrl('u') => app.get_browse_previous_page(),
+        _ => {}
+    }
+}
+
+fn on_enter(app: &mut App) {
+    let index = app.browse.selected_index;
+    match app.browse.section {
+        BrowseSection::Categories => {
+            if let Some(category) = app
+                .browse
+                .categories
+                .get_results(None)
+                .and_then(|page| page.items.get(index))
+                .cloned()
+            {
+                app.get_category_playlists(category, 0);
+            }
+        }
+        BrowseSection::NewReleases => {
+            if let Some(album) = app
+                .browse
+                .new_releases
+                .get_results(None)
+                .and_then(|page| page.items.get(index))
+                .cloned()
+            {
+                app.get_album_tracks(album);
+            }
+        }
+        BrowseSection::FeaturedPlaylists => {
+            if let Some(playlist) = app
+                .browse
+                .featured_playlists
+                .get_results(None)
+                .and_then(|page| page.items.get(index))
+                .cloned()
+            {
+                app.get_browse_playlist_tracks(playlist);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn on_switch_section() {
+        let mut app = App::new();
+        app.browse.selected_index = 3;
+
+        handler(Key::Tab, &mut app);
+        assert_eq!(app.browse.section, BrowseSection::NewReleases);
+        assert_eq!(app.browse.selected_index, 0);
+
+        handler(Key::Tab, &mut app);
+        handler(Key::Tab, &mut app);
+        assert_eq!(app.browse.section, BrowseSection::Categories);
+    }
+}
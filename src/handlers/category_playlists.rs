@@ -0,0 +1,46 @@
+use super::common_key_events;
+use crate::{app::App, event::Key};
+
+pub fn handler(key: Key, app: &mut App) {
+    let playlists = app
+        .browse
+        .category_playlists
+        .get_results(None)
+        .map(|page| page.items.clone())
+        .unwrap_or_default();
+
+    match key {
+        k if common_key_events::left_event(k) => common_key_events::handle_left_event(app),
+        k if common_key_events::down_event(k) => {
+            app.browse.category_playlist_index = common_key_events::on_down_press_handler(
+                &playlists,
+                Some(app.browse.category_playlist_index),
+            );
+        }
+        k if common_key_events::up_event(k) => {
+            app.browse.category_playlist_index = common_key_events::on_up_press_handler(
+                &playlists,
+                Some(app.browse.category_playlist_index),
+            );
+        }
+        k if common_key_events::high_event(k) => {
+            app.browse.category_playlist_index = common_key_events::on_high_press_handler();
+        }
+        k if common_key_events::middle_event(k) && !playlists.is_empty() => {
+            app.browse.category_playlist_index =
+                common_key_events::on_middle_press_handler(&playlists);
+        }
+        k if common_key_events::low_event(k) && !playlists.is_empty() => {
+            app.browse.category_playlist_index =
+                common_key_events::on_low_press_handler(&playlists);
+        }
+        Key::Enter => {
+            if let Some(playlist) = playlists.get(app.browse.category_playlist_index) {
+                app.get_browse_playlist_tracks(playlist.clone());
+            }
+        }
+        Key::Ctrl('d') => app.get_category_playlists_next_page(),
+        Key::Ctrl('u') => app.get_category_playlists_previous_page(),
+        _ => {}
+    }
+}
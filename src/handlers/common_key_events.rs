@@ -119,6 +119,15 @@ pub fn handle_right_event(app: &mut App) {
                     Some(ActiveBlock::Podcasts),
                 );
             }
+            RouteId::Stats => {
+                app.set_current_route_state(Some(ActiveBlock::Stats), Some(ActiveBlock::Stats));
+            }
+            RouteId::Top => {
+                app.set_current_route_state(Some(ActiveBlock::Top), Some(ActiveBlock::Top));
+            }
+            RouteId::Import => {
+                app.set_current_route_state(Some(ActiveBlock::Import), Some(ActiveBlock::Import));
+            }
             RouteId::Recommendations => {
                 app.set_current_route_state(
                     Some(ActiveBlock::TrackTable),
@@ -159,7 +168,26 @@ pub fn handle_right_event(app: &mut App) {
             RouteId::Home => {
                 app.set_current_route_state(Some(ActiveBlock::Home), Some(ActiveBlock::Home));
             }
+            RouteId::Lyrics => {
+                app.set_current_route_state(Some(ActiveBlock::Lyrics), Some(ActiveBlock::Lyrics));
+            }
+            RouteId::Browse => {
+                app.set_current_route_state(Some(ActiveBlock::Browse), Some(ActiveBlock::Browse));
+            }
+            RouteId::CategoryPlaylists => {
+                app.set_current_route_state(
+                    Some(ActiveBlock::CategoryPlaylists),
+                    Some(ActiveBlock::CategoryPlaylists),
+                );
+            }
+            RouteId::RecommendationsBuilder => {
+                app.set_current_route_state(
+                    Some(ActiveBlock::RecommendationsBuilder),
+                    Some(ActiveBlock::RecommendationsBuilder),
+                );
+            }
             RouteId::SelectedDevice => {}
+            RouteId::SelectProfile => {}
             RouteId::Error => {}
             RouteId::Analysis => {}
         },
@@ -19,10 +19,14 @@ pub fn handler(key: Key, app: &mut App) {
             | ActiveBlock::AlbumList
             | ActiveBlock::AlbumTracks
             | ActiveBlock::Artists
+            | ActiveBlock::Browse
+            | ActiveBlock::CategoryPlaylists
             | ActiveBlock::Home
+            | ActiveBlock::Lyrics
             | ActiveBlock::MadeForYou
             | ActiveBlock::MyPlaylists
             | ActiveBlock::RecentlyPlayed
+            | ActiveBlock::RecommendationsBuilder
             | ActiveBlock::TrackTable => {
                 app.set_current_route_state(None, Some(ActiveBlock::PlayBar));
             }
@@ -42,9 +46,13 @@ pub fn handler(key: Key, app: &mut App) {
             | ActiveBlock::AlbumList
             | ActiveBlock::AlbumTracks
             | ActiveBlock::Artists
+            | ActiveBlock::Browse
+            | ActiveBlock::CategoryPlaylists
             | ActiveBlock::Home
+            | ActiveBlock::Lyrics
             | ActiveBlock::MadeForYou
             | ActiveBlock::RecentlyPlayed
+            | ActiveBlock::RecommendationsBuilder
             | ActiveBlock::TrackTable => {
                 app.set_current_route_state(None, Some(ActiveBlock::Library));
             }
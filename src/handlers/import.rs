@@ -0,0 +1,100 @@
+use super::{super::app::App, common_key_events};
+use crate::event::Key;
+
+pub fn handler(key: Key, app: &mut App) {
+    let import = match &mut app.import {
+        Some(import) => import,
+        None => return,
+    };
+    let candidates = match import.reviewing() {
+        Some(item) => item.candidates.len(),
+        None => {
+            // Only the report is left
+            if common_key_events::left_event(key) {
+                common_key_events::handle_left_event(app);
+            }
+            return;
+        }
+    };
+
+    match key {
+        k if common_key_events::down_event(k) => {
+            import.selected_candidate = (import.selected_candidate + 1) % candidates.max(1);
+        }
+        k if common_key_events::up_event(k) => {
+            import.selected_candidate =
+                (import.selected_candidate + candidates.max(1) - 1) % candidates.max(1);
+        }
+        Key::Enter => import.accept(),
+        Key::Char('s') => import.skip(),
+        _ => {}
+    }
+
+    if import.review_index.is_none() {
+        app.finish_import();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        import::{Candidate, Decision, Import, ImportEntry, ImportItem},
+        spotify_api::fake,
+    };
+    use serde_json::json;
+
+    fn item(title: &str, candidates: &[&str]) -> ImportItem {
+        ImportItem {
+            entry: ImportEntry {
+                title: title.to_string(),
+                ..ImportEntry::default()
+            },
+            candidates: candidates
+                .iter()
+                .map(|id| Candidate {
+                    track: serde_json::from_value(fake::track(id, title, "Band")).unwrap(),
+                    score: 0.5,
+                })
+                .collect(),
+            decision: Decision::Pending,
+        }
+    }
+
+    #[test]
+    fn on_review() {
+        let (mut app, spotify) = fake::app_with_fake();
+        spotify
+            .respond("current_user", fake::user("alice"))
+            .respond(
+                "user_playlist_create",
+                fake::full_playlist("new", "Mix", "alice"),
+            )
+            .respond("user_playlist_add_tracks", json!({ "snapshot_id": "s" }))
+            .respond("current_user_playlists", fake::page(vec![]));
+
+        app.import = Some(Import::new(
+            "Mix",
+            vec![item("One", &["t1", "t2"]), item("Two", &["t3"])],
+        ));
+
+        // Pick the alternative for the first, wrapping around from the top
+        handler(Key::Up, &mut app);
+        handler(Key::Enter, &mut app);
+        assert_eq!(
+            app.import.as_ref().unwrap().items[0].decision,
+            Decision::Accepted(1)
+        );
+        assert!(spotify.calls_to("user_playlist_create").is_empty());
+
+        handler(Key::Char('s'), &mut app);
+        let report = app.import.as_ref().unwrap().report.clone().unwrap();
+        assert_eq!(report.added, 1);
+        assert_eq!(report.skipped, vec!["Two"]);
+        assert_eq!(
+            spotify.calls_to("user_playlist_add_tracks"),
+            vec!["\"alice\", \"new\", [\"t2\"], None"]
+        );
+        assert!(app.playlists.is_some());
+    }
+}
@@ -84,7 +84,12 @@ pub fn handler(key: Key, app: &mut App) {
                 }
 
                 // Can I run these functions in parellel?
-                match spotify.search_track(&input_str, app.small_search_limit, 0, country) {
+                match spotify.search_track(
+                    &input_str,
+                    Some(app.small_search_limit),
+                    Some(0),
+                    country,
+                ) {
                     Ok(result) => {
                         app.set_tracks_to_table(result.tracks.items.clone());
                         app.search_results.tracks = Some(result);
@@ -94,7 +99,12 @@ pub fn handler(key: Key, app: &mut App) {
                     }
                 }
 
-                match spotify.search_artist(&input_str, app.small_search_limit, 0, country) {
+                match spotify.search_artist(
+                    &input_str,
+                    Some(app.small_search_limit),
+                    Some(0),
+                    country,
+                ) {
                     Ok(result) => {
                         app.search_results.artists = Some(result);
                     }
@@ -103,7 +113,12 @@ pub fn handler(key: Key, app: &mut App) {
                     }
                 }
 
-                match spotify.search_album(&input_str, app.small_search_limit, 0, country) {
+                match spotify.search_album(
+                    &input_str,
+                    Some(app.small_search_limit),
+                    Some(0),
+                    country,
+                ) {
                     Ok(result) => {
                         app.search_results.albums = Some(result);
                     }
@@ -112,7 +127,12 @@ pub fn handler(key: Key, app: &mut App) {
                     }
                 }
 
-                match spotify.search_playlist(&input_str, app.small_search_limit, 0, country) {
+                match spotify.search_playlist(
+                    &input_str,
+                    Some(app.small_search_limit),
+                    Some(0),
+                    country,
+                ) {
                     Ok(result) => {
                         app.search_results.playlists = Some(result);
                     }
@@ -157,6 +177,8 @@ fn compute_character_width(character: char) -> u16 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::spotify_api::fake;
+    use serde_json::json;
 
     fn str_to_vec_char(s: &str) -> Vec<char> {
         String::from(s).chars().collect()
@@ -169,6 +191,32 @@ mod tests {
         assert_eq!(1, compute_character_width('ç'));
     }
 
+    #[test]
+    fn test_input_handler_searches_on_enter() {
+        let (mut app, spotify) = fake::app_with_fake();
+        spotify
+            .respond(
+                "search_track",
+                json!({ "tracks": fake::page(vec![fake::track("1", "Song", "Band")]) }),
+            )
+            .respond("search_artist", json!({ "artists": fake::page(vec![]) }))
+            .respond("search_album", json!({ "albums": fake::page(vec![]) }))
+            .respond(
+                "search_playlist",
+                json!({ "playlists": fake::page(vec![]) }),
+            );
+        app.user = serde_json::from_value(fake::user("alice")).ok();
+        app.input = str_to_vec_char("song");
+
+        handler(Key::Enter, &mut app);
+
+        let search_calls = spotify.calls_to("search_track");
+        assert_eq!(search_calls.len(), 1);
+        assert!(search_calls[0].starts_with("\"song\""));
+        assert_eq!(app.track_table.tracks[0].name, "Song");
+        assert_eq!(app.get_current_route().id, RouteId::Search);
+    }
+
     #[test]
     fn test_input_handler_clear_input_on_ctrl_u() {
         let mut app = App::new();
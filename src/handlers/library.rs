@@ -41,14 +41,16 @@ pub fn handler(key: Key, app: &mut App) {
                 app.get_made_for_you();
                 app.push_navigation_stack(RouteId::MadeForYou, ActiveBlock::MadeForYou);
             }
+            // Browse,
+            1 => app.get_browse(),
             // Recently Played,
-            1 => {
+            2 => {
                 if let Some(spotify) = &app.spotify {
                     match spotify
                         // Seems I need to clone here becuase `current_user_recently_played`
                         // consumes `self`?
                         .clone()
-                        .current_user_recently_played(app.large_search_limit)
+                        .current_user_recently_played(Some(app.large_search_limit))
                     {
                         Ok(result) => {
                             app.recently_played.result = Some(result.clone());
@@ -73,24 +75,28 @@ pub fn handler(key: Key, app: &mut App) {
                 };
             }
             // Liked Songs,
-            2 => {
+            3 => {
                 app.get_current_user_saved_tracks(None);
                 app.push_navigation_stack(RouteId::TrackTable, ActiveBlock::TrackTable);
             }
             // Albums,
-            3 => {
+            4 => {
                 app.get_current_user_saved_albums(Some(0));
                 app.push_navigation_stack(RouteId::AlbumList, ActiveBlock::AlbumList);
             }
             //  Artists,
-            4 => {
+            5 => {
                 app.get_artists(None);
                 app.push_navigation_stack(RouteId::Artists, ActiveBlock::Artists);
             }
             // Podcasts,
-            5 => {
+            6 => {
                 app.push_navigation_stack(RouteId::Podcasts, ActiveBlock::Podcasts);
             }
+            // Top,
+            7 => app.get_top(),
+            // Stats,
+            8 => app.get_stats(),
             // This is required because Rust can't tell if this pattern in exhaustive
             _ => {}
         },
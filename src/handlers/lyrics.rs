@@ -0,0 +1,63 @@
+use super::{super::app::App, common_key_events};
+use crate::event::Key;
+
+const LARGE_SCROLL: u16 = 10;
+const SMALL_SCROLL: u16 = 1;
+
+pub fn handler(key: Key, app: &mut App) {
+    // Synced lyrics scroll along with the playing track
+    let is_scrollable = app
+        .lyrics
+        .as_ref()
+        .is_some_and(|lyrics| !lyrics.is_synced());
+
+    match key {
+        k if common_key_events::left_event(k) => common_key_events::handle_left_event(app),
+        k if common_key_events::down_event(k) && is_scrollable => {
+            app.lyrics_scroll += SMALL_SCROLL;
+        }
+        k if common_key_events::up_event(k) && is_scrollable => {
+            app.lyrics_scroll = app.lyrics_scroll.saturating_sub(SMALL_SCROLL);
+        }
+        Key::Ctrl('d') if is_scrollable => {
+            app.lyrics_scroll += LARGE_SCROLL;
+        }
+        Key::Ctrl('u') if is_scrollable => {
+            app.lyrics_scroll = app.lyrics_scroll.saturating_sub(LARGE_SCROLL);
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lyrics::Lyrics;
+
+    #[test]
+    fn on_scroll_plain_lyrics() {
+        let mut app = App::new();
+        app.lyrics = Some(Lyrics::parse("One more time\nWe're gonna celebrate"));
+
+        handler(Key::Down, &mut app);
+        assert_eq!(app.lyrics_scroll, SMALL_SCROLL);
+
+        handler(Key::Ctrl('d'), &mut app);
+        assert_eq!(app.lyrics_scroll, SMALL_SCROLL + LARGE_SCROLL);
+
+        handler(Key::Ctrl('u'), &mut app);
+        handler(Key::Up, &mut app);
+        handler(Key::Up, &mut app);
+        assert_eq!(app.lyrics_scroll, 0);
+    }
+
+    #[test]
+    fn on_scroll_synced_lyrics() {
+        let mut app = App::new();
+        app.lyrics = Some(Lyrics::parse("[00:01.00]One more time"));
+
+        handler(Key::Down, &mut app);
+        handler(Key::Ctrl('d'), &mut app);
+        assert_eq!(app.lyrics_scroll, 0);
+    }
+}
@@ -3,24 +3,36 @@ mod album_tracks;
 mod analysis;
 mod artist;
 mod artists;
+mod browse;
+mod category_playlists;
 mod common_key_events;
 mod empty;
 mod error_screen;
 mod help_menu;
 mod home;
+mod import;
 mod input;
 mod library;
+mod lyrics;
 mod made_for_you;
 mod playbar;
 mod playlist;
 mod podcasts;
 mod recently_played;
+mod recommendations_builder;
 mod search_results;
 mod select_device;
+mod select_profile;
+mod stats;
+mod top;
+mod track_info;
 mod track_table;
 
-use super::app::{ActiveBlock, App, ArtistBlock, RouteId, SearchResultBlock};
-use crate::event::Key;
+use super::app::{
+    ActiveBlock, AlbumTableContext, App, ArtistBlock, RouteId, SearchResultBlock, TopSection,
+};
+use crate::{event::Key, recommendations::RecommendationSeed};
+use rspotify::spotify::model::artist::FullArtist;
 
 pub use input::handler as input_handler;
 
@@ -83,10 +95,59 @@ pub fn handle_app(key: Key, app: &mut App) {
         _ if key == app.user_config.keys.audio_analysis => {
             app.get_audio_analysis();
         }
+        _ if key == app.user_config.keys.lyrics && !has_own_keys(app) => {
+            app.get_lyrics();
+        }
+        _ if key == app.user_config.keys.track_info && has_selectable_item(app) => {
+            handle_track_info(app);
+        }
+        _ if key == app.user_config.keys.recommendations_builder && !has_own_keys(app) => {
+            app.open_recommendations_builder();
+        }
+        _ if key == app.user_config.keys.add_recommendation_seed && has_selectable_item(app) => {
+            handle_add_recommendation_seed(app);
+        }
+        _ if key == app.user_config.keys.toggle_radio && !has_own_keys(app) => {
+            app.toggle_radio();
+        }
+        _ if key == app.user_config.keys.switch_profile && !has_own_keys(app) => {
+            app.handle_get_profiles();
+        }
         _ => handle_block_events(key, app),
     }
 }
 
+// Popups and screens with letter keys of their own, which the newer app-wide keys leave alone
+fn has_own_keys(app: &App) -> bool {
+    matches!(
+        app.get_current_route().active_block,
+        ActiveBlock::Error
+            | ActiveBlock::HelpMenu
+            | ActiveBlock::Import
+            | ActiveBlock::RecommendationsBuilder
+            | ActiveBlock::SelectDevice
+            | ActiveBlock::SelectProfile
+            | ActiveBlock::TrackInfo
+    )
+}
+
+// Blocks showing a track or artist for the track info and recommendation seed keys to act on
+fn has_selectable_item(app: &App) -> bool {
+    matches!(
+        app.get_current_route().active_block,
+        ActiveBlock::AlbumTracks
+            | ActiveBlock::Analysis
+            | ActiveBlock::ArtistBlock
+            | ActiveBlock::Artists
+            | ActiveBlock::Lyrics
+            | ActiveBlock::PlayBar
+            | ActiveBlock::RecentlyPlayed
+            | ActiveBlock::SearchResultBlock
+            | ActiveBlock::Top
+            | ActiveBlock::TrackTable
+    )
+}
+
 // Handle event for the current active block
 fn handle_block_events(key: Key, app: &mut App) {
     let current_route = app.get_current_route();
@@ -115,6 +176,9 @@ fn handle_block_events(key: Key, app: &mut App) {
         ActiveBlock::SelectDevice => {
             select_device::handler(key, app);
         }
+        ActiveBlock::SelectProfile => {
+            select_profile::handler(key, app);
+        }
         ActiveBlock::SearchResultBlock => {
             search_results::handler(key, app);
         }
@@ -148,6 +212,30 @@ fn handle_block_events(key: Key, app: &mut App) {
         ActiveBlock::PlayBar => {
             playbar::handler(key, app);
         }
+        ActiveBlock::Lyrics => {
+            lyrics::handler(key, app);
+        }
+        ActiveBlock::TrackInfo => {
+            track_info::handler(key, app);
+        }
+        ActiveBlock::Browse => {
+            browse::handler(key, app);
+        }
+        ActiveBlock::CategoryPlaylists => {
+            category_playlists::handler(key, app);
+        }
+        ActiveBlock::RecommendationsBuilder => {
+            recommendations_builder::handler(key, app);
+        }
+        ActiveBlock::Stats => {
+            stats::handler(key, app);
+        }
+        ActiveBlock::Top => {
+            top::handler(key, app);
+        }
+        ActiveBlock::Import => {
+            import::handler(key, app);
+        }
     }
 }
 
@@ -164,6 +252,11 @@ fn handle_escape(app: &mut App) {
         ActiveBlock::Error => {
             app.pop_navigation_stack();
         }
+        ActiveBlock::TrackInfo => {
+            // Close the popup like Enter does, rather than leaving nothing selected
+            let hovered_block = app.get_current_route().hovered_block;
+            app.set_current_route_state(Some(hovered_block), None);
+        }
         _ => {
             app.set_current_route_state(Some(ActiveBlock::Empty), None);
         }
@@ -191,3 +284,138 @@ fn handle_jump_to_artist_album(app: &mut App) {
         }
     };
 }
+
+fn handle_track_info(app: &mut App) {
+    if let Some(RecommendationSeed::Track { id, .. }) = get_selected_track(app) {
+        app.get_track_info(&id);
+    }
+}
+
+fn handle_add_recommendation_seed(app: &mut App) {
+    if let Some(seed) = get_selected_artist(app).or_else(|| get_selected_track(app)) {
+        app.add_recommendation_seed(seed);
+    }
+}
+
+fn get_selected_artist(app: &App) -> Option<RecommendationSeed> {
+    let artist_seed = |artist: &FullArtist| RecommendationSeed::Artist {
+        id: artist.id.clone(),
+        name: artist.name.clone(),
+    };
+
+    match app.get_current_route().active_block {
+        ActiveBlock::Artists => app.artists.get(app.artists_list_index).map(artist_seed),
+        ActiveBlock::Top if app.top.section == TopSection::Artists => app
+            .top
+            .artists
+            .get(app.top.selected_artist_index)
+            .map(artist_seed),
+        ActiveBlock::ArtistBlock => app.artist.as_ref().and_then(|artist| {
+            if artist.artist_selected_block == ArtistBlock::RelatedArtists {
+                artist
+                    .related_artists
+                    .get(artist.selected_related_artist_index)
+                    .map(artist_seed)
+            } else {
+                None
+            }
+        }),
+        ActiveBlock::SearchResultBlock
+            if app.search_results.selected_block == SearchResultBlock::ArtistSearch =>
+        {
+            match (
+                &app.search_results.artists,
+                app.search_results.selected_artists_index,
+            ) {
+                (Some(result), Some(index)) => result.artists.items.get(index).map(artist_seed),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+// The track selected in the active block, falling back to the one currently playing
+fn get_selected_track(app: &App) -> Option<RecommendationSeed> {
+    fn track_seed(id: &Option<String>, name: &str) -> Option<RecommendationSeed> {
+        id.as_ref().map(|id| RecommendationSeed::Track {
+            id: id.clone(),
+            name: name.to_string(),
+        })
+    }
+
+    let selected_track = match app.get_current_route().active_block {
+        ActiveBlock::TrackTable => app
+            .track_table
+            .tracks
+            .get(app.track_table.selected_index)
+            .and_then(|track| track_seed(&track.id, &track.name)),
+        ActiveBlock::Top if app.top.section == TopSection::Tracks => app
+            .top
+            .tracks
+            .get(app.top.selected_track_index)
+            .and_then(|track| track_seed(&track.id, &track.name)),
+        ActiveBlock::AlbumTracks => match app.album_table_context {
+            AlbumTableContext::Simplified => {
+                app.selected_album_simplified
+                    .as_ref()
+                    .and_then(|selected_album| {
+                        selected_album
+                            .tracks
+                            .items
+                            .get(selected_album.selected_index)
+                            .and_then(|track| track_seed(&track.id, &track.name))
+                    })
+            }
+            AlbumTableContext::Full => {
+                app.selected_album_full.as_ref().and_then(|selected_album| {
+                    selected_album
+                        .album
+                        .tracks
+                        .items
+                        .get(app.saved_album_tracks_index)
+                        .and_then(|track| track_seed(&track.id, &track.name))
+                })
+            }
+        },
+        ActiveBlock::RecentlyPlayed => app
+            .recently_played
+            .result
+            .as_ref()
+            .and_then(|result| result.items.get(app.recently_played.index))
+            .and_then(|item| track_seed(&item.track.id, &item.track.name)),
+        ActiveBlock::SearchResultBlock
+            if app.search_results.selected_block == SearchResultBlock::SongSearch =>
+        {
+            match (
+                &app.search_results.tracks,
+                app.search_results.selected_tracks_index,
+            ) {
+                (Some(result), Some(index)) => result
+                    .tracks
+                    .items
+                    .get(index)
+                    .and_then(|track| track_seed(&track.id, &track.name)),
+                _ => None,
+            }
+        }
+        ActiveBlock::ArtistBlock => app.artist.as_ref().and_then(|artist| {
+            if artist.artist_selected_block == ArtistBlock::TopTracks {
+                artist
+                    .top_tracks
+                    .get(artist.selected_top_track_index)
+                    .and_then(|track| track_seed(&track.id, &track.name))
+            } else {
+                None
+            }
+        }),
+        _ => None,
+    };
+
+    selected_track.or_else(|| {
+        app.current_playback_context
+            .as_ref()
+            .and_then(|context| context.item.as_ref())
+            .and_then(|track| track_seed(&track.id, &track.name))
+    })
+}
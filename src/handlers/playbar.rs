@@ -25,6 +25,8 @@ pub fn handler(key: Key, app: &mut App) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::spotify_api::fake;
+    use serde_json::json;
 
     #[test]
     fn on_left_press() {
@@ -36,4 +38,25 @@ mod tests {
         assert_eq!(current_route.active_block, ActiveBlock::Empty);
         assert_eq!(current_route.hovered_block, ActiveBlock::MyPlaylists);
     }
+
+    #[test]
+    fn on_s_press_removes_saved_track() {
+        let (mut app, spotify) = fake::app_with_fake();
+        spotify.respond("current_user_saved_tracks_contains", json!([true]));
+        app.current_playback_context = serde_json::from_value(fake::playing_context(
+            fake::track("1", "Song", "Band"),
+            fake::device("laptop", "Laptop", true),
+            true,
+        ))
+        .ok();
+        app.liked_song_ids_set.insert("1".to_string());
+
+        handler(Key::Char('s'), &mut app);
+
+        assert_eq!(
+            spotify.calls_to("current_user_saved_tracks_delete"),
+            vec!["[\"1\"]"]
+        );
+        assert!(!app.liked_song_ids_set.contains("1"));
+    }
 }
@@ -77,7 +77,7 @@ pub fn handler(key: Key, app: &mut App) {
         Key::Char('D') => {
             app.user_unfollow_playlists();
             if let Some(spotify) = &app.spotify {
-                let playlists = spotify.current_user_playlists(app.large_search_limit, None);
+                let playlists = spotify.current_user_playlists(Some(app.large_search_limit), None);
 
                 match playlists {
                     Ok(p) => app.playlists = Some(p),
@@ -85,12 +85,32 @@ pub fn handler(key: Key, app: &mut App) {
                 };
             }
         }
+        // After the fixed keys, so binding export to one of them doesn't take it over
+        k if k == app.user_config.keys.export => app.export_selected_playlist(),
         _ => {}
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use crate::spotify_api::fake;
+
     #[test]
-    fn test() {}
+    fn delete_is_kept_when_export_is_bound_to_it() {
+        let playlists = fake::page(vec![fake::playlist("p1", "Road Trip", "alice")]);
+        let (mut app, spotify) = fake::app_with_fake();
+        spotify.respond("current_user_playlists", playlists.clone());
+        app.user = serde_json::from_value(fake::user("alice")).ok();
+        app.playlists = serde_json::from_value(playlists).ok();
+        app.selected_playlist_index = Some(0);
+        app.user_config.keys.export = Key::Char('D');
+
+        handler(Key::Char('D'), &mut app);
+
+        assert_eq!(
+            spotify.calls_to("user_playlist_unfollow"),
+            vec!["\"alice\", \"p1\""]
+        );
+    }
 }
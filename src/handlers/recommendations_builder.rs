@@ -0,0 +1,129 @@
+use super::common_key_events;
+use crate::{
+    app::App,
+    event::Key,
+    recommendations::{BuilderSection, RecommendationSeed, TUNABLES},
+};
+
+pub fn handler(key: Key, app: &mut App) {
+    let section = app.recommendations_builder.section;
+    let builder = &mut app.recommendations_builder;
+
+    match key {
+        Key::Tab => builder.next_section(),
+        // In the tunables table left and right pick the min, target or max column
+        k if common_key_events::left_event(k) && section == BuilderSection::Tunables => {
+            builder.previous_bound();
+        }
+        k if common_key_events::right_event(k) && section == BuilderSection::Tunables => {
+            builder.next_bound();
+        }
+        k if common_key_events::left_event(k) => common_key_events::handle_left_event(app),
+        k if common_key_events::down_event(k) => match section {
+            BuilderSection::Seeds => {
+                builder.selected_seed_index = common_key_events::on_down_press_handler(
+                    &builder.seeds,
+                    Some(builder.selected_seed_index),
+                );
+            }
+            BuilderSection::Genres => {
+                builder.selected_genre_index = common_key_events::on_down_press_handler(
+                    &builder.genres,
+                    Some(builder.selected_genre_index),
+                );
+            }
+            BuilderSection::Tunables => {
+                builder.selected_tunable_index = common_key_events::on_down_press_handler(
+                    &TUNABLES,
+                    Some(builder.selected_tunable_index),
+                );
+            }
+        },
+        k if common_key_events::up_event(k) => match section {
+            BuilderSection::Seeds => {
+                builder.selected_seed_index = common_key_events::on_up_press_handler(
+                    &builder.seeds,
+                    Some(builder.selected_seed_index),
+                );
+            }
+            BuilderSection::Genres => {
+                builder.selected_genre_index = common_key_events::on_up_press_handler(
+                    &builder.genres,
+                    Some(builder.selected_genre_index),
+                );
+            }
+            BuilderSection::Tunables => {
+                builder.selected_tunable_index = common_key_events::on_up_press_handler(
+                    &TUNABLES,
+                    Some(builder.selected_tunable_index),
+                );
+            }
+        },
+        Key::Char(']') if section == BuilderSection::Tunables => {
+            builder.adjust_selected_value(1);
+        }
+        Key::Char('[') if section == BuilderSection::Tunables => {
+            builder.adjust_selected_value(-1);
+        }
+        Key::Char('x') | Key::Backspace | Key::Delete => match section {
+            BuilderSection::Seeds => builder.remove_selected_seed(),
+            BuilderSection::Tunables => builder.clear_selected_value(),
+            BuilderSection::Genres => {}
+        },
+        k if k == app.user_config.keys.submit && section == BuilderSection::Genres => {
+            if let Some(genre) = builder.genres.get(builder.selected_genre_index).cloned() {
+                app.add_recommendation_seed(RecommendationSeed::Genre(genre));
+            }
+        }
+        Key::Char('g') => app.get_builder_recommendations(),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recommendations::Bound;
+
+    #[test]
+    fn on_add_and_remove_genre() {
+        let mut app = App::new();
+        app.recommendations_builder.genres = vec!["disco".to_string(), "house".to_string()];
+
+        handler(Key::Tab, &mut app);
+        assert_eq!(app.recommendations_builder.section, BuilderSection::Genres);
+
+        handler(Key::Down, &mut app);
+        handler(Key::Enter, &mut app);
+        assert_eq!(
+            app.recommendations_builder.seeds,
+            vec![RecommendationSeed::Genre("house".to_string())]
+        );
+
+        handler(Key::Tab, &mut app);
+        handler(Key::Tab, &mut app);
+        assert_eq!(app.recommendations_builder.section, BuilderSection::Seeds);
+
+        handler(Key::Char('x'), &mut app);
+        assert!(app.recommendations_builder.seeds.is_empty());
+    }
+
+    #[test]
+    fn on_adjust_tunable() {
+        let mut app = App::new();
+        app.recommendations_builder.section = BuilderSection::Tunables;
+
+        handler(Key::Left, &mut app);
+        assert_eq!(app.recommendations_builder.selected_bound, Bound::Min);
+
+        handler(Key::Char(']'), &mut app);
+        assert_eq!(app.recommendations_builder.values[0].min, Some(0.5));
+
+        handler(Key::Char('['), &mut app);
+        let min = app.recommendations_builder.values[0].min.unwrap();
+        assert!((min - 0.45).abs() < f32::EPSILON);
+
+        handler(Key::Char('x'), &mut app);
+        assert_eq!(app.recommendations_builder.values[0].min, None);
+    }
+}
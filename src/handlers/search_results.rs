@@ -431,7 +431,8 @@ pub fn handler(key: Key, app: &mut App) {
             SearchResultBlock::PlaylistSearch => {
                 app.user_follow_playlists();
                 if let Some(spotify) = &app.spotify {
-                    let playlists = spotify.current_user_playlists(app.large_search_limit, None);
+                    let playlists =
+                        spotify.current_user_playlists(Some(app.large_search_limit), None);
 
                     match playlists {
                         Ok(p) => app.playlists = Some(p),
@@ -72,15 +72,9 @@ pub fn handler(key: Key, app: &mut App) {
         }
         Key::Enter => {
             if let (Some(devices), Some(index)) = (&app.devices, app.selected_device_index) {
-                if let Some(device) = &devices.devices.get(index) {
-                    match app.client_config.set_device_id(device.id.clone()) {
-                        Ok(()) => {
-                            app.pop_navigation_stack();
-                        }
-                        Err(e) => {
-                            app.handle_error(e);
-                        }
-                    };
+                if let Some(device) = devices.devices.get(index) {
+                    let device_id = device.id.clone();
+                    app.select_device(device_id);
                 }
             };
         }
@@ -0,0 +1,58 @@
+use super::common_key_events;
+use crate::{app::App, event::Key};
+
+pub fn handler(key: Key, app: &mut App) {
+    match key {
+        Key::Esc => {
+            app.pop_navigation_stack();
+        }
+        k if common_key_events::down_event(k) => {
+            app.selected_profile_index = common_key_events::on_down_press_handler(
+                &app.profiles,
+                Some(app.selected_profile_index),
+            );
+        }
+        k if common_key_events::up_event(k) => {
+            app.selected_profile_index = common_key_events::on_up_press_handler(
+                &app.profiles,
+                Some(app.selected_profile_index),
+            );
+        }
+        k if common_key_events::high_event(k) => {
+            app.selected_profile_index = common_key_events::on_high_press_handler();
+        }
+        k if common_key_events::middle_event(k) && !app.profiles.is_empty() => {
+            app.selected_profile_index = common_key_events::on_middle_press_handler(&app.profiles);
+        }
+        k if common_key_events::low_event(k) && !app.profiles.is_empty() => {
+            app.selected_profile_index = common_key_events::on_low_press_handler(&app.profiles);
+        }
+        Key::Enter => {
+            if let Some(profile) = app.profiles.get(app.selected_profile_index).cloned() {
+                app.pop_navigation_stack();
+                if profile != app.client_config.get_profile_name() {
+                    app.pending_profile = Some(profile);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn on_select_profile() {
+        let mut app = App::new();
+        app.profiles = vec!["default".to_string(), "work".to_string()];
+
+        handler(Key::Enter, &mut app);
+        assert_eq!(app.pending_profile, None);
+
+        handler(Key::Down, &mut app);
+        handler(Key::Enter, &mut app);
+        assert_eq!(app.pending_profile, Some("work".to_string()));
+    }
+}
@@ -0,0 +1,32 @@
+use super::{super::app::App, common_key_events};
+use crate::event::Key;
+
+pub fn handler(key: Key, app: &mut App) {
+    match key {
+        k if common_key_events::left_event(k) => common_key_events::handle_left_event(app),
+        Key::Tab => app.next_stats_period(),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::{History, StatsPeriod};
+
+    #[test]
+    fn on_tab() {
+        let mut app = App::new();
+        app.history = Some(History::open_in_memory().unwrap());
+        app.get_stats();
+        assert_eq!(app.stats.as_ref().unwrap().period, StatsPeriod::Week);
+
+        handler(Key::Tab, &mut app);
+        assert_eq!(app.stats_period, StatsPeriod::Month);
+        assert_eq!(app.stats.as_ref().unwrap().period, StatsPeriod::Month);
+
+        handler(Key::Tab, &mut app);
+        handler(Key::Tab, &mut app);
+        assert_eq!(app.stats_period, StatsPeriod::Week);
+    }
+}
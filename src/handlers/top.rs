@@ -0,0 +1,136 @@
+use super::common_key_events;
+use crate::{
+    app::{ActiveBlock, App, RouteId, TopSection},
+    event::Key,
+};
+
+pub fn handler(key: Key, app: &mut App) {
+    let (len, selected_index) = match app.top.section {
+        TopSection::Artists => (app.top.artists.len(), app.top.selected_artist_index),
+        TopSection::Tracks => (app.top.tracks.len(), app.top.selected_track_index),
+    };
+    // Only the length matters for moving the selection
+    let items = vec![(); len];
+
+    let next_index = match key {
+        k if common_key_events::left_event(k) => {
+            match app.top.section {
+                TopSection::Tracks => app.top.section = TopSection::Artists,
+                TopSection::Artists => common_key_events::handle_left_event(app),
+            }
+            return;
+        }
+        k if common_key_events::right_event(k) => {
+            app.top.section = TopSection::Tracks;
+            return;
+        }
+        Key::Tab => {
+            app.next_top_time_range();
+            return;
+        }
+        Key::Enter => {
+            match app.top.section {
+                TopSection::Artists => {
+                    if let Some(artist) = app.top.artists.get(selected_index).cloned() {
+                        app.get_artist(&artist.id, &artist.name);
+                        app.push_navigation_stack(RouteId::Artist, ActiveBlock::ArtistBlock);
+                    }
+                }
+                TopSection::Tracks => app.play_top_track(),
+            }
+            return;
+        }
+        _ if items.is_empty() => return,
+        k if common_key_events::down_event(k) => {
+            common_key_events::on_down_press_handler(&items, Some(selected_index))
+        }
+        k if common_key_events::up_event(k) => {
+            common_key_events::on_up_press_handler(&items, Some(selected_index))
+        }
+        k if common_key_events::high_event(k) => common_key_events::on_high_press_handler(),
+        k if common_key_events::middle_event(k) => {
+            common_key_events::on_middle_press_handler(&items)
+        }
+        k if common_key_events::low_event(k) => common_key_events::on_low_press_handler(&items),
+        _ => return,
+    };
+
+    match app.top.section {
+        TopSection::Artists => app.top.selected_artist_index = next_index,
+        TopSection::Tracks => app.top.selected_track_index = next_index,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app::TopTimeRange,
+        spotify_api::fake::{self, FakeSpotify},
+    };
+    use std::rc::Rc;
+
+    fn app_with_top() -> (App, Rc<FakeSpotify>) {
+        let (mut app, spotify) = fake::app_with_fake();
+        spotify
+            .respond(
+                "current_user_top_artists",
+                fake::page(vec![
+                    fake::full_artist("a1", "First Artist"),
+                    fake::full_artist("a2", "Second Artist"),
+                ]),
+            )
+            .respond(
+                "current_user_top_tracks",
+                fake::page(vec![
+                    fake::track("t1", "First Song", "First Artist"),
+                    fake::track("t2", "Second Song", "Second Artist"),
+                ]),
+            );
+        app.client_config.device_id = Some("laptop".to_string());
+        app.get_top();
+        (app, spotify)
+    }
+
+    #[test]
+    fn on_tab() {
+        let (mut app, spotify) = app_with_top();
+        handler(Key::Tab, &mut app);
+
+        assert_eq!(app.top.time_range, TopTimeRange::MediumTerm);
+        assert_eq!(
+            spotify.calls_to("current_user_top_tracks"),
+            vec![
+                "Some(20), None, Some(ShortTerm)",
+                "Some(20), None, Some(MediumTerm)"
+            ]
+        );
+    }
+
+    #[test]
+    fn on_enter_track() {
+        let (mut app, spotify) = app_with_top();
+        handler(Key::Right, &mut app);
+        handler(Key::Down, &mut app);
+        assert_eq!(app.top.selected_artist_index, 0);
+        assert_eq!(app.top.selected_track_index, 1);
+
+        handler(Key::Enter, &mut app);
+        assert_eq!(
+            spotify.calls_to("start_playback"),
+            vec![
+                "Some(\"laptop\"), None, Some([\"spotify:track:t1\", \"spotify:track:t2\"]), \
+                 Some(Offset { position: Some(1), uri: None }), None"
+            ]
+        );
+    }
+
+    #[test]
+    fn on_left_from_tracks() {
+        let (mut app, _) = app_with_top();
+        handler(Key::Right, &mut app);
+        handler(Key::Left, &mut app);
+        assert_eq!(app.top.section, TopSection::Artists);
+        assert_eq!(app.get_current_route().active_block, ActiveBlock::Top);
+    }
+}
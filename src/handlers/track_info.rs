@@ -0,0 +1,52 @@
+use crate::{app::App, event::Key};
+
+pub fn handler(key: Key, app: &mut App) {
+    if key == app.user_config.keys.submit {
+        // Close the popup and go back to the block it was opened from
+        let hovered_block = app.get_current_route().hovered_block;
+        app.set_current_route_state(Some(hovered_block), None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::ActiveBlock;
+
+    #[test]
+    fn on_enter() {
+        let mut app = App::new();
+
+        app.set_current_route_state(Some(ActiveBlock::TrackInfo), Some(ActiveBlock::TrackTable));
+
+        handler(Key::Enter, &mut app);
+        let current_route = app.get_current_route();
+
+        assert_eq!(current_route.active_block, ActiveBlock::TrackTable);
+        assert_eq!(current_route.hovered_block, ActiveBlock::TrackTable);
+    }
+
+    #[test]
+    fn on_esc() {
+        let mut app = App::new();
+
+        app.set_current_route_state(Some(ActiveBlock::TrackInfo), Some(ActiveBlock::TrackTable));
+
+        super::super::handle_app(Key::Esc, &mut app);
+        let current_route = app.get_current_route();
+
+        assert_eq!(current_route.active_block, ActiveBlock::TrackTable);
+        assert_eq!(current_route.hovered_block, ActiveBlock::TrackTable);
+    }
+
+    #[test]
+    fn track_info_key_is_left_to_other_blocks() {
+        let mut app = App::new();
+
+        app.set_current_route_state(Some(ActiveBlock::Import), Some(ActiveBlock::Import));
+
+        super::super::handle_app(Key::Char('i'), &mut app);
+
+        assert_eq!(app.get_current_route().active_block, ActiveBlock::Import);
+    }
+}
@@ -86,6 +86,18 @@ pub fn handler(key: Key, app: &mut App) {
                             }
                         }
                     }
+                    TrackTableContext::BrowsePlaylist => {
+                        if let (Some(playlist), Some(playlist_tracks)) =
+                            (&app.browse.selected_playlist, &app.playlist_tracks)
+                        {
+                            if app.playlist_offset + app.large_search_limit < playlist_tracks.total
+                            {
+                                app.playlist_offset += app.large_search_limit;
+                                let playlist_id = playlist.id.to_owned();
+                                app.get_playlist_tracks(playlist_id);
+                            }
+                        }
+                    }
                 },
                 None => {}
             };
@@ -133,6 +145,15 @@ pub fn handler(key: Key, app: &mut App) {
                             app.get_made_for_you_playlist_tracks(playlist_id);
                         }
                     }
+                    TrackTableContext::BrowsePlaylist => {
+                        if let Some(playlist) = &app.browse.selected_playlist {
+                            if app.playlist_offset >= app.large_search_limit {
+                                app.playlist_offset -= app.large_search_limit;
+                            }
+                            let playlist_id = playlist.id.to_owned();
+                            app.get_playlist_tracks(playlist_id);
+                        }
+                    }
                 },
                 None => {}
             };
@@ -143,6 +164,14 @@ pub fn handler(key: Key, app: &mut App) {
         Key::Char('r') => {
             handle_recommended_tracks(app);
         }
+        Key::Char('w') if app.track_table.context == Some(TrackTableContext::RecommendedTracks) => {
+            app.save_recommendations_as_playlist();
+        }
+        k if k == app.user_config.keys.export
+            && app.track_table.context == Some(TrackTableContext::SavedTracks) =>
+        {
+            app.export_saved_tracks();
+        }
         _ => {}
     }
 }
@@ -192,6 +221,7 @@ fn jump_to_end(app: &mut App) {
             TrackTableContext::AlbumSearch => {}
             TrackTableContext::PlaylistSearch => {}
             TrackTableContext::MadeForYou => {}
+            TrackTableContext::BrowsePlaylist => {}
         },
         None => {}
     }
@@ -297,6 +327,17 @@ fn on_enter(app: &mut App) {
                     );
                 }
             }
+            TrackTableContext::BrowsePlaylist => {
+                if let (Some(_track), Some(playlist)) =
+                    (tracks.get(*selected_index), &app.browse.selected_playlist)
+                {
+                    app.start_playback(
+                        Some(playlist.uri.to_owned()),
+                        None,
+                        Some(app.track_table.selected_index + app.playlist_offset as usize),
+                    );
+                }
+            }
         },
         None => {}
     };
@@ -322,6 +363,7 @@ fn jump_to_start(app: &mut App) {
             TrackTableContext::AlbumSearch => {}
             TrackTableContext::PlaylistSearch => {}
             TrackTableContext::MadeForYou => {}
+            TrackTableContext::BrowsePlaylist => {}
         },
         None => {}
     }
@@ -0,0 +1,468 @@
+//! A local log of every play spt sees, kept in SQLite so the stats work without the network.
+//!
+//! Spotify only reports what is playing right now, so `PlayTracker` follows the polled playback
+//! state and hands back each play once the next track starts or playback stops. Listening time
+//! only counts progress made at normal speed, so seeking ahead is not counted as listening.
+use rspotify::spotify::model::context::FullPlayingContext;
+use rusqlite::{params, Connection, NO_PARAMS};
+use std::{
+    path::Path,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+// A play that stops closer than this to the end of the track is not a skip
+const SKIP_MARGIN_MS: u32 = 10_000;
+// Polls are a few seconds apart, allow for the progress and the clock drifting by this much
+const PROGRESS_SLACK_MS: u32 = 2_000;
+const DAY_SECONDS: i64 = 24 * 60 * 60;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS plays (
+        id INTEGER PRIMARY KEY,
+        track_id TEXT NOT NULL,
+        track_name TEXT NOT NULL,
+        artist_id TEXT,
+        artist_name TEXT NOT NULL,
+        album_id TEXT,
+        album_name TEXT NOT NULL,
+        context_uri TEXT,
+        device_name TEXT NOT NULL,
+        started_at INTEGER NOT NULL,
+        duration_ms INTEGER NOT NULL,
+        listened_ms INTEGER NOT NULL,
+        skipped INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS plays_started_at ON plays (started_at);
+";
+
+/// One play of a track. The artist is the track's first one
+#[derive(Clone, Debug, PartialEq)]
+pub struct Play {
+    pub track_id: String,
+    pub track_name: String,
+    pub artist_id: Option<String>,
+    pub artist_name: String,
+    pub album_id: Option<String>,
+    pub album_name: String,
+    pub context_uri: Option<String>,
+    pub device_name: String,
+    // Seconds since the Unix epoch
+    pub started_at: i64,
+    pub duration_ms: u32,
+    pub listened_ms: u32,
+    pub skipped: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StatsPeriod {
+    Week,
+    Month,
+    AllTime,
+}
+
+impl StatsPeriod {
+    pub fn title(self) -> &'static str {
+        match self {
+            StatsPeriod::Week => "Last 7 days",
+            StatsPeriod::Month => "Last 30 days",
+            StatsPeriod::AllTime => "All time",
+        }
+    }
+
+    pub fn next(self) -> StatsPeriod {
+        match self {
+            StatsPeriod::Week => StatsPeriod::Month,
+            StatsPeriod::Month => StatsPeriod::AllTime,
+            StatsPeriod::AllTime => StatsPeriod::Week,
+        }
+    }
+
+    fn since(self, now: i64) -> i64 {
+        match self {
+            StatsPeriod::Week => now - 7 * DAY_SECONDS,
+            StatsPeriod::Month => now - 30 * DAY_SECONDS,
+            StatsPeriod::AllTime => 0,
+        }
+    }
+}
+
+/// A track, artist or album with how much it was played
+#[derive(Clone, Debug, PartialEq)]
+pub struct TopItem {
+    pub name: String,
+    // The artist of a track or album, empty for artists
+    pub artist_name: String,
+    pub plays: u32,
+    pub listened_ms: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stats {
+    pub period: StatsPeriod,
+    pub top_tracks: Vec<TopItem>,
+    pub top_artists: Vec<TopItem>,
+    pub top_albums: Vec<TopItem>,
+    // Local dates (YYYY-MM-DD) with the time listened on them, oldest first
+    pub daily_listening_ms: Vec<(String, u64)>,
+    pub plays: u32,
+    pub skips: u32,
+    pub listened_ms: u64,
+}
+
+impl Stats {
+    /// The share of plays that were skipped, from 0 to 1
+    pub fn skip_rate(&self) -> f64 {
+        if self.plays == 0 {
+            0.0
+        } else {
+            f64::from(self.skips) / f64::from(self.plays)
+        }
+    }
+}
+
+pub struct History {
+    connection: Connection,
+}
+
+impl History {
+    pub fn open(path: &Path) -> Result<History, failure::Error> {
+        History::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<History, failure::Error> {
+        History::init(Connection::open_in_memory()?)
+    }
+
+    fn init(connection: Connection) -> Result<History, failure::Error> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(History { connection })
+    }
+
+    pub fn record(&self, play: &Play) -> Result<(), failure::Error> {
+        self.connection.execute(
+            "INSERT INTO plays (track_id, track_name, artist_id, artist_name, album_id, \
+             album_name, context_uri, device_name, started_at, duration_ms, listened_ms, skipped) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                play.track_id,
+                play.track_name,
+                play.artist_id,
+                play.artist_name,
+                play.album_id,
+                play.album_name,
+                play.context_uri,
+                play.device_name,
+                play.started_at,
+                play.duration_ms,
+                play.listened_ms,
+                play.skipped,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Stats for the plays in `period` up to `now` (seconds since the epoch), with at most
+    /// `limit` top tracks, artists and albums
+    pub fn stats(
+        &self,
+        period: StatsPeriod,
+        now: i64,
+        limit: u32,
+    ) -> Result<Stats, failure::Error> {
+        let since = period.since(now);
+        let (plays, skips, listened_ms) = self.connection.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(skipped), 0), COALESCE(SUM(listened_ms), 0) \
+             FROM plays WHERE started_at >= ?1",
+            params![since],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get::<_, i64>(2)?)),
+        )?;
+
+        let mut daily = self.connection.prepare(
+            "SELECT date(started_at, 'unixepoch', 'localtime') AS day, SUM(listened_ms) \
+             FROM plays WHERE started_at >= ?1 GROUP BY day ORDER BY day",
+        )?;
+        let daily_listening_ms = daily
+            .query_map(params![since], |row| {
+                Ok((row.get(0)?, row.get::<_, i64>(1)? as u64))
+            })?
+            .collect::<Result<Vec<(String, u64)>, _>>()?;
+
+        Ok(Stats {
+            period,
+            top_tracks: self.top("track_name", "artist_name", "track_id", since, limit)?,
+            top_artists: self.top("artist_name", "''", "artist_name", since, limit)?,
+            top_albums: self.top(
+                "album_name",
+                "artist_name",
+                "album_name, artist_name",
+                since,
+                limit,
+            )?,
+            daily_listening_ms,
+            plays,
+            skips,
+            listened_ms: listened_ms as u64,
+        })
+    }
+
+    // The most played groups of `group_by`, with the `name` and `artist_name` columns of each
+    fn top(
+        &self,
+        name: &str,
+        artist_name: &str,
+        group_by: &str,
+        since: i64,
+        limit: u32,
+    ) -> Result<Vec<TopItem>, failure::Error> {
+        let mut statement = self.connection.prepare(&format!(
+            "SELECT {}, {}, COUNT(*) AS play_count, SUM(listened_ms) AS listened \
+             FROM plays WHERE started_at >= ?1 GROUP BY {} \
+             ORDER BY play_count DESC, listened DESC LIMIT ?2",
+            name, artist_name, group_by
+        ))?;
+        let items = statement
+            .query_map(params![since, limit], |row| {
+                Ok(TopItem {
+                    name: row.get(0)?,
+                    artist_name: row.get(1)?,
+                    plays: row.get(2)?,
+                    listened_ms: row.get::<_, i64>(3)? as u64,
+                })
+            })?
+            .collect::<Result<Vec<TopItem>, _>>()?;
+        Ok(items)
+    }
+
+    pub fn play_count(&self) -> Result<u32, failure::Error> {
+        Ok(self
+            .connection
+            .query_row("SELECT COUNT(*) FROM plays", NO_PARAMS, |row| row.get(0))?)
+    }
+}
+
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() as i64)
+}
+
+struct TrackedPlay {
+    play: Play,
+    progress_ms: u32,
+    is_playing: bool,
+    seen_at: Instant,
+}
+
+impl TrackedPlay {
+    fn start(context: &FullPlayingContext, now: Instant, unix_now: i64) -> Option<TrackedPlay> {
+        let track = context.item.as_ref()?;
+        let progress_ms = context.progress_ms.unwrap_or(0);
+        let artist = track.artists.first();
+        Some(TrackedPlay {
+            play: Play {
+                track_id: track.id.clone()?,
+                track_name: track.name.clone(),
+                artist_id: artist.and_then(|artist| artist.id.clone()),
+                artist_name: artist.map_or_else(String::new, |artist| artist.name.clone()),
+                album_id: track.album.id.clone(),
+                album_name: track.album.name.clone(),
+                context_uri: context.context.as_ref().map(|context| context.uri.clone()),
+                device_name: context.device.name.clone(),
+                started_at: unix_now - i64::from(progress_ms / 1000),
+                duration_ms: track.duration_ms,
+                listened_ms: 0,
+                skipped: false,
+            },
+            progress_ms,
+            is_playing: context.is_playing,
+            seen_at: now,
+        })
+    }
+
+    // Count the progress made since the last poll, up to the time that actually passed
+    fn advance(&mut self, progress_ms: u32, now: Instant) {
+        if self.is_playing && progress_ms > self.progress_ms {
+            let elapsed_ms = now.saturating_duration_since(self.seen_at).as_millis() as u32;
+            self.play.listened_ms +=
+                (progress_ms - self.progress_ms).min(elapsed_ms + PROGRESS_SLACK_MS);
+        }
+        self.progress_ms = progress_ms;
+        self.seen_at = now;
+    }
+
+    fn finish(mut self, now: Instant) -> Play {
+        // The track kept playing after the last poll, until the end at most
+        let elapsed_ms = now.saturating_duration_since(self.seen_at).as_millis() as u32;
+        let progress_ms = if self.is_playing {
+            self.progress_ms
+                .saturating_add(elapsed_ms)
+                .min(self.play.duration_ms)
+        } else {
+            self.progress_ms
+        };
+        self.advance(progress_ms, now);
+        self.play.skipped = progress_ms + SKIP_MARGIN_MS < self.play.duration_ms;
+        self.play
+    }
+}
+
+/// Follows the polled playback state and hands back each play when it is over
+#[derive(Default)]
+pub struct PlayTracker {
+    current: Option<TrackedPlay>,
+}
+
+impl PlayTracker {
+    /// Feed the state from each poll, `None` when nothing is playing. Returns the play that
+    /// ended since the last call, if any
+    pub fn observe(
+        &mut self,
+        context: Option<&FullPlayingContext>,
+        now: Instant,
+        unix_now: i64,
+    ) -> Option<Play> {
+        let track_id = context
+            .and_then(|context| context.item.as_ref())
+            .and_then(|track| track.id.as_deref());
+
+        if let (Some(current), Some(context), Some(track_id)) =
+            (&mut self.current, context, track_id)
+        {
+            let progress_ms = context.progress_ms.unwrap_or(0);
+            // Starting the same track over, e.g. on repeat, is another play
+            let restarted = progress_ms + PROGRESS_SLACK_MS < current.progress_ms
+                && progress_ms < SKIP_MARGIN_MS;
+            if current.play.track_id == track_id && !restarted {
+                current.advance(progress_ms, now);
+                current.is_playing = context.is_playing;
+                return None;
+            }
+        }
+
+        let finished = self.current.take().map(|current| current.finish(now));
+        self.current = context.and_then(|context| TrackedPlay::start(context, now, unix_now));
+        finished
+    }
+
+    /// The play in progress, as listened to up to the last poll
+    pub fn current(&self) -> Option<&Play> {
+        self.current.as_ref().map(|current| &current.play)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spotify_api::fake;
+    use std::time::Duration;
+
+    fn play(track_id: &str, artist_name: &str, started_at: i64, skipped: bool) -> Play {
+        Play {
+            track_id: track_id.to_string(),
+            track_name: format!("Track {}", track_id),
+            artist_id: None,
+            artist_name: artist_name.to_string(),
+            album_id: None,
+            album_name: format!("Album by {}", artist_name),
+            context_uri: None,
+            device_name: "Laptop".to_string(),
+            started_at,
+            duration_ms: 200_000,
+            listened_ms: if skipped { 30_000 } else { 200_000 },
+            skipped,
+        }
+    }
+
+    fn playing(track_id: &str, progress_ms: u32, is_playing: bool) -> FullPlayingContext {
+        let mut context = fake::playing_context(
+            fake::track(track_id, "Song", "Band"),
+            fake::device("laptop", "Laptop", true),
+            is_playing,
+        );
+        context["progress_ms"] = progress_ms.into();
+        serde_json::from_value(context).unwrap()
+    }
+
+    #[test]
+    fn test_stats() {
+        let history = History::open_in_memory().unwrap();
+        let now = 1_600_000_000;
+        history
+            .record(&play("1", "Band", now - 100, false))
+            .unwrap();
+        history.record(&play("1", "Band", now - 50, true)).unwrap();
+        history
+            .record(&play("2", "Other Band", now - 10, false))
+            .unwrap();
+        // Outside the week, but in the month
+        history
+            .record(&play("3", "Other Band", now - 10 * DAY_SECONDS, false))
+            .unwrap();
+
+        let week = history.stats(StatsPeriod::Week, now, 10).unwrap();
+        assert_eq!(week.plays, 3);
+        assert_eq!(week.skips, 1);
+        assert_eq!(week.listened_ms, 430_000);
+        assert_eq!(week.top_tracks[0].name, "Track 1");
+        assert_eq!(week.top_tracks[0].artist_name, "Band");
+        assert_eq!(week.top_tracks[0].plays, 2);
+        assert_eq!(week.top_artists[0].name, "Band");
+        assert_eq!(week.top_albums.len(), 2);
+        assert_eq!(
+            week.daily_listening_ms.iter().map(|day| day.1).sum::<u64>(),
+            430_000
+        );
+
+        let month = history.stats(StatsPeriod::Month, now, 1).unwrap();
+        assert_eq!(month.plays, 4);
+        assert_eq!(month.top_artists.len(), 1);
+        assert_eq!(month.top_artists[0].name, "Other Band");
+        assert_eq!(history.play_count().unwrap(), 4);
+    }
+
+    #[test]
+    fn test_tracker() {
+        let mut tracker = PlayTracker::default();
+        let start = Instant::now();
+        let at = |seconds| start + Duration::from_secs(seconds);
+
+        assert_eq!(
+            tracker.observe(Some(&playing("1", 0, true)), at(0), 1000),
+            None
+        );
+        assert_eq!(
+            tracker.observe(Some(&playing("1", 5_000, true)), at(5), 1005),
+            None
+        );
+        // Seeking ahead only counts the time that passed
+        assert_eq!(
+            tracker.observe(Some(&playing("1", 60_000, true)), at(10), 1010),
+            None
+        );
+
+        let skipped = tracker
+            .observe(Some(&playing("2", 0, true)), at(12), 1012)
+            .unwrap();
+        assert_eq!(skipped.track_id, "1");
+        assert_eq!(skipped.started_at, 1000);
+        assert_eq!(skipped.device_name, "Laptop");
+        assert_eq!(skipped.listened_ms, 5_000 + 7_000 + 2_000);
+        assert!(skipped.skipped);
+        assert_eq!(tracker.current().unwrap().track_id, "2");
+
+        // The second track plays to its end, which comes after the last poll
+        let duration_ms = playing("2", 0, true).item.unwrap().duration_ms;
+        let end = duration_ms / 1000;
+        tracker.observe(
+            Some(&playing("2", duration_ms - 3_000, true)),
+            at(12 + u64::from(end) - 3),
+            1000,
+        );
+        let finished = tracker
+            .observe(None, at(12 + u64::from(end) + 2), 1000)
+            .unwrap();
+        assert_eq!(finished.track_id, "2");
+        assert!(!finished.skipped);
+        assert_eq!(tracker.observe(None, at(300), 1000), None);
+    }
+}
@@ -0,0 +1,847 @@
+//! Creating a playlist from a local M3U, CSV or tag list with `spt import`.
+//!
+//! Each entry is looked up with the same track search as the search box. Spotify URIs are used
+//! as they are, an ISRC is searched for exactly, and anything else is scored by how close the
+//! title, artists and duration of each result are. Entries whose best result is not a clear
+//! match wait for review on the Import screen, where each one is accepted, swapped for another
+//! result or skipped, and the report at the end lists everything that was left out.
+use crate::{
+    spotify_api::SpotifyApi,
+    uri::{SpotifyUri, UriKind},
+};
+use failure::{err_msg, format_err};
+use rspotify::spotify::{model::track::FullTrack, senum::Country};
+use std::{fs, path::Path, str::FromStr};
+
+// Search results to score for each entry, and how many of the best to offer for review
+const SEARCH_LIMIT: u32 = 10;
+const MAX_CANDIDATES: usize = 5;
+// Results scoring this much are added without review
+pub const CONFIDENT_SCORE: f64 = 0.85;
+// Durations this close count as the same, then the score falls to nothing over 30 seconds
+const DURATION_SLACK_MS: u32 = 3_000;
+const DURATION_RANGE_MS: f64 = 30_000.0;
+const TITLE_WEIGHT: f64 = 0.5;
+const ARTIST_WEIGHT: f64 = 0.35;
+const DURATION_WEIGHT: f64 = 0.15;
+// The most tracks Spotify adds to a playlist at once
+const ADD_TRACKS_LIMIT: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImportFormat {
+    M3u,
+    Csv,
+    // One `Artist - Title` or `Artist - Album - Title` per line
+    Tags,
+}
+
+impl ImportFormat {
+    pub fn parse(format: &str) -> Result<ImportFormat, failure::Error> {
+        match format.to_lowercase().as_str() {
+            "m3u" | "m3u8" => Ok(ImportFormat::M3u),
+            "csv" => Ok(ImportFormat::Csv),
+            "tags" | "txt" => Ok(ImportFormat::Tags),
+            _ => Err(format_err!(
+                "Import format must be one of m3u, csv or tags, is {}",
+                format
+            )),
+        }
+    }
+
+    /// The format going by the file extension, a tag list for anything unknown
+    pub fn from_path(path: &Path) -> ImportFormat {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| ImportFormat::parse(extension).ok())
+            .unwrap_or(ImportFormat::Tags)
+    }
+}
+
+/// A track as the file describes it
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImportEntry {
+    pub title: String,
+    pub artists: Vec<String>,
+    pub album: Option<String>,
+    pub duration_ms: Option<u32>,
+    pub isrc: Option<String>,
+    pub uri: Option<String>,
+}
+
+impl ImportEntry {
+    /// `Artist - Title`, as the report lists it
+    pub fn describe(&self) -> String {
+        if self.artists.is_empty() {
+            self.title.clone()
+        } else {
+            format!("{} - {}", self.artists.join(", "), self.title)
+        }
+    }
+
+    // `Artist - Title`, or only the title when there is no ` - ` in it
+    fn from_display(display: &str) -> ImportEntry {
+        let display = display.trim();
+        match display.split_once(" - ") {
+            Some((artists, title)) => ImportEntry {
+                title: title.trim().to_string(),
+                artists: split_artists(artists),
+                ..ImportEntry::default()
+            },
+            None => ImportEntry {
+                title: display.to_string(),
+                ..ImportEntry::default()
+            },
+        }
+    }
+}
+
+fn split_artists(artists: &str) -> Vec<String> {
+    artists
+        .split([',', ';'])
+        .map(str::trim)
+        .filter(|artist| !artist.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn track_uri(location: &str) -> Option<String> {
+    match SpotifyUri::parse(location) {
+        Some(uri) if uri.kind == UriKind::Track => Some(format!("spotify:track:{}", uri.id)),
+        _ => None,
+    }
+}
+
+/// Read the entries of a file in `format`
+pub fn parse(format: ImportFormat, text: &str) -> Result<Vec<ImportEntry>, failure::Error> {
+    let entries = match format {
+        ImportFormat::M3u => parse_m3u(text),
+        ImportFormat::Csv => parse_csv(text)?,
+        ImportFormat::Tags => parse_tags(text),
+    };
+    if entries.is_empty() {
+        return Err(err_msg("No tracks found to import"));
+    }
+    Ok(entries)
+}
+
+// Extended M3U, with the artist and title from `#EXTINF` or else from the file name
+fn parse_m3u(text: &str) -> Vec<ImportEntry> {
+    let mut entries = vec![];
+    let mut info: Option<ImportEntry> = None;
+    for line in text.lines().map(str::trim) {
+        if let Some(extinf) = line.strip_prefix("#EXTINF:") {
+            let (seconds, display) = extinf.split_once(',').unwrap_or((extinf, ""));
+            let mut entry = ImportEntry::from_display(display);
+            entry.duration_ms = seconds
+                .trim()
+                .parse::<i64>()
+                .ok()
+                .filter(|seconds| *seconds > 0)
+                .map(|seconds| seconds as u32 * 1000);
+            info = Some(entry);
+        } else if let Some(album) = line.strip_prefix("#EXTALB:") {
+            if let Some(entry) = &mut info {
+                entry.album = Some(album.trim().to_string()).filter(|album| !album.is_empty());
+            }
+        } else if !line.is_empty() && !line.starts_with('#') {
+            let mut entry = info.take().unwrap_or_else(|| {
+                // `Artist - Title.mp3`, without the directories and a leading track number
+                let file_name = Path::new(line)
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .unwrap_or(line);
+                let file_name = file_name
+                    .trim_start_matches(|c: char| c.is_ascii_digit())
+                    .trim_start_matches(['.', ' ', '-']);
+                ImportEntry::from_display(file_name)
+            });
+            entry.uri = track_uri(line);
+            if !entry.title.is_empty() || entry.uri.is_some() {
+                entries.push(entry);
+            }
+        }
+    }
+    entries
+}
+
+// A header row names the columns, which can come in any order. The names from `spt export` and
+// other exporters are understood
+fn parse_csv(text: &str) -> Result<Vec<ImportEntry>, failure::Error> {
+    let mut rows = csv_rows(text).into_iter();
+    let header = rows
+        .next()
+        .ok_or_else(|| err_msg("The CSV file is empty"))?
+        .iter()
+        .map(|name| name.trim().to_lowercase())
+        .collect::<Vec<String>>();
+    let column = |names: &[&str]| {
+        header
+            .iter()
+            .position(|name| names.contains(&name.as_str()))
+    };
+    let title_column = column(&["title", "name", "track", "track name", "song"])
+        .ok_or_else(|| err_msg("The CSV file needs a title column"))?;
+    let artist_column = column(&["artists", "artist", "artist name", "artist name(s)"]);
+    let album_column = column(&["album", "album name"]);
+    let duration_ms_column = column(&["duration_ms", "duration (ms)"]);
+    let duration_column = column(&["duration", "length"]);
+    let isrc_column = column(&["isrc"]);
+    let uri_column = column(&["uri", "track uri", "spotify uri"]);
+
+    Ok(rows
+        .filter_map(|row| {
+            let field = |column: Option<usize>| {
+                column
+                    .and_then(|column| row.get(column))
+                    .map(|field| field.trim())
+                    .filter(|field| !field.is_empty())
+            };
+            let title = field(Some(title_column))?;
+            Some(ImportEntry {
+                title: title.to_string(),
+                artists: field(artist_column).map_or(vec![], split_artists),
+                album: field(album_column).map(String::from),
+                duration_ms: field(duration_ms_column)
+                    .and_then(|duration| duration.parse().ok())
+                    .or_else(|| field(duration_column).and_then(parse_duration)),
+                isrc: field(isrc_column).map(|isrc| isrc.to_uppercase()),
+                uri: field(uri_column).and_then(track_uri),
+            })
+        })
+        .collect())
+}
+
+// `m:ss`, `h:mm:ss` or seconds
+fn parse_duration(duration: &str) -> Option<u32> {
+    let mut seconds = 0;
+    for part in duration.split(':') {
+        seconds = seconds * 60 + part.trim().parse::<u32>().ok()?;
+    }
+    Some(seconds * 1000)
+}
+
+// RFC 4180: fields are separated by commas and may be quoted, with `""` for a quote
+fn csv_rows(text: &str) -> Vec<Vec<String>> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => row.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes => {}
+            '\n' if !in_quotes => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows.retain(|row| row.iter().any(|field| !field.trim().is_empty()));
+    rows
+}
+
+// `Artist - Title` or `Artist - Album - Title` (the default of `beet ls`), `#` starts a comment
+fn parse_tags(text: &str) -> Vec<ImportEntry> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let parts = line.split(" - ").map(str::trim).collect::<Vec<&str>>();
+            match parts.as_slice() {
+                [artists, album, title] => ImportEntry {
+                    title: title.to_string(),
+                    artists: split_artists(artists),
+                    album: Some(album.to_string()),
+                    ..ImportEntry::default()
+                },
+                _ => ImportEntry::from_display(line),
+            }
+        })
+        .collect()
+}
+
+// Lowercase words, without what is in brackets or after ` - `, e.g. `(feat. X)` or
+// `- Remastered 2011`, unless that would leave nothing
+fn normalize(text: &str) -> String {
+    let mut stripped = String::new();
+    let mut depth = 0;
+    for c in text.split(" - ").next().unwrap_or(text).chars() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = (depth - 1).max(0),
+            c if depth == 0 => stripped.push(c),
+            _ => {}
+        }
+    }
+    let words = |text: &str| {
+        text.to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<&str>>()
+            .join(" ")
+    };
+    let normalized = words(&stripped);
+    if normalized.is_empty() {
+        words(text)
+    } else {
+        normalized
+    }
+}
+
+// The Sørensen–Dice coefficient of the character pairs, 1 for the same text
+fn similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (normalize(a), normalize(b));
+    if a == b {
+        return 1.0;
+    }
+    let pairs = |text: &str| {
+        let chars = text.chars().collect::<Vec<char>>();
+        chars
+            .windows(2)
+            .map(|pair| (pair[0], pair[1]))
+            .collect::<Vec<(char, char)>>()
+    };
+    let (a_pairs, mut b_pairs) = (pairs(&a), pairs(&b));
+    if a_pairs.is_empty() || b_pairs.is_empty() {
+        return 0.0;
+    }
+    let total = (a_pairs.len() + b_pairs.len()) as f64;
+    let mut shared = 0;
+    for pair in a_pairs {
+        if let Some(index) = b_pairs.iter().position(|other| *other == pair) {
+            b_pairs.swap_remove(index);
+            shared += 1;
+        }
+    }
+    2.0 * f64::from(shared) / total
+}
+
+/// How well `track` matches `entry`, from 0 to 1
+pub fn score(entry: &ImportEntry, track: &FullTrack) -> f64 {
+    if entry.isrc.is_some() && entry.isrc.as_ref() == track.external_ids.get("isrc") {
+        return 1.0;
+    }
+
+    let mut scores = vec![(similarity(&entry.title, &track.name), TITLE_WEIGHT)];
+    if !entry.artists.is_empty() {
+        let track_artists = track
+            .artists
+            .iter()
+            .map(|artist| artist.name.as_str())
+            .collect::<Vec<&str>>();
+        // The artists as a whole, or the first one among the track's, whichever is closer
+        let together = similarity(&entry.artists.join(" "), &track_artists.join(" "));
+        let first = track_artists
+            .iter()
+            .map(|artist| similarity(&entry.artists[0], artist))
+            .fold(0.0, f64::max);
+        scores.push((together.max(first), ARTIST_WEIGHT));
+    }
+    if let Some(duration_ms) = entry.duration_ms {
+        let difference = (i64::from(duration_ms) - i64::from(track.duration_ms)).unsigned_abs();
+        let over = difference.saturating_sub(u64::from(DURATION_SLACK_MS)) as f64;
+        scores.push(((1.0 - over / DURATION_RANGE_MS).max(0.0), DURATION_WEIGHT));
+    }
+
+    let total_weight = scores.iter().map(|(_, weight)| weight).sum::<f64>();
+    scores
+        .iter()
+        .map(|(score, weight)| score * weight)
+        .sum::<f64>()
+        / total_weight
+}
+
+#[derive(Clone, Debug)]
+pub struct Candidate {
+    pub track: FullTrack,
+    pub score: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Decision {
+    // The candidate at this index goes in the playlist
+    Accepted(usize),
+    Pending,
+    Skipped,
+}
+
+#[derive(Clone, Debug)]
+pub struct ImportItem {
+    pub entry: ImportEntry,
+    // Best first
+    pub candidates: Vec<Candidate>,
+    pub decision: Decision,
+}
+
+impl ImportItem {
+    fn new(entry: ImportEntry, mut candidates: Vec<Candidate>) -> ImportItem {
+        candidates.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        candidates.truncate(MAX_CANDIDATES);
+        let decision = match candidates.first() {
+            Some(best) if best.score >= CONFIDENT_SCORE => Decision::Accepted(0),
+            Some(_) => Decision::Pending,
+            None => Decision::Skipped,
+        };
+        ImportItem {
+            entry,
+            candidates,
+            decision,
+        }
+    }
+
+    fn matched(&self) -> Option<&FullTrack> {
+        match self.decision {
+            Decision::Accepted(index) => self.candidates.get(index).map(|c| &c.track),
+            _ => None,
+        }
+    }
+}
+
+/// Look up `entry`: by URI, then by ISRC, then by searching for the artist and title
+fn match_entry(
+    spotify: &dyn SpotifyApi,
+    entry: ImportEntry,
+    country: Option<Country>,
+) -> Result<ImportItem, failure::Error> {
+    if let Some(uri) = &entry.uri {
+        // Tracks that are no longer available are searched for below
+        if let Ok(track) = spotify.track(uri) {
+            let candidate = Candidate { track, score: 1.0 };
+            return Ok(ImportItem::new(entry, vec![candidate]));
+        }
+    }
+
+    if let Some(isrc) = &entry.isrc {
+        let result = spotify.search_track(
+            &format!("isrc:{}", isrc),
+            Some(SEARCH_LIMIT),
+            Some(0),
+            country,
+        )?;
+        let candidates = result
+            .tracks
+            .items
+            .into_iter()
+            .filter(|track| track.external_ids.get("isrc") == Some(isrc))
+            .map(|track| Candidate { track, score: 1.0 })
+            .collect::<Vec<Candidate>>();
+        if !candidates.is_empty() {
+            return Ok(ImportItem::new(entry, candidates));
+        }
+    }
+
+    let query = match entry.artists.first() {
+        Some(artist) => format!("{} {}", artist, normalize(&entry.title)),
+        None => normalize(&entry.title),
+    };
+    let result = spotify.search_track(&query, Some(SEARCH_LIMIT), Some(0), country)?;
+    let candidates = result
+        .tracks
+        .items
+        .into_iter()
+        .map(|track| Candidate {
+            score: score(&entry, &track),
+            track,
+        })
+        .collect();
+    Ok(ImportItem::new(entry, candidates))
+}
+
+/// What an import added and left out
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImportReport {
+    pub playlist_name: String,
+    pub added: usize,
+    // Skipped on review
+    pub skipped: Vec<String>,
+    // Without any search results
+    pub unmatched: Vec<String>,
+}
+
+impl ImportReport {
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "Created the playlist {} with {} tracks",
+            self.playlist_name, self.added
+        )];
+        if !self.unmatched.is_empty() {
+            lines.push(String::new());
+            lines.push(format!("Not found ({}):", self.unmatched.len()));
+            lines.extend(self.unmatched.iter().map(|entry| format!("  {}", entry)));
+        }
+        if !self.skipped.is_empty() {
+            lines.push(String::new());
+            lines.push(format!("Skipped ({}):", self.skipped.len()));
+            lines.extend(self.skipped.iter().map(|entry| format!("  {}", entry)));
+        }
+        lines
+    }
+}
+
+/// An import in progress, from matching to the report
+pub struct Import {
+    pub name: String,
+    pub items: Vec<ImportItem>,
+    // The item being reviewed, `None` once every item is decided
+    pub review_index: Option<usize>,
+    pub selected_candidate: usize,
+    pub report: Option<ImportReport>,
+}
+
+impl Import {
+    pub fn new(name: &str, items: Vec<ImportItem>) -> Import {
+        let mut import = Import {
+            name: name.to_string(),
+            items,
+            review_index: None,
+            selected_candidate: 0,
+            report: None,
+        };
+        import.next_review();
+        import
+    }
+
+    /// Match every entry, calling `progress` with the number done and the total after each
+    pub fn match_entries(
+        spotify: &dyn SpotifyApi,
+        name: &str,
+        entries: Vec<ImportEntry>,
+        country: Option<Country>,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<Import, failure::Error> {
+        let total = entries.len();
+        let mut items = vec![];
+        for entry in entries {
+            items.push(match_entry(spotify, entry, country)?);
+            progress(items.len(), total);
+        }
+        Ok(Import::new(name, items))
+    }
+
+    pub fn reviewing(&self) -> Option<&ImportItem> {
+        self.review_index.and_then(|index| self.items.get(index))
+    }
+
+    /// Which of the uncertain matches is being reviewed and how many there are, e.g. (3, 7)
+    pub fn review_progress(&self) -> (usize, usize) {
+        let needing_review = |item: &&ImportItem| {
+            item.decision == Decision::Pending
+                || item
+                    .candidates
+                    .first()
+                    .is_some_and(|best| best.score < CONFIDENT_SCORE)
+        };
+        let total = self.items.iter().filter(needing_review).count();
+        let done = self.review_index.map_or(total, |index| {
+            self.items[..index].iter().filter(needing_review).count() + 1
+        });
+        (done, total)
+    }
+
+    pub fn accept(&mut self) {
+        if let Some(index) = self.review_index {
+            self.items[index].decision = Decision::Accepted(self.selected_candidate);
+            self.next_review();
+        }
+    }
+
+    pub fn skip(&mut self) {
+        if let Some(index) = self.review_index {
+            self.items[index].decision = Decision::Skipped;
+            self.next_review();
+        }
+    }
+
+    fn next_review(&mut self) {
+        self.review_index = self
+            .items
+            .iter()
+            .position(|item| item.decision == Decision::Pending);
+        self.selected_candidate = 0;
+    }
+
+    /// Create the playlist with every accepted track, in the order of the file
+    pub fn create_playlist(
+        &mut self,
+        spotify: &dyn SpotifyApi,
+        user_id: &str,
+    ) -> Result<&ImportReport, failure::Error> {
+        let track_ids = self
+            .items
+            .iter()
+            .filter_map(ImportItem::matched)
+            .filter_map(|track| track.id.clone())
+            .collect::<Vec<String>>();
+        let playlist = spotify.user_playlist_create(user_id, &self.name, Some(false), None)?;
+        for chunk in track_ids.chunks(ADD_TRACKS_LIMIT) {
+            spotify.user_playlist_add_tracks(user_id, &playlist.id, chunk, None)?;
+        }
+
+        let left_out = |has_candidates: bool| {
+            self.items
+                .iter()
+                .filter(|item| {
+                    item.decision == Decision::Skipped
+                        && item.candidates.is_empty() != has_candidates
+                })
+                .map(|item| item.entry.describe())
+                .collect::<Vec<String>>()
+        };
+        let report = ImportReport {
+            playlist_name: self.name.clone(),
+            added: track_ids.len(),
+            skipped: left_out(true),
+            unmatched: left_out(false),
+        };
+        Ok(self.report.get_or_insert(report))
+    }
+}
+
+/// `spt import` up to the review: read the file at `path` and match its tracks, printing the
+/// progress. Without `format` it is guessed from the extension, and without `name` the playlist
+/// is named after the file. Returns the user's id as well, for `Import::create_playlist`
+pub fn match_file(
+    spotify: &dyn SpotifyApi,
+    path: &Path,
+    format: Option<ImportFormat>,
+    name: Option<&str>,
+) -> Result<(Import, String), failure::Error> {
+    let format = format.unwrap_or_else(|| ImportFormat::from_path(path));
+    let entries = parse(format, &fs::read_to_string(path)?)?;
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "Imported".to_string()),
+    };
+
+    let user = spotify.current_user()?;
+    let country = user
+        .country
+        .as_ref()
+        .and_then(|country| Country::from_str(country).ok());
+    let import = Import::match_entries(spotify, &name, entries, country, |done, total| {
+        eprint!("\rMatching tracks {}/{}", done, total)
+    })?;
+    eprintln!();
+    Ok((import, user.id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spotify_api::fake::{self, FakeSpotify};
+    use serde_json::json;
+
+    fn entry(artist: &str, title: &str, duration_ms: Option<u32>) -> ImportEntry {
+        ImportEntry {
+            title: title.to_string(),
+            artists: vec![artist.to_string()],
+            duration_ms,
+            ..ImportEntry::default()
+        }
+    }
+
+    fn full_track(id: &str, name: &str, artist: &str, duration_ms: u32) -> FullTrack {
+        let track = fake::TrackBuilder::new(id, name, artist)
+            .duration_ms(duration_ms)
+            .build();
+        serde_json::from_value(track).unwrap()
+    }
+
+    #[test]
+    fn test_parse_m3u() {
+        let entries = parse(
+            ImportFormat::M3u,
+            "#EXTM3U\n\
+             #EXTINF:181,Band, Singer - Song\n#EXTALB:Album\nspotify:track:t1\n\
+             #EXTINF:-1,Untitled\n/music/untitled.mp3\n\
+             /music/Band/03 - Band - Other Song.flac\n",
+        )
+        .unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                ImportEntry {
+                    title: "Song".to_string(),
+                    artists: vec!["Band".to_string(), "Singer".to_string()],
+                    album: Some("Album".to_string()),
+                    duration_ms: Some(181_000),
+                    isrc: None,
+                    uri: Some("spotify:track:t1".to_string()),
+                },
+                ImportEntry {
+                    title: "Untitled".to_string(),
+                    ..ImportEntry::default()
+                },
+                entry("Band", "Other Song", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        // As `spt export` writes it
+        let entries = parse(
+            ImportFormat::Csv,
+            "title,artists,album,duration_ms,isrc,uri,added_at\r\n\
+             \"Song, with a comma\",\"Band, Singer\",Album,181500,usrc17607839,spotify:track:t1,\r\n",
+        )
+        .unwrap();
+        assert_eq!(entries[0].title, "Song, with a comma");
+        assert_eq!(entries[0].artists, vec!["Band", "Singer"]);
+        assert_eq!(entries[0].duration_ms, Some(181_500));
+        assert_eq!(entries[0].isrc.as_deref(), Some("USRC17607839"));
+        assert_eq!(entries[0].uri.as_deref(), Some("spotify:track:t1"));
+
+        // Other column names and orders, durations as m:ss
+        let entries = parse(
+            ImportFormat::Csv,
+            "\u{feff}Artist,Length,Track Name\nBand,3:01,\"Say \"\"hi\"\"\"\n\n,,\n",
+        )
+        .unwrap();
+        assert_eq!(entries, vec![entry("Band", "Say \"hi\"", Some(181_000))]);
+
+        assert!(parse(ImportFormat::Csv, "artist,album\nBand,Album\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_tags() {
+        let entries = parse(
+            ImportFormat::Tags,
+            "# From beets\nBand - Album - Song\n\nSinger - Other Song\nJust a title\n",
+        )
+        .unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].album.as_deref(), Some("Album"));
+        assert_eq!(entries[1], entry("Singer", "Other Song", None));
+        assert_eq!(entries[2].title, "Just a title");
+        assert!(parse(ImportFormat::Tags, "# Nothing\n").is_err());
+        assert_eq!(
+            ImportFormat::from_path(Path::new("list.M3U8")),
+            ImportFormat::M3u
+        );
+    }
+
+    #[test]
+    fn test_score() {
+        let wanted = entry("The Band", "Song (feat. Singer)", Some(180_000));
+        let exact = full_track("1", "Song", "The Band", 181_000);
+        let remaster = full_track("2", "Song - Remastered 2011", "The Band", 186_000);
+        let cover = full_track("3", "Song", "Tribute Stars", 150_000);
+        let other = full_track("4", "Another Tune", "The Band", 180_000);
+
+        assert!(score(&wanted, &exact) > 0.99);
+        assert!(score(&wanted, &remaster) >= CONFIDENT_SCORE);
+        assert!(score(&wanted, &cover) < CONFIDENT_SCORE);
+        assert!(score(&wanted, &other) < score(&wanted, &cover));
+
+        let mut with_isrc = wanted.clone();
+        with_isrc.isrc = Some("USRC17607839".to_string());
+        let mut isrc_match = other.clone();
+        isrc_match
+            .external_ids
+            .insert("isrc".to_string(), "USRC17607839".to_string());
+        assert_eq!(score(&with_isrc, &isrc_match), 1.0);
+    }
+
+    #[test]
+    fn test_import() {
+        let spotify = FakeSpotify::new();
+        spotify
+            .respond(
+                "search_track",
+                json!({ "tracks": fake::page(vec![
+                    fake::track("t1", "Song", "Band"),
+                    fake::track("t2", "Song (Live)", "Tribute Stars"),
+                ]) }),
+            )
+            .respond("track", fake::track("t3", "Linked", "Band"))
+            .respond(
+                "user_playlist_create",
+                fake::full_playlist("new", "Imported", "alice"),
+            )
+            .respond("user_playlist_add_tracks", json!({ "snapshot_id": "s" }));
+
+        let entries = vec![
+            entry("Band", "Song", Some(180_000)),
+            entry("Someone Else", "Sang", None),
+            ImportEntry {
+                uri: Some("spotify:track:t3".to_string()),
+                ..entry("Band", "Linked", None)
+            },
+            entry("Nobody", "Nothing", None),
+        ];
+        let mut progress = vec![];
+        let mut import =
+            Import::match_entries(&spotify, "Imported", entries, None, |done, total| {
+                progress.push((done, total))
+            })
+            .unwrap();
+        assert_eq!(progress.last(), Some(&(4, 4)));
+        assert_eq!(
+            spotify.calls_to("search_track"),
+            vec![
+                "\"Band song\", Some(10), Some(0), None",
+                "\"Someone Else sang\", Some(10), Some(0), None",
+                "\"Nobody nothing\", Some(10), Some(0), None",
+            ]
+        );
+
+        let decisions = import
+            .items
+            .iter()
+            .map(|item| item.decision)
+            .collect::<Vec<Decision>>();
+        assert_eq!(
+            decisions,
+            vec![
+                Decision::Accepted(0),
+                Decision::Pending,
+                Decision::Accepted(0),
+                Decision::Pending
+            ]
+        );
+        assert_eq!(import.review_index, Some(1));
+        assert_eq!(import.review_progress(), (1, 2));
+
+        // Pick the second result for the first, skip the other
+        let ids = import.items[1]
+            .candidates
+            .iter()
+            .map(|candidate| candidate.track.id.clone().unwrap())
+            .collect::<Vec<String>>();
+        assert_eq!(ids, vec!["t2", "t1"]);
+        import.selected_candidate = 1;
+        import.accept();
+        assert_eq!(import.review_index, Some(3));
+        assert_eq!(import.review_progress(), (2, 2));
+        import.skip();
+        assert_eq!(import.review_index, None);
+
+        let report = import.create_playlist(&spotify, "alice").unwrap().clone();
+        assert_eq!(
+            spotify.calls_to("user_playlist_add_tracks"),
+            vec!["\"alice\", \"new\", [\"t1\", \"t1\", \"t3\"], None"]
+        );
+        assert_eq!(report.added, 3);
+        assert_eq!(report.skipped, vec!["Nobody - Nothing"]);
+        assert!(report.unmatched.is_empty());
+    }
+}
@@ -0,0 +1,54 @@
+//! The library behind `spt`, the terminal user interface for Spotify. The binary is a thin
+//! frontend over it, and the same pieces can be used to build other tools:
+//!
+//! - [`config`] and [`user_config`]: loading `client.yml` and `config.yml` for a profile
+//! - [`auth`]: logging in and turning the token into a [`spotify_api::WebApiClient`]
+//! - [`spotify_api`]: the [`spotify_api::SpotifyApi`] trait over every endpoint the app uses,
+//!   and recording or replaying its traffic
+//! - [`playback`]: a [`playback::PlaybackController`] for play, pause, seek, volume and the like
+//! - [`uri`]: parsing Spotify URIs and open.spotify.com links
+//! - [`export`]: exporting playlists and the library to CSV, JSON, M3U and XSPF
+//! - [`import`]: creating a playlist from a local M3U, CSV or tag list
+//! - [`history`]: the local listening history and the stats drawn from it
+//! - [`scrobbler`]: scrobbling plays to Last.fm or ListenBrainz
+//! - [`app`], [`handlers`] and [`ui`]: the app state, its key handling and the widgets drawing
+//!   it, which [`runner::start_ui`] ties together in a terminal
+//!
+//! # Stability
+//!
+//! Only the public items of [`config`], [`auth`], [`spotify_api`], [`playback`], [`uri`],
+//! [`export`] and [`import`] follow semver: while the version is `0.x`, a breaking change to
+//! them bumps the minor version and everything else only bumps the patch version.
+//!
+//! [`app`], [`handlers`], [`ui`], [`runner`], [`user_config`], [`event`], [`history`] and
+//! [`scrobbler`] are public so a frontend can reuse them, but they are unstable: they grow with
+//! nearly every feature of `spt` (new `App` fields, keybindings and `draw_*` functions) and can
+//! change in any release. The same goes for everything hidden from the docs.
+pub mod app;
+pub mod auth;
+pub mod config;
+pub mod event;
+pub mod export;
+pub mod handlers;
+pub mod history;
+pub mod import;
+pub mod playback;
+pub mod runner;
+pub mod scrobbler;
+pub mod spotify_api;
+pub mod ui;
+pub mod uri;
+pub mod user_config;
+
+mod album_art;
+#[doc(hidden)]
+pub mod banner;
+mod credentials;
+mod lyrics;
+#[cfg(any(test, feature = "mock-api"))]
+#[doc(hidden)]
+pub mod mock_api;
+mod oauth;
+mod qr;
+mod recommendations;
+mod redirect_uri;
@@ -0,0 +1,570 @@
+use failure::{err_msg, format_err};
+use std::{
+    collections::HashSet,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+const LYRICS_EXTENSIONS: [&str; 2] = ["lrc", "txt"];
+// A file whose `[length:]` tag is further than this from the track is for another version of it
+const MAX_DURATION_DIFFERENCE_MS: u32 = 5_000;
+// Provider commands usually fetch from the network, one that hangs is killed after this long
+const COMMAND_TIMEOUT_SECONDS: u64 = 10;
+const COMMAND_POLL_MILLISECONDS: u64 = 50;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LyricLine {
+    pub start_ms: Option<u128>,
+    pub text: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Lyrics {
+    pub lines: Vec<LyricLine>,
+    pub artist: Option<String>,
+    pub title: Option<String>,
+    pub length_ms: Option<u32>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LyricsQuery {
+    pub artist: String,
+    pub title: String,
+    pub album: String,
+    pub duration_ms: u32,
+}
+
+type FoundLyrics = (String, Result<Option<Lyrics>, failure::Error>);
+
+// Looks lyrics up on their own threads, so a slow provider command never freezes the UI
+pub struct LyricsLoader {
+    pending_track_ids: HashSet<String>,
+    found_tx: Sender<FoundLyrics>,
+    found_rx: Receiver<FoundLyrics>,
+}
+
+impl Default for LyricsLoader {
+    fn default() -> Self {
+        let (found_tx, found_rx) = mpsc::channel();
+        LyricsLoader {
+            pending_track_ids: HashSet::new(),
+            found_tx,
+            found_rx,
+        }
+    }
+}
+
+impl LyricsLoader {
+    pub fn is_pending(&self, track_id: &str) -> bool {
+        self.pending_track_ids.contains(track_id)
+    }
+
+    // Starts a lookup in the background, its result comes out of `receive`
+    pub fn load(
+        &mut self,
+        track_id: &str,
+        query: LyricsQuery,
+        lyrics_dir: Option<String>,
+        lyrics_command: Option<String>,
+    ) {
+        if !self.pending_track_ids.insert(track_id.to_string()) {
+            return;
+        }
+
+        let track_id = track_id.to_string();
+        let found_tx = self.found_tx.clone();
+        thread::spawn(move || {
+            let result = find_lyrics(lyrics_dir.as_deref(), lyrics_command.as_deref(), &query);
+            // The receiver is only gone when the app is exiting
+            found_tx.send((track_id, result)).ok();
+        });
+    }
+
+    pub fn receive(&mut self) -> Vec<FoundLyrics> {
+        let mut found = vec![];
+        while let Ok((track_id, result)) = self.found_rx.try_recv() {
+            self.pending_track_ids.remove(&track_id);
+            found.push((track_id, result));
+        }
+        found
+    }
+}
+
+impl Lyrics {
+    // Parses the LRC format, anything without timestamps is kept as plain text
+    pub fn parse(contents: &str) -> Lyrics {
+        let mut lyrics = Lyrics::default();
+        let mut offset_ms = 0i64;
+        let mut synced_lines = vec![];
+        let mut plain_lines = vec![];
+
+        for line in contents.lines() {
+            let mut rest = line.trim();
+            let mut timestamps = vec![];
+            let mut is_tag_line = false;
+
+            while rest.starts_with('[') {
+                let end = match rest.find(']') {
+                    Some(end) => end,
+                    None => break,
+                };
+                let tag = &rest[1..end];
+                rest = &rest[end + 1..];
+
+                if let Some(timestamp) = parse_timestamp(tag) {
+                    timestamps.push(timestamp);
+                    continue;
+                }
+
+                is_tag_line = true;
+                let mut parts = tag.splitn(2, ':');
+                let (key, value) = (
+                    parts.next().unwrap_or_default().trim(),
+                    parts.next().unwrap_or_default().trim(),
+                );
+                match key {
+                    "ar" => lyrics.artist = Some(value.to_string()),
+                    "ti" => lyrics.title = Some(value.to_string()),
+                    "length" => lyrics.length_ms = parse_timestamp(value).map(|ms| ms as u32),
+                    "offset" => offset_ms = value.parse().unwrap_or(0),
+                    _ => {}
+                }
+            }
+
+            let text = rest.trim().to_string();
+            if !timestamps.is_empty() {
+                for timestamp in timestamps {
+                    synced_lines.push((timestamp, text.clone()));
+                }
+            } else if !is_tag_line {
+                plain_lines.push(text);
+            }
+        }
+
+        if synced_lines.is_empty() {
+            // Drop the blank lines left over from the tags at the top of the file
+            let start = plain_lines
+                .iter()
+                .position(|line| !line.is_empty())
+                .unwrap_or(plain_lines.len());
+            lyrics.lines = plain_lines[start..]
+                .iter()
+                .map(|text| LyricLine {
+                    start_ms: None,
+                    text: text.to_string(),
+                })
+                .collect();
+        } else {
+            synced_lines.sort_by_key(|(timestamp, _)| *timestamp);
+            // A positive offset makes the lyrics appear sooner
+            lyrics.lines = synced_lines
+                .into_iter()
+                .map(|(timestamp, text)| LyricLine {
+                    start_ms: Some(max_zero(timestamp as i64 - offset_ms)),
+                    text,
+                })
+                .collect();
+        }
+
+        lyrics
+    }
+
+    pub fn is_synced(&self) -> bool {
+        self.lines.iter().any(|line| line.start_ms.is_some())
+    }
+
+    pub fn get_current_line_index(&self, progress_ms: u128) -> Option<usize> {
+        self.lines.iter().rposition(|line| {
+            line.start_ms
+                .is_some_and(|start_ms| start_ms <= progress_ms)
+        })
+    }
+
+    fn matches(&self, query: &LyricsQuery) -> bool {
+        if let Some(length_ms) = self.length_ms {
+            if length_ms.abs_diff(query.duration_ms) > MAX_DURATION_DIFFERENCE_MS {
+                return false;
+            }
+        }
+
+        match (&self.artist, &self.title) {
+            (Some(artist), Some(title)) => {
+                normalize(title) == normalize(&query.title)
+                    && normalize(&query.artist).contains(&normalize(artist))
+            }
+            (None, Some(title)) => normalize(title) == normalize(&query.title),
+            _ => true,
+        }
+    }
+}
+
+fn max_zero(value: i64) -> u128 {
+    if value > 0 {
+        value as u128
+    } else {
+        0
+    }
+}
+
+// Parses `mm:ss`, `mm:ss.xx` and `mm:ss.xxx` into milliseconds
+fn parse_timestamp(timestamp: &str) -> Option<u128> {
+    let mut parts = timestamp.trim().splitn(2, ':');
+    let minutes = parts.next()?.parse::<u128>().ok()?;
+    let seconds_part = parts.next()?;
+
+    let mut seconds_parts = seconds_part.splitn(2, ['.', ':']);
+    let seconds = seconds_parts.next()?.parse::<u128>().ok()?;
+    let fraction_ms = match seconds_parts.next() {
+        Some(fraction) if !fraction.is_empty() && fraction.chars().all(|c| c.is_ascii_digit()) => {
+            let digits = fraction.chars().take(3).collect::<String>();
+            digits.parse::<u128>().ok()? * 10u128.pow(3 - digits.len() as u32)
+        }
+        Some(_) => return None,
+        None => 0,
+    };
+
+    Some(minutes * 60_000 + seconds * 1000 + fraction_ms)
+}
+
+// Compare names without case, punctuation or spacing, e.g. "AC/DC" and "ac dc"
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+pub(crate) fn expand_home(path: &str) -> PathBuf {
+    if let Some(relative) = path.strip_prefix("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(relative);
+        }
+    }
+    PathBuf::from(path)
+}
+
+// Files are named `Artist - Title.lrc`, `Title.lrc` or anything with matching `[ar:]` and `[ti:]`
+// tags
+fn find_in_dir(dir: &Path, query: &LyricsQuery) -> Result<Option<Lyrics>, failure::Error> {
+    if !dir.is_dir() {
+        return Ok(None);
+    }
+
+    let first_artist = query.artist.split(", ").next().unwrap_or(&query.artist);
+    let full_name = normalize(&format!("{} - {}", first_artist, query.title));
+    let full_name_all_artists = normalize(&format!("{} - {}", query.artist, query.title));
+    let title_name = normalize(&query.title);
+
+    let mut best_match: Option<(u8, Lyrics)> = None;
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_lyrics_file = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| {
+                LYRICS_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+            });
+        if !is_lyrics_file {
+            continue;
+        }
+
+        let stem = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .map(normalize)
+            .unwrap_or_default();
+
+        // Lower is better
+        let rank = if stem == full_name || stem == full_name_all_artists {
+            0
+        } else if stem == title_name {
+            1
+        } else {
+            2
+        };
+        if best_match
+            .as_ref()
+            .is_some_and(|(best_rank, _)| *best_rank <= rank)
+        {
+            continue;
+        }
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(_) => continue,
+        };
+        let lyrics = Lyrics::parse(&contents);
+
+        let has_tags = lyrics.title.is_some();
+        if (rank < 2 || has_tags) && lyrics.matches(query) && !lyrics.lines.is_empty() {
+            best_match = Some((rank, lyrics));
+        }
+    }
+
+    Ok(best_match.map(|(_, lyrics)| lyrics))
+}
+
+// The command gets the track through environment variables and prints LRC or plain text. A
+// non-zero exit status or empty output means it has no lyrics for the track
+fn run_command(
+    command: &str,
+    query: &LyricsQuery,
+    timeout: Duration,
+) -> Result<Option<Lyrics>, failure::Error> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+
+    let mut child = shell
+        .arg(command)
+        .env("SPT_LYRICS_ARTIST", &query.artist)
+        .env("SPT_LYRICS_TITLE", &query.title)
+        .env("SPT_LYRICS_ALBUM", &query.album)
+        .env("SPT_LYRICS_DURATION_MS", query.duration_ms.to_string())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format_err!("Failed to run lyrics command `{}`: {}", command, e))?;
+
+    // Read on another thread, a command printing more than the pipe holds would block otherwise
+    let mut stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| err_msg("Lyrics command has no output"))?;
+    let reader = thread::spawn(move || {
+        let mut output = vec![];
+        stdout.read_to_end(&mut output).map(|_| output)
+    });
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            child.kill().ok();
+            child.wait().ok();
+            return Err(format_err!(
+                "Lyrics command `{}` did not finish within {} seconds",
+                command,
+                timeout.as_secs()
+            ));
+        }
+        thread::sleep(Duration::from_millis(COMMAND_POLL_MILLISECONDS));
+    };
+
+    let output = reader
+        .join()
+        .map_err(|_| err_msg("Failed to read the lyrics command output"))??;
+    if !status.success() {
+        return Ok(None);
+    }
+
+    let contents = String::from_utf8_lossy(&output);
+    if contents.trim().is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(Lyrics::parse(&contents)))
+}
+
+pub fn find_lyrics(
+    lyrics_dir: Option<&str>,
+    lyrics_command: Option<&str>,
+    query: &LyricsQuery,
+) -> Result<Option<Lyrics>, failure::Error> {
+    if let Some(lyrics_dir) = lyrics_dir {
+        if let Some(lyrics) = find_in_dir(&expand_home(lyrics_dir), query)? {
+            return Ok(Some(lyrics));
+        }
+    }
+
+    match lyrics_command {
+        Some(lyrics_command) => run_command(
+            lyrics_command,
+            query,
+            Duration::from_secs(COMMAND_TIMEOUT_SECONDS),
+        ),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LRC: &str = "[ar:Daft Punk]
+[ti:One More Time]
+[length: 05:20]
+[offset:+500]
+
+[00:01.00]One more time
+[00:03.50][00:10.25]We're gonna celebrate
+[00:05]Oh yeah, all right
+";
+
+    fn query() -> LyricsQuery {
+        LyricsQuery {
+            artist: "Daft Punk".to_string(),
+            title: "One More Time".to_string(),
+            album: "Discovery".to_string(),
+            duration_ms: 320_357,
+        }
+    }
+
+    #[test]
+    fn parse_timestamp_test() {
+        assert_eq!(parse_timestamp("01:02"), Some(62_000));
+        assert_eq!(parse_timestamp("01:02.5"), Some(62_500));
+        assert_eq!(parse_timestamp("01:02.50"), Some(62_500));
+        assert_eq!(parse_timestamp("01:02.505"), Some(62_505));
+        assert_eq!(parse_timestamp("ar:Daft Punk"), None);
+    }
+
+    #[test]
+    fn parse_lrc_test() {
+        let lyrics = Lyrics::parse(LRC);
+
+        assert!(lyrics.is_synced());
+        assert_eq!(lyrics.artist, Some("Daft Punk".to_string()));
+        assert_eq!(lyrics.title, Some("One More Time".to_string()));
+        assert_eq!(lyrics.length_ms, Some(320_000));
+        assert_eq!(
+            lyrics.lines,
+            vec![
+                LyricLine {
+                    start_ms: Some(500),
+                    text: "One more time".to_string()
+                },
+                LyricLine {
+                    start_ms: Some(3_000),
+                    text: "We're gonna celebrate".to_string()
+                },
+                LyricLine {
+                    start_ms: Some(4_500),
+                    text: "Oh yeah, all right".to_string()
+                },
+                LyricLine {
+                    start_ms: Some(9_750),
+                    text: "We're gonna celebrate".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_plain_text_test() {
+        let lyrics =
+            Lyrics::parse("[ti:One More Time]\n\nOne more time\n\nWe're gonna celebrate\n");
+
+        assert!(!lyrics.is_synced());
+        assert_eq!(lyrics.get_current_line_index(10_000), None);
+        assert_eq!(
+            lyrics
+                .lines
+                .iter()
+                .map(|line| line.text.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["One more time", "", "We're gonna celebrate"]
+        );
+    }
+
+    #[test]
+    fn get_current_line_index_test() {
+        let lyrics = Lyrics::parse(LRC);
+
+        assert_eq!(lyrics.get_current_line_index(0), None);
+        assert_eq!(lyrics.get_current_line_index(500), Some(0));
+        assert_eq!(lyrics.get_current_line_index(4_000), Some(1));
+        assert_eq!(lyrics.get_current_line_index(60_000), Some(3));
+    }
+
+    #[test]
+    fn matches_test() {
+        let lyrics = Lyrics::parse(LRC);
+        assert!(lyrics.matches(&query()));
+
+        let other_version = LyricsQuery {
+            duration_ms: 600_000,
+            ..query()
+        };
+        assert!(!lyrics.matches(&other_version));
+
+        let other_song = LyricsQuery {
+            title: "Aerodynamic".to_string(),
+            ..query()
+        };
+        assert!(!lyrics.matches(&other_song));
+    }
+
+    #[test]
+    fn find_in_dir_test() {
+        let dir = std::env::temp_dir().join(format!("spt-lyrics-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("daft punk - one more time.lrc"), LRC).unwrap();
+        fs::write(
+            dir.join("tagged.lrc"),
+            "[ti:Aerodynamic]\n[00:01]Instrumental",
+        )
+        .unwrap();
+        fs::write(dir.join("notes.md"), "One More Time").unwrap();
+
+        let lyrics = find_in_dir(&dir, &query()).unwrap().unwrap();
+        assert_eq!(lyrics.lines[0].text, "One more time");
+
+        let tagged = LyricsQuery {
+            title: "Aerodynamic".to_string(),
+            ..query()
+        };
+        let lyrics = find_in_dir(&dir, &tagged).unwrap().unwrap();
+        assert_eq!(lyrics.lines[0].text, "Instrumental");
+
+        let missing = LyricsQuery {
+            title: "Digital Love".to_string(),
+            ..query()
+        };
+        assert_eq!(find_in_dir(&dir, &missing).unwrap(), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_command_test() {
+        let lyrics = run_command(
+            "echo \"[00:01]$SPT_LYRICS_TITLE\"",
+            &query(),
+            Duration::from_secs(5),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(lyrics.lines[0].text, "One More Time");
+
+        assert_eq!(
+            run_command("exit 1", &query(), Duration::from_secs(5)).unwrap(),
+            None
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_command_timeout_test() {
+        let started = Instant::now();
+        let result = run_command("sleep 5", &query(), Duration::from_millis(200));
+
+        assert!(result.is_err());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
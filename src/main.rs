@@ -1,138 +1,50 @@
-mod app;
-mod banner;
-mod config;
-mod event;
-mod handlers;
-mod redirect_uri;
-mod ui;
-mod user_config;
-
-use crate::app::RouteId;
-use crate::event::Key;
-use app::{ActiveBlock, App};
-use backtrace::Backtrace;
-use banner::BANNER;
-use clap::{App as ClapApp, Arg};
-use config::ClientConfig;
-use crossterm::{
-    cursor::MoveTo,
-    event::{DisableMouseCapture, EnableMouseCapture},
-    execute,
-    style::Print,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-    ExecutableCommand,
-};
-use redirect_uri::redirect_uri_web_server;
-use rspotify::spotify::{
-    client::Spotify,
-    oauth2::{SpotifyClientCredentials, SpotifyOAuth, TokenInfo},
-    util::{get_token, process_token, request_token},
-};
-use std::{
-    cmp::{max, min},
-    io::{self, stdout, Write},
-    panic::{self, PanicInfo},
-    time::{Duration, Instant},
+//! `spt`: parses the command line, logs in and hands over to `spotify_tui::runner`.
+use clap::{App as ClapApp, AppSettings, Arg, SubCommand};
+use failure::format_err;
+#[cfg(feature = "mock-api")]
+use spotify_tui::mock_api;
+use spotify_tui::{
+    auth::{build_oauth, get_spotify, get_token_auto, log_in, log_in_headless, Login},
+    banner::BANNER,
+    config::ClientConfig,
+    export::{self, ExportFormat, ExportSource},
+    import::{self, ImportFormat},
+    runner, scrobbler,
+    spotify_api::{
+        traffic::{Recorder, Replayer, Traffic},
+        WebApiClient, DEFAULT_API_URL,
+    },
+    user_config::load_user_config,
 };
-use tui::{
-    backend::{Backend, CrosstermBackend},
-    Terminal,
-};
-use user_config::UserConfig;
-
-const SCOPES: [&str; 13] = [
-    "playlist-read-collaborative",
-    "playlist-read-private",
-    "playlist-modify-private",
-    "playlist-modify-public",
-    "user-follow-read",
-    "user-follow-modify",
-    "user-library-modify",
-    "user-library-read",
-    "user-modify-playback-state",
-    "user-read-currently-playing",
-    "user-read-playback-state",
-    "user-read-private",
-    "user-read-recently-played",
-];
-
-fn get_spotify(token_info: TokenInfo) -> (Spotify, Instant) {
-    let token_expiry = Instant::now()
-        + Duration::from_secs(token_info.expires_in.into())
-        // Set 10 seconds early
-        - Duration::from_secs(10);
-
-    let client_credential = SpotifyClientCredentials::default()
-        .token_info(token_info)
-        .build();
-
-    let spotify = Spotify::default()
-        .client_credentials_manager(client_credential)
-        .build();
-
-    (spotify, token_expiry)
-}
-/// get token automatically with local webserver
-pub fn get_token_auto(spotify_oauth: &mut SpotifyOAuth, port: u16) -> Option<TokenInfo> {
-    match spotify_oauth.get_cached_token() {
-        Some(token_info) => Some(token_info),
-        None => match redirect_uri_web_server(spotify_oauth, port) {
-            Ok(mut url) => process_token(spotify_oauth, &mut url),
-            Err(()) => {
-                println!("Starting webserver failed. Continuing with manual authentication");
-                request_token(spotify_oauth);
-                println!("Enter the URL you were redirected to: ");
-                let mut input = String::new();
-                match io::stdin().read_line(&mut input) {
-                    Ok(_) => process_token(spotify_oauth, &mut input),
-                    Err(_) => None,
-                }
-            }
-        },
-    }
-}
-
-fn close_application() -> Result<(), failure::Error> {
-    disable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, LeaveAlternateScreen, DisableMouseCapture)?;
-    Ok(())
-}
-
-fn panic_hook(info: &PanicInfo<'_>) {
-    if cfg!(debug_assertions) {
-        let location = info.location().unwrap();
-
-        let msg = match info.payload().downcast_ref::<&'static str>() {
-            Some(s) => *s,
-            None => match info.payload().downcast_ref::<String>() {
-                Some(s) => &s[..],
-                None => "Box<Any>",
-            },
-        };
-
-        let stacktrace: String = format!("{:?}", Backtrace::new()).replace('\n', "\n\r");
-
-        disable_raw_mode().unwrap();
-        execute!(
-            io::stdout(),
-            LeaveAlternateScreen,
-            Print(format!(
-                "thread '<unnamed>' panicked at '{}', {}\n\r{}",
-                msg, location, stacktrace
-            )),
-            DisableMouseCapture
+use std::{panic, path::Path, rc::Rc};
+
+// The arguments every `spt export` subcommand takes
+fn export_args<'a, 'b>(subcommand: ClapApp<'a, 'b>) -> ClapApp<'a, 'b> {
+    subcommand
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .short("f")
+                .help("File format")
+                .possible_values(&["csv", "json", "m3u", "xspf"])
+                .default_value("csv")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .short("o")
+                .help("File to write to instead of standard output")
+                .takes_value(true),
         )
-        .unwrap();
-    }
 }
 
 fn main() -> Result<(), failure::Error> {
     panic::set_hook(Box::new(|info| {
-        panic_hook(info);
+        runner::panic_hook(info);
     }));
 
-    let matches = ClapApp::new(env!("CARGO_PKG_NAME"))
+    let clap_app = ClapApp::new(env!("CARGO_PKG_NAME"))
         .version(env!("CARGO_PKG_VERSION"))
         .author(env!("CARGO_PKG_AUTHORS"))
         .about(env!("CARGO_PKG_DESCRIPTION"))
@@ -144,10 +56,104 @@ fn main() -> Result<(), failure::Error> {
                                .long("tick-rate")
                                .help("Set the tick rate (milliseconds): the lower the number the higher the FPS. It can be nicer to have a lower value when you want to use the audio analysis view of the app. Beware that this comes at a CPU cost!")
                                .takes_value(true))
-        .get_matches();
+         .arg(Arg::with_name("profile")
+                               .short("P")
+                               .long("profile")
+                               .help("Use a named profile, with its own Spotify login, device and config.yml overrides stored in $HOME/.config/spotify-tui/profiles/<name>")
+                               .takes_value(true))
+         .arg(Arg::with_name("dump-theme")
+                               .long("dump-theme")
+                               .help("Print the resolved theme as YAML, ready to be saved as a theme file, and exit"))
+         .arg(Arg::with_name("record")
+                               .long("record")
+                               .help("Save every Web API request and response to <dir> (without your login token), to attach to a bug report")
+                               .value_name("dir")
+                               .takes_value(true))
+         .arg(Arg::with_name("replay")
+                               .long("replay")
+                               .help("Answer Web API requests from a --record directory instead of Spotify, without logging in")
+                               .value_name("dir")
+                               .conflicts_with("record")
+                               .takes_value(true))
+         .subcommand(SubCommand::with_name("auth")
+                               .about("Log in to Spotify and exit, replacing any saved login")
+                               .arg(Arg::with_name("no-browser")
+                                    .long("no-browser")
+                                    .help("Print the login URL and a QR code to open on another device instead of opening a browser, e.g. over SSH")))
+         .subcommand(SubCommand::with_name("lastfm-auth")
+                               .about("Log in to Last.fm for scrobbling and exit, saving the session to client.yml"))
+         .subcommand(SubCommand::with_name("export")
+                               .about("Export a playlist, your liked songs or your saved albums with every track's title, artists, album, duration, ISRC, URI and when it was added")
+                               .setting(AppSettings::SubcommandRequiredElseHelp)
+                               .subcommand(export_args(SubCommand::with_name("playlist")
+                                    .about("Export one of your playlists")
+                                    .arg(Arg::with_name("playlist")
+                                         .help("Name, ID, URI or link of the playlist")
+                                         .required(true))))
+                               .subcommand(export_args(SubCommand::with_name("saved-tracks")
+                                    .about("Export your liked songs")))
+                               .subcommand(export_args(SubCommand::with_name("saved-albums")
+                                    .about("Export every track of your saved albums"))))
+         .subcommand(SubCommand::with_name("import")
+                               .about("Create a playlist from an M3U playlist, a CSV file or a list of `Artist - Title` lines, reviewing the tracks that could not be matched for sure")
+                               .arg(Arg::with_name("file")
+                                    .help("File to import")
+                                    .required(true))
+                               .arg(Arg::with_name("name")
+                                    .long("name")
+                                    .short("n")
+                                    .help("Name of the new playlist (default: the file name)")
+                                    .takes_value(true))
+                               .arg(Arg::with_name("format")
+                                    .long("format")
+                                    .short("f")
+                                    .help("File format (default: from the file extension, or tags)")
+                                    .possible_values(&["m3u", "csv", "tags"])
+                                    .takes_value(true)));
+
+    #[cfg(feature = "mock-api")]
+    let clap_app = clap_app.subcommand(
+        SubCommand::with_name("mock-api")
+            .about("Serve a mock Spotify Web API, to run spt against without the network")
+            .arg(
+                Arg::with_name("port")
+                    .long("port")
+                    .help("Port to listen on (default 8900)")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("fixtures")
+                    .long("fixtures")
+                    .help("Directory of JSON files to use instead of the built in fixtures with the same name")
+                    .takes_value(true),
+            ),
+    );
+
+    let matches = clap_app.get_matches();
+
+    #[cfg(feature = "mock-api")]
+    {
+        if let Some(mock_matches) = matches.subcommand_matches("mock-api") {
+            let port = mock_matches
+                .value_of("port")
+                .and_then(|port| port.parse().ok())
+                .unwrap_or(mock_api::DEFAULT_PORT);
+            let fixtures_dir = mock_matches
+                .value_of("fixtures")
+                .map(std::path::PathBuf::from);
+            return mock_api::run(port, fixtures_dir);
+        }
+    }
+
+    let mut client_config =
+        ClientConfig::with_profile(matches.value_of("profile").map(String::from))?;
+
+    let mut user_config = load_user_config(&client_config)?;
 
-    let mut user_config = UserConfig::new();
-    user_config.load_config()?;
+    if matches.is_present("dump-theme") {
+        print!("{}", user_config.dump_theme()?);
+        return Ok(());
+    }
 
     if let Some(tick_rate) = matches
         .value_of("tick-rate")
@@ -160,206 +166,117 @@ fn main() -> Result<(), failure::Error> {
         }
     }
 
-    let mut client_config = ClientConfig::new();
-    client_config.load_config()?;
-
-    let config_paths = client_config.get_or_build_paths()?;
-
-    // Start authorization with spotify
-    let mut oauth = SpotifyOAuth::default()
-        .client_id(&client_config.client_id)
-        .client_secret(&client_config.client_secret)
-        .redirect_uri(&client_config.get_redirect_uri())
-        .cache_path(config_paths.token_cache_path)
-        .scope(&SCOPES.join(" "))
-        .build();
-
-    match get_token_auto(&mut oauth, client_config.get_port()) {
-        Some(token_info) => {
-            // Terminal initialization
-            let mut stdout = stdout();
-            execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-            enable_raw_mode()?;
-
-            let backend = CrosstermBackend::new(stdout);
-            let mut terminal = Terminal::new(backend)?;
-            terminal.hide_cursor()?;
-
-            let events = event::Events::new(user_config.behavior.tick_rate_milliseconds);
-
-            // Initialise app state
-            let mut app = App::new();
-
-            let (mut spotify, mut token_expiry) = get_spotify(token_info);
-
-            app.client_config = client_config;
-            app.user_config = user_config;
-
-            app.spotify = Some(spotify);
-
-            app.clipboard_context = clipboard::ClipboardProvider::new().ok();
-
-            app.help_docs_size = ui::help::get_help_docs().len() as u32;
-
-            // Now that spotify is ready, check if the user has already selected a device_id to
-            // play music on, if not send them to the device selection view
-            if app.client_config.device_id.is_none() {
-                app.handle_get_devices();
-            }
-
-            let mut is_first_render = true;
-
-            loop {
-                // Get the size of the screen on each loop to account for resize event
-                if let Ok(size) = terminal.backend().size() {
-                    // Reset the help menu is the terminal was resized
-                    if app.size != size {
-                        app.help_menu_max_lines = 0;
-                        app.help_menu_offset = 0;
-                        app.help_menu_page = 0;
-                    }
-
-                    app.size = size;
-
-                    // Based on the size of the terminal, adjust the search limit.
-                    let potential_limit = max((app.size.height as i32) - 13, 0) as u32;
-                    let max_limit = min(potential_limit, 50);
-                    app.large_search_limit = min((f32::from(size.height) / 1.4) as u32, max_limit);
-                    app.small_search_limit =
-                        min((f32::from(size.height) / 2.85) as u32, max_limit / 2);
-
-                    // Based on the size of the terminal, adjust how many lines are
-                    // dislayed in the help menu
-                    if app.size.height > 8 {
-                        app.help_menu_max_lines = (app.size.height as u32) - 8;
-                    } else {
-                        app.help_menu_max_lines = 0;
-                    }
-                };
-
-                let current_route = app.get_current_route();
-                terminal.draw(|mut f| match current_route.active_block {
-                    ActiveBlock::HelpMenu => {
-                        ui::draw_help_menu(&mut f, &app);
-                    }
-                    ActiveBlock::Error => {
-                        ui::draw_error_screen(&mut f, &app);
-                    }
-                    ActiveBlock::SelectDevice => {
-                        ui::draw_device_list(&mut f, &app);
-                    }
-                    ActiveBlock::Analysis => {
-                        ui::audio_analysis::draw(&mut f, &app);
-                    }
-                    _ => {
-                        ui::draw_main_layout(&mut f, &app);
-                    }
-                })?;
-
-                if current_route.active_block == ActiveBlock::Input {
-                    match terminal.show_cursor() {
-                        Ok(_r) => {}
-                        Err(_e) => {}
-                    };
-                } else {
-                    match terminal.hide_cursor() {
-                        Ok(_r) => {}
-                        Err(_e) => {}
-                    };
-                }
-
-                let cursor_offset = if app.size.height > ui::util::SMALL_TERMINAL_HEIGHT {
-                    2
-                } else {
-                    1
-                };
-
-                // Put the cursor back inside the input box
-                terminal.backend_mut().execute(MoveTo(
-                    cursor_offset + app.input_cursor_position,
-                    cursor_offset,
-                ))?;
-
-                if Instant::now() > token_expiry {
-                    // refresh token
-                    if let Some(new_token_info) = get_token(&mut oauth) {
-                        let (new_spotify, new_token_expiry) = get_spotify(new_token_info);
-                        spotify = new_spotify;
-                        token_expiry = new_token_expiry;
-                        app.spotify = Some(spotify);
-                    } else {
-                        println!("\nFailed to refresh authentication token");
-                        close_application()?;
-                        break;
-                    }
-                }
-
-                match events.next()? {
-                    event::Event::Input(key) => {
-                        if key == Key::Ctrl('c') {
-                            close_application()?;
-                            break;
-                        }
-
-                        let current_active_block = app.get_current_route().active_block;
-
-                        // To avoid swallowing the global key presses `q` and `-` make a special
-                        // case for the input handler
-                        if current_active_block == ActiveBlock::Input {
-                            handlers::input_handler(key, &mut app);
-                        } else if key == app.user_config.keys.back {
-                            if app.get_current_route().active_block != ActiveBlock::Input {
-                                // Go back through navigation stack when not in search input mode and exit the app if there are no more places to back to
-
-                                let pop_result = match app.pop_navigation_stack() {
-                                    Some(ref x) if x.id == RouteId::Search => {
-                                        app.pop_navigation_stack()
-                                    }
-                                    Some(x) => Some(x),
-                                    None => None,
-                                };
-                                if pop_result.is_none() {
-                                    close_application()?;
-                                    break; // Exit application
-                                }
-                            }
-                        } else {
-                            handlers::handle_app(key, &mut app);
-                        }
-                    }
-                    event::Event::Tick => {
-                        app.update_on_tick();
-                    }
-                }
-
-                // Delay spotify request until first render, will have the effect of improving
-                // startup speed
-                if is_first_render {
-                    if let Some(spotify) = &app.spotify {
-                        let playlists =
-                            spotify.current_user_playlists(app.large_search_limit, None);
+    let replayer = match matches.value_of("replay") {
+        Some(replay_dir) => Some(Rc::new(Replayer::load(Path::new(replay_dir))?)),
+        None => None,
+    };
+
+    // A replay answers every request from the recording, so there is nothing to log in to
+    let (spotify, traffic, login) = if let Some(replayer) = replayer {
+        client_config.device_id = replayer.device_id.clone();
+        let traffic = Traffic::Replay(replayer);
+        let spotify = WebApiClient::new(DEFAULT_API_URL, "").with_traffic(traffic.clone());
+        (spotify, traffic, None)
+    } else {
+        client_config.load_config()?;
+
+        if matches.subcommand_matches("lastfm-auth").is_some() {
+            scrobbler::log_in_lastfm(client_config.scrobbler.as_ref())
+                .and_then(|session_key| client_config.set_scrobbler_session_key(session_key))
+                .map_err(|e| format_err!("Last.fm auth failed: {}", e))?;
+            println!(
+                "\nLogged in to Last.fm, profile `{}` scrobbles from now on",
+                client_config.get_profile_name()
+            );
+            return Ok(());
+        }
 
-                        match playlists {
-                            Ok(p) => {
-                                app.playlists = Some(p);
-                                // Select the first playlist
-                                app.selected_playlist_index = Some(0);
-                            }
-                            Err(e) => {
-                                app.handle_error(e);
-                            }
-                        };
+        // Start authorization with spotify
+        let oauth = build_oauth(&client_config)?;
+
+        if let Some(auth_matches) = matches.subcommand_matches("auth") {
+            let token_info = if auth_matches.is_present("no-browser") {
+                log_in_headless(&oauth)
+            } else {
+                log_in(&oauth, client_config.get_port())
+            };
+            // Fail with a non-zero exit code, so scripts can tell
+            token_info.map_err(|e| format_err!("Spotify auth failed: {}", e))?;
+            println!(
+                "\nLogged in to Spotify as profile `{}`",
+                client_config.get_profile_name()
+            );
+            return Ok(());
+        }
 
-                        app.get_user();
-                    }
+        let token_info = get_token_auto(&oauth, client_config.get_port())
+            .map_err(|e| format_err!("Spotify auth failed: {}", e))?;
+        let traffic = match matches.value_of("record") {
+            Some(record_dir) => Traffic::Record(Rc::new(Recorder::create(
+                Path::new(record_dir),
+                client_config.device_id.clone(),
+            )?)),
+            None => Traffic::Live,
+        };
+        let (spotify, token_expiry) =
+            get_spotify(token_info, client_config.get_api_url(), &traffic);
+        (
+            spotify,
+            traffic,
+            Some(Login {
+                oauth,
+                token_expiry,
+            }),
+        )
+    };
+
+    if let Some(export_matches) = matches.subcommand_matches("export") {
+        let (source, matches) = match export_matches.subcommand() {
+            ("playlist", Some(matches)) => (
+                ExportSource::Playlist(
+                    matches.value_of("playlist").unwrap_or_default().to_string(),
+                ),
+                matches,
+            ),
+            ("saved-tracks", Some(matches)) => (ExportSource::SavedTracks, matches),
+            ("saved-albums", Some(matches)) => (ExportSource::SavedAlbums, matches),
+            _ => return Ok(()),
+        };
+        let format = ExportFormat::parse(matches.value_of("format").unwrap_or("csv"))?;
+        return export::run(
+            &spotify,
+            &source,
+            format,
+            matches.value_of("output").map(Path::new),
+        );
+    }
 
-                    app.get_current_playback();
-                    is_first_render = false;
-                }
-            }
+    if let Some(import_matches) = matches.subcommand_matches("import") {
+        let format = match import_matches.value_of("format") {
+            Some(format) => Some(ImportFormat::parse(format)?),
+            None => None,
+        };
+        let (mut import, user_id) = import::match_file(
+            &spotify,
+            Path::new(import_matches.value_of("file").unwrap_or_default()),
+            format,
+            import_matches.value_of("name"),
+        )?;
+        if import.review_index.is_some() {
+            return runner::start_import_ui(
+                client_config,
+                user_config,
+                spotify,
+                traffic,
+                login,
+                import,
+            );
+        }
+        for line in import.create_playlist(&spotify, &user_id)?.lines() {
+            eprintln!("{}", line);
         }
-        None => println!("\nSpotify auth failed"),
+        return Ok(());
     }
 
-    Ok(())
+    runner::start_ui(client_config, user_config, spotify, traffic, login)
 }
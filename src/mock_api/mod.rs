@@ -0,0 +1,825 @@
+//! A stand-in for the Spotify Web API and accounts service, to run the app and test it end to
+//! end without the network. It answers the endpoints spt calls from the JSON fixtures in
+//! `fixtures` (each can be replaced by a file of the same name in another directory) and
+//! simulates a player, so playback commands change what `me/player` reports. It also stands in
+//! for the Last.fm and ListenBrainz scrobbling APIs. See "Mock Web API" in the README for how
+//! to point spt at it.
+mod player;
+mod scrobble;
+
+use failure::format_err;
+use player::Player;
+use reqwest::Url;
+use scrobble::Scrobbles;
+use serde_json::{json, Value};
+use std::{
+    collections::HashSet,
+    fs,
+    io::{prelude::*, BufReader},
+    net::{TcpListener, TcpStream},
+    path::PathBuf,
+};
+
+#[cfg(feature = "mock-api")]
+pub const DEFAULT_PORT: u16 = 8900;
+// The only code `/api/token` accepts, handed out by `/authorize`
+const AUTHORIZATION_CODE: &str = "mock-authorization-code";
+const ACCESS_TOKEN: &str = "mock-access-token";
+const REFRESH_TOKEN: &str = "mock-refresh-token";
+const ADDED_AT: &str = "2020-01-01T00:00:00Z";
+// What the scrobbling stand-ins accept, see `scrobble`
+pub const MOCK_LASTFM_API_KEY: &str = "mock-api-key";
+pub const MOCK_LASTFM_API_SECRET: &str = "mock-api-secret";
+pub const MOCK_LASTFM_SESSION_KEY: &str = "mock-session-key";
+pub const MOCK_LISTENBRAINZ_TOKEN: &str = "mock-listenbrainz-token";
+
+struct Request {
+    method: String,
+    path: String,
+    query: Vec<(String, String)>,
+    authorization: Option<String>,
+    body: String,
+}
+
+impl Request {
+    fn read(stream: &TcpStream) -> Result<Request, failure::Error> {
+        let mut reader = BufReader::new(stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        let mut parts = request_line.split_whitespace();
+        let (method, target) = match (parts.next(), parts.next()) {
+            (Some(method), Some(target)) => (method.to_string(), target),
+            _ => return Err(format_err!("Malformed request")),
+        };
+
+        let mut authorization = None;
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header)?;
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                match name.to_lowercase().as_str() {
+                    "authorization" => authorization = Some(value.trim().to_string()),
+                    "content-length" => content_length = value.trim().parse()?,
+                    _ => {}
+                }
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+
+        let url = Url::parse(&format!("http://localhost{}", target))?;
+        Ok(Request {
+            method,
+            path: url.path().to_string(),
+            query: url.query_pairs().into_owned().collect(),
+            authorization,
+            body: String::from_utf8(body)?,
+        })
+    }
+
+    fn param(&self, key: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn device_id(&self) -> Option<&str> {
+        self.param("device_id")
+    }
+
+    fn ids(&self) -> Vec<&str> {
+        self.param("ids")
+            .map_or(vec![], |ids| ids.split(',').collect())
+    }
+
+    fn json_body(&self) -> Value {
+        serde_json::from_str(&self.body).unwrap_or(Value::Null)
+    }
+
+    /// The fields of a form encoded body, as sent to the token endpoint
+    fn form(&self) -> Vec<(String, String)> {
+        Url::parse(&format!("http://localhost/?{}", self.body))
+            .map(|url| url.query_pairs().into_owned().collect())
+            .unwrap_or_default()
+    }
+}
+
+struct Response {
+    status: u16,
+    location: Option<String>,
+    body: String,
+}
+
+impl Response {
+    fn json(body: Value) -> Response {
+        Response {
+            status: 200,
+            location: None,
+            body: body.to_string(),
+        }
+    }
+
+    fn no_content() -> Response {
+        Response {
+            status: 204,
+            location: None,
+            body: String::new(),
+        }
+    }
+
+    /// An error in the Web API's format
+    fn error(status: u16, message: &str) -> Response {
+        Response {
+            status,
+            location: None,
+            body: json!({ "error": { "status": status, "message": message } }).to_string(),
+        }
+    }
+
+    /// An error in the accounts service's format
+    fn oauth_error(error: &str, description: &str) -> Response {
+        Response {
+            status: 400,
+            location: None,
+            body: json!({ "error": error, "error_description": description }).to_string(),
+        }
+    }
+
+    fn write_to(&self, stream: &mut TcpStream) -> Result<(), failure::Error> {
+        let reason = match self.status {
+            200 => "OK",
+            204 => "No Content",
+            302 => "Found",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            403 => "Forbidden",
+            _ => "Not Found",
+        };
+        let mut head = format!("HTTP/1.1 {} {}\r\n", self.status, reason);
+        if let Some(location) = &self.location {
+            head.push_str(&format!("Location: {}\r\n", location));
+        }
+        head.push_str(&format!(
+            "Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.body.len()
+        ));
+        stream.write_all(head.as_bytes())?;
+        stream.write_all(self.body.as_bytes())?;
+        stream.flush()?;
+        Ok(())
+    }
+}
+
+/// Turn a player command's outcome into Spotify's response
+fn command(result: Result<(), String>) -> Response {
+    match result {
+        Ok(()) => Response::no_content(),
+        Err(message) => Response::error(404, &message),
+    }
+}
+
+/// Apply `limit` and `offset` from the query, like Spotify's paging
+fn page(request: &Request, items: Vec<Value>) -> Value {
+    let limit = request
+        .param("limit")
+        .and_then(|limit| limit.parse().ok())
+        .unwrap_or(20);
+    let offset = request
+        .param("offset")
+        .and_then(|offset| offset.parse().ok())
+        .unwrap_or(0);
+    let total = items.len();
+    json!({
+        "href": request.path,
+        "items": items.into_iter().skip(offset).take(limit).collect::<Vec<Value>>(),
+        "limit": limit,
+        "next": null,
+        "offset": offset,
+        "previous": null,
+        "total": total,
+    })
+}
+
+fn matches_query(item: &Value, query: &str) -> bool {
+    let names = [
+        &item["name"],
+        &item["artists"][0]["name"],
+        &item["album"]["name"],
+        &item["owner"]["display_name"],
+    ];
+    names.iter().any(|name| {
+        name.as_str()
+            .is_some_and(|name| name.to_lowercase().contains(query))
+    })
+}
+
+fn load_fixture(dir: &Option<PathBuf>, name: &str) -> Result<Value, failure::Error> {
+    if let Some(path) = dir.as_ref().map(|dir| dir.join(format!("{}.json", name))) {
+        if path.exists() {
+            return serde_json::from_str(&fs::read_to_string(&path)?)
+                .map_err(|e| format_err!("Invalid fixture {}: {}", path.display(), e));
+        }
+    }
+    let fixture = match name {
+        "artists" => include_str!("fixtures/artists.json"),
+        "audio_analysis" => include_str!("fixtures/audio_analysis.json"),
+        "devices" => include_str!("fixtures/devices.json"),
+        "me" => include_str!("fixtures/me.json"),
+        "playlists" => include_str!("fixtures/playlists.json"),
+        "tracks" => include_str!("fixtures/tracks.json"),
+        _ => return Err(format_err!("No fixture named {}", name)),
+    };
+    Ok(serde_json::from_str(fixture)?)
+}
+
+fn load_list(dir: &Option<PathBuf>, name: &str) -> Result<Vec<Value>, failure::Error> {
+    match load_fixture(dir, name)? {
+        Value::Array(items) => Ok(items),
+        _ => Err(format_err!("The {} fixture should be a list", name)),
+    }
+}
+
+pub struct MockApi {
+    user: Value,
+    tracks: Vec<Value>,
+    artists: Vec<Value>,
+    playlists: Vec<Value>,
+    audio_analysis: Value,
+    saved_track_ids: HashSet<String>,
+    player: Player,
+    scrobbles: Scrobbles,
+}
+
+impl MockApi {
+    /// Load the fixtures, from `fixtures_dir` where it has them
+    pub fn new(fixtures_dir: Option<PathBuf>) -> Result<MockApi, failure::Error> {
+        let devices = load_fixture(&fixtures_dir, "devices")?["devices"]
+            .as_array()
+            .cloned()
+            .ok_or_else(|| format_err!("The devices fixture should have a `devices` list"))?;
+        Ok(MockApi {
+            user: load_fixture(&fixtures_dir, "me")?,
+            tracks: load_list(&fixtures_dir, "tracks")?,
+            artists: load_list(&fixtures_dir, "artists")?,
+            playlists: load_list(&fixtures_dir, "playlists")?,
+            audio_analysis: load_fixture(&fixtures_dir, "audio_analysis")?,
+            saved_track_ids: HashSet::new(),
+            player: Player::new(devices),
+            scrobbles: Scrobbles::default(),
+        })
+    }
+
+    /// Answer requests one at a time, for as long as the process runs
+    pub fn serve(mut self, listener: TcpListener) {
+        for stream in listener.incoming() {
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    println!("Error: {}", e);
+                    continue;
+                }
+            };
+            let response = match Request::read(&stream) {
+                Ok(request) => self.handle(&request),
+                Err(e) => Response::error(400, &e.to_string()),
+            };
+            if let Err(e) = response.write_to(&mut stream) {
+                println!("Error: {}", e);
+            }
+        }
+    }
+
+    fn handle(&mut self, request: &Request) -> Response {
+        match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/authorize") => self.authorize(request),
+            ("POST", "/api/token") => self.token(request),
+            ("POST", "/2.0") | ("POST", "/2.0/") => self.scrobbles.lastfm(request),
+            ("POST", "/1/submit-listens") => self.scrobbles.submit_listens(request),
+            ("GET", "/mock/scrobbles") => self.scrobbles.list(),
+            (_, path) if path.starts_with("/v1/") => {
+                if request.authorization.as_deref() != Some(&format!("Bearer {}", ACCESS_TOKEN)) {
+                    return Response::error(401, "Invalid access token");
+                }
+                let path = path["/v1/".len()..].trim_end_matches('/').to_string();
+                let segments: Vec<&str> = path.split('/').collect();
+                self.api(request, &segments)
+            }
+            _ => Response::error(404, "Service not found"),
+        }
+    }
+
+    /// Log in straight away, sending the browser back to the app with a code
+    fn authorize(&self, request: &Request) -> Response {
+        let mut redirect_uri = match request.param("redirect_uri").map(Url::parse) {
+            Some(Ok(url)) => url,
+            _ => return Response::error(400, "Invalid redirect URI"),
+        };
+        redirect_uri
+            .query_pairs_mut()
+            .append_pair("code", AUTHORIZATION_CODE)
+            .append_pair("state", request.param("state").unwrap_or_default());
+        Response {
+            status: 302,
+            location: Some(redirect_uri.to_string()),
+            body: String::new(),
+        }
+    }
+
+    fn token(&self, request: &Request) -> Response {
+        let form = request.form();
+        let field = |key: &str| {
+            form.iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value.as_str())
+        };
+        match field("grant_type") {
+            Some("authorization_code") if field("code") == Some(AUTHORIZATION_CODE) => {
+                Response::json(json!({
+                    "access_token": ACCESS_TOKEN,
+                    "token_type": "Bearer",
+                    "expires_in": 3600,
+                    "refresh_token": REFRESH_TOKEN,
+                    "scope": "",
+                }))
+            }
+            Some("refresh_token") if field("refresh_token") == Some(REFRESH_TOKEN) => {
+                Response::json(json!({
+                    "access_token": ACCESS_TOKEN,
+                    "token_type": "Bearer",
+                    "expires_in": 3600,
+                    "scope": "",
+                }))
+            }
+            Some("authorization_code") | Some("refresh_token") => {
+                Response::oauth_error("invalid_grant", "Invalid authorization code")
+            }
+            _ => Response::oauth_error("unsupported_grant_type", "grant_type must be set"),
+        }
+    }
+
+    fn track(&self, id: &str) -> Option<&Value> {
+        self.tracks.iter().find(|track| track["id"] == json!(id))
+    }
+
+    fn albums(&self) -> Vec<Value> {
+        let mut albums: Vec<Value> = vec![];
+        for track in &self.tracks {
+            if !albums.contains(&track["album"]) {
+                albums.push(track["album"].clone());
+            }
+        }
+        albums
+    }
+
+    fn api(&mut self, request: &Request, segments: &[&str]) -> Response {
+        let device_id = request.device_id();
+        match (request.method.as_str(), segments) {
+            ("GET", ["me"]) => Response::json(self.user.clone()),
+            ("GET", ["me", "playlists"]) => Response::json(page(request, self.playlists.clone())),
+            ("GET", ["users", _, "playlists", _, "tracks"])
+            | ("GET", ["playlists", _, "tracks"]) => {
+                let items = self
+                    .tracks
+                    .iter()
+                    .map(|track| {
+                        json!({
+                            "added_at": ADDED_AT,
+                            "added_by": null,
+                            "is_local": false,
+                            "track": track,
+                        })
+                    })
+                    .collect();
+                Response::json(page(request, items))
+            }
+
+            ("GET", ["me", "tracks"]) => {
+                let items = self
+                    .tracks
+                    .iter()
+                    .filter(|track| {
+                        track["id"]
+                            .as_str()
+                            .is_some_and(|id| self.saved_track_ids.contains(id))
+                    })
+                    .map(|track| json!({ "added_at": ADDED_AT, "track": track }))
+                    .collect();
+                Response::json(page(request, items))
+            }
+            ("GET", ["me", "tracks", "contains"]) => {
+                let saved: Vec<bool> = request
+                    .ids()
+                    .iter()
+                    .map(|id| self.saved_track_ids.contains(*id))
+                    .collect();
+                Response::json(json!(saved))
+            }
+            ("PUT", ["me", "tracks"]) => {
+                for id in request.ids() {
+                    self.saved_track_ids.insert(id.to_string());
+                }
+                Response::no_content()
+            }
+            ("DELETE", ["me", "tracks"]) => {
+                for id in request.ids() {
+                    self.saved_track_ids.remove(id);
+                }
+                Response::no_content()
+            }
+            ("GET", ["me", "albums"]) => Response::json(page(request, vec![])),
+            ("GET", ["me", "following"]) => Response::json(json!({
+                "artists": {
+                    "href": request.path,
+                    "items": self.artists,
+                    "limit": 20,
+                    "next": null,
+                    "cursors": { "after": null },
+                    "total": self.artists.len(),
+                }
+            })),
+            // The fixtures in order, whatever the time range
+            ("GET", ["me", "top", "artists"]) => {
+                Response::json(page(request, self.artists.clone()))
+            }
+            ("GET", ["me", "top", "tracks"]) => Response::json(page(request, self.tracks.clone())),
+            ("GET", ["me", "player", "recently-played"]) => Response::json(json!({
+                "href": request.path,
+                "items": [],
+                "limit": 50,
+                "next": null,
+                "cursors": { "after": null },
+            })),
+
+            ("GET", ["tracks"]) => {
+                let tracks: Vec<Option<&Value>> =
+                    request.ids().iter().map(|id| self.track(id)).collect();
+                Response::json(json!({ "tracks": tracks }))
+            }
+            ("GET", ["tracks", id]) => match self.track(id) {
+                Some(track) => Response::json(track.clone()),
+                None => Response::error(404, "Non existing id"),
+            },
+            ("GET", ["audio-analysis", id]) => match self.track(id) {
+                Some(_) => Response::json(self.audio_analysis.clone()),
+                None => Response::error(404, "Analysis not found"),
+            },
+            ("GET", ["albums", id, "tracks"]) => {
+                let uri = json!(format!("spotify:album:{}", id));
+                let items = self
+                    .tracks
+                    .iter()
+                    .filter(|track| track["album"]["uri"] == uri)
+                    .cloned()
+                    .collect();
+                Response::json(page(request, items))
+            }
+            ("GET", ["artists", id]) => {
+                match self.artists.iter().find(|artist| artist["id"] == json!(id)) {
+                    Some(artist) => Response::json(artist.clone()),
+                    None => Response::error(404, "Non existing id"),
+                }
+            }
+            ("GET", ["artists", id, "top-tracks"]) => {
+                let tracks: Vec<&Value> = self
+                    .tracks
+                    .iter()
+                    .filter(|track| track["artists"][0]["id"] == json!(id))
+                    .collect();
+                Response::json(json!({ "tracks": tracks }))
+            }
+            ("GET", ["artists", id, "albums"]) => {
+                let albums = self
+                    .albums()
+                    .into_iter()
+                    .filter(|album| album["artists"][0]["id"] == json!(id))
+                    .collect();
+                Response::json(page(request, albums))
+            }
+            ("GET", ["artists", id, "related-artists"]) => {
+                let artists: Vec<&Value> = self
+                    .artists
+                    .iter()
+                    .filter(|artist| artist["id"] != json!(id))
+                    .collect();
+                Response::json(json!({ "artists": artists }))
+            }
+            ("GET", ["search"]) => self.search(request),
+            ("GET", ["recommendations"]) => {
+                Response::json(json!({ "seeds": [], "tracks": self.tracks }))
+            }
+
+            ("GET", ["me", "player"]) => match self.player.state() {
+                Some(state) => Response::json(state),
+                None => Response::no_content(),
+            },
+            ("GET", ["me", "player", "devices"]) => Response::json(self.player.devices()),
+            ("PUT", ["me", "player"]) => {
+                let body = request.json_body();
+                match body["device_ids"][0].as_str() {
+                    Some(device_id) => command(
+                        self.player
+                            .transfer(device_id, body["play"].as_bool().unwrap_or(false)),
+                    ),
+                    None => Response::error(400, "Missing device_ids"),
+                }
+            }
+            ("PUT", ["me", "player", "play"]) => command(self.player.play(
+                device_id,
+                &request.json_body(),
+                &self.tracks,
+            )),
+            ("PUT", ["me", "player", "pause"]) => command(self.player.pause(device_id)),
+            ("POST", ["me", "player", "next"]) => command(self.player.next(device_id)),
+            ("POST", ["me", "player", "previous"]) => command(self.player.previous(device_id)),
+            ("PUT", ["me", "player", "seek"]) => {
+                match request.param("position_ms").and_then(|ms| ms.parse().ok()) {
+                    Some(position_ms) => command(self.player.seek(device_id, position_ms)),
+                    None => Response::error(400, "Missing position_ms"),
+                }
+            }
+            ("PUT", ["me", "player", "shuffle"]) => {
+                let shuffle = request.param("state") == Some("true");
+                command(self.player.set_shuffle(device_id, shuffle))
+            }
+            ("PUT", ["me", "player", "repeat"]) => command(
+                self.player
+                    .set_repeat(device_id, request.param("state").unwrap_or_default()),
+            ),
+            ("PUT", ["me", "player", "volume"]) => {
+                match request.param("volume_percent").and_then(|v| v.parse().ok()) {
+                    Some(volume) => command(self.player.set_volume(device_id, volume)),
+                    None => Response::error(400, "Missing volume_percent"),
+                }
+            }
+            ("POST", ["me", "player", "queue"]) => {
+                let uri = json!(request.param("uri").unwrap_or_default());
+                match self.tracks.iter().find(|track| track["uri"] == uri) {
+                    Some(track) => command(self.player.add_to_queue(device_id, track.clone())),
+                    None => Response::error(404, "Track not found"),
+                }
+            }
+
+            _ => Response::error(404, "Service not found"),
+        }
+    }
+
+    fn search(&self, request: &Request) -> Response {
+        let query = request.param("q").unwrap_or_default().to_lowercase();
+        let find = |items: Vec<Value>| -> Vec<Value> {
+            items
+                .into_iter()
+                .filter(|item| matches_query(item, &query))
+                .collect()
+        };
+
+        let mut results = json!({});
+        for search_type in request.param("type").unwrap_or_default().split(',') {
+            let items = match search_type {
+                "track" => find(self.tracks.clone()),
+                "artist" => find(self.artists.clone()),
+                "album" => find(self.albums()),
+                "playlist" => find(self.playlists.clone()),
+                _ => return Response::error(400, "Unsupported search type"),
+            };
+            results[format!("{}s", search_type)] = page(request, items);
+        }
+        Response::json(results)
+    }
+}
+
+/// Serve the mock on `port` until the process is stopped
+#[cfg(feature = "mock-api")]
+pub fn run(port: u16, fixtures_dir: Option<PathBuf>) -> Result<(), failure::Error> {
+    let mock_api = MockApi::new(fixtures_dir)?;
+    let listener = TcpListener::bind(format!("127.0.0.1:{}", port))?;
+    println!(
+        "Mock Spotify API listening on http://127.0.0.1:{}, point spt at it with these lines in client.yml:\n",
+        port
+    );
+    println!("api_url: http://127.0.0.1:{}/v1", port);
+    println!("accounts_url: http://127.0.0.1:{}", port);
+    println!(
+        "\nIt stands in for Last.fm at http://127.0.0.1:{}/2.0/ and ListenBrainz at http://127.0.0.1:{} \
+         too, see \"Scrobbling\" in the README",
+        port, port
+    );
+    mock_api.serve(listener);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        credentials::TokenCache,
+        oauth::OAuth,
+        redirect_uri::listen_for_redirect,
+        spotify_api::{SpotifyApi, WebApiClient},
+    };
+    use reqwest::Method;
+    use rspotify::spotify::{model::offset::for_position, senum::RepeatState};
+    use std::{env, thread};
+
+    // Start a mock on a free port, returning its address
+    fn start_mock_api() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let mock_api = MockApi::new(None).unwrap();
+        thread::spawn(move || mock_api.serve(listener));
+        address
+    }
+
+    fn free_port() -> u16 {
+        TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port()
+    }
+
+    fn client(address: &str) -> WebApiClient {
+        WebApiClient::new(&format!("{}/v1", address), ACCESS_TOKEN)
+    }
+
+    #[test]
+    fn test_fixtures_deserialize() {
+        let spotify = client(&start_mock_api());
+        assert_eq!(spotify.current_user().unwrap().id, "mockuser");
+        assert_eq!(spotify.device().unwrap().devices.len(), 2);
+        assert_eq!(
+            spotify
+                .current_user_playlists(None, None)
+                .unwrap()
+                .items
+                .len(),
+            2
+        );
+        let playlist_tracks = spotify
+            .user_playlist_tracks("mockuser", "mockplaylist1", None, None, None, None)
+            .unwrap();
+        assert_eq!(playlist_tracks.total, 5);
+        assert!(spotify.audio_analysis("mocktrack1").is_ok());
+        assert_eq!(
+            spotify
+                .artist_top_tracks("mockartist2", None)
+                .unwrap()
+                .tracks
+                .len(),
+            2
+        );
+        assert!(spotify
+            .recommendations(
+                None,
+                None,
+                Some(vec!["mocktrack1".to_string()]),
+                None,
+                None,
+                &Default::default()
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn test_export() {
+        let spotify = client(&start_mock_api());
+        let (id, name) = crate::export::find_playlist(&spotify, "mockplaylist1").unwrap();
+        let export = crate::export::playlist(&spotify, &id, &name).unwrap();
+        assert_eq!(export.tracks.len(), 5);
+        assert_eq!(export.tracks[0].added_at.as_deref(), Some(ADDED_AT));
+        assert!(crate::export::saved_albums(&spotify).is_ok());
+    }
+
+    #[test]
+    fn test_search() {
+        let spotify = client(&start_mock_api());
+        let tracks = spotify.search_track("FLAKY", Some(10), None, None).unwrap();
+        assert_eq!(tracks.tracks.items.len(), 1);
+        assert_eq!(tracks.tracks.items[0].name, "Flaky");
+
+        // Artist names match their tracks too
+        let tracks = spotify.search_track("stub", None, None, None).unwrap();
+        assert_eq!(tracks.tracks.total, 2);
+        let artists = spotify.search_artist("stub", None, None, None).unwrap();
+        assert_eq!(artists.artists.items[0].id, "mockartist2");
+        let albums = spotify.search_album("green", None, None, None).unwrap();
+        assert_eq!(albums.albums.items.len(), 1);
+    }
+
+    #[test]
+    fn test_player_simulation() {
+        let spotify = client(&start_mock_api());
+        assert!(spotify.current_playback(None).unwrap().is_none());
+
+        spotify
+            .start_playback(
+                None,
+                Some("spotify:album:mockalbum1".to_string()),
+                None,
+                for_position(1),
+                None,
+            )
+            .unwrap();
+        let playback = spotify.current_playback(None).unwrap().unwrap();
+        assert!(playback.is_playing);
+        assert_eq!(playback.item.unwrap().name, "Works On My Machine");
+        assert_eq!(playback.context.unwrap().uri, "spotify:album:mockalbum1");
+
+        spotify.pause_playback(None).unwrap();
+        spotify.next_track(None).unwrap();
+        spotify.shuffle(true, None).unwrap();
+        spotify.repeat(RepeatState::Context, None).unwrap();
+        spotify.volume(20, None).unwrap();
+        let playback = spotify.current_playback(None).unwrap().unwrap();
+        assert!(!playback.is_playing);
+        assert_eq!(playback.item.unwrap().name, "Flaky");
+        assert!(playback.shuffle_state);
+        assert_eq!(playback.repeat_state, RepeatState::Context);
+        assert_eq!(playback.device.volume_percent, 20);
+
+        // Seeking to the end moves on, and repeating the context goes back to the start
+        spotify
+            .start_playback(None, None, None, None, None)
+            .unwrap();
+        spotify.seek_track(224_000, None).unwrap();
+        let playback = spotify.current_playback(None).unwrap().unwrap();
+        assert_eq!(playback.item.unwrap().name, "Red, Green, Refactor");
+
+        spotify
+            .web_api_call(
+                Method::PUT,
+                "me/player",
+                Some(&json!({ "device_ids": ["mockdevice2"] })),
+            )
+            .unwrap();
+        let devices = spotify.device().unwrap().devices;
+        assert!(!devices[0].is_active);
+        assert!(devices[1].is_active);
+
+        assert!(spotify
+            .start_playback(Some("unknown".to_string()), None, None, None, None)
+            .is_err());
+    }
+
+    #[test]
+    fn test_saved_tracks() {
+        let spotify = client(&start_mock_api());
+        let ids = vec!["mocktrack2".to_string(), "mocktrack3".to_string()];
+        spotify.current_user_saved_tracks_add(&ids[..1]).unwrap();
+        assert_eq!(
+            spotify.current_user_saved_tracks_contains(&ids).unwrap(),
+            vec![true, false]
+        );
+        let saved = spotify.current_user_saved_tracks(None, None).unwrap();
+        assert_eq!(saved.items[0].track.name, "Works On My Machine");
+
+        spotify.current_user_saved_tracks_delete(&ids).unwrap();
+        assert_eq!(
+            spotify.current_user_saved_tracks(None, None).unwrap().total,
+            0
+        );
+    }
+
+    #[test]
+    fn test_rejects_unknown_token() {
+        let spotify = WebApiClient::new(&format!("{}/v1", start_mock_api()), "expired");
+        let error = spotify.current_user().unwrap_err();
+        assert_eq!(error.to_string(), "Unauthorized request to API");
+    }
+
+    #[test]
+    fn test_log_in_through_redirect() {
+        let address = start_mock_api();
+        let port = free_port();
+        let token_cache_path = env::temp_dir().join(format!("spt-mock-api-token-{}.json", port));
+        let mut oauth = OAuth::new(
+            "mock-client",
+            None,
+            &format!("http://localhost:{}/callback", port),
+            "user-read-private",
+            TokenCache::File(token_cache_path.clone()),
+        );
+        oauth.accounts_url = address.clone();
+
+        // Stands in for the browser, which follows the mock's redirect back to spt
+        let auth_url = oauth.get_authorize_url();
+        let open_browser = move || {
+            thread::spawn(move || reqwest::blocking::get(&auth_url));
+        };
+        let redirect = listen_for_redirect(port, open_browser).unwrap();
+        let token_info = oauth.process_redirect(&redirect).unwrap();
+        assert_eq!(token_info.access_token, ACCESS_TOKEN);
+        assert_eq!(token_info.refresh_token.as_deref(), Some(REFRESH_TOKEN));
+
+        let spotify = WebApiClient::new(&format!("{}/v1", address), &token_info.access_token);
+        assert_eq!(spotify.current_user().unwrap().id, "mockuser");
+
+        assert!(oauth.process_redirect("not-the-code").is_err());
+        let _ = fs::remove_file(token_cache_path);
+    }
+}
@@ -0,0 +1,287 @@
+//! The mock's playback state. Time moves on as in a real player, so a track that is left playing
+//! ends and the next one in the queue starts.
+use serde_json::{json, Value};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+const NO_ACTIVE_DEVICE: &str = "Player command failed: No active device found";
+
+pub struct Player {
+    devices: Vec<Value>,
+    active_device: Option<usize>,
+    queue: Vec<Value>,
+    position: usize,
+    context_uri: Option<String>,
+    is_playing: bool,
+    // Progress at `resumed_at`
+    progress_ms: u64,
+    resumed_at: Instant,
+    shuffle: bool,
+    repeat: String,
+}
+
+impl Player {
+    pub fn new(devices: Vec<Value>) -> Player {
+        let active_device = devices
+            .iter()
+            .position(|device| device["is_active"] == json!(true));
+        Player {
+            devices,
+            active_device,
+            queue: vec![],
+            position: 0,
+            context_uri: None,
+            is_playing: false,
+            progress_ms: 0,
+            resumed_at: Instant::now(),
+            shuffle: false,
+            repeat: "off".to_string(),
+        }
+    }
+
+    fn current_progress_ms(&self) -> u64 {
+        if self.is_playing {
+            self.progress_ms + self.resumed_at.elapsed().as_millis() as u64
+        } else {
+            self.progress_ms
+        }
+    }
+
+    fn set_progress(&mut self, progress_ms: u64) {
+        self.progress_ms = progress_ms;
+        self.resumed_at = Instant::now();
+    }
+
+    fn duration_ms(&self) -> Option<u64> {
+        self.queue
+            .get(self.position)
+            .and_then(|track| track["duration_ms"].as_u64())
+    }
+
+    /// Catch up with the time that has passed, moving through the queue like Spotify would
+    fn update(&mut self) {
+        while let Some(duration_ms) = self.duration_ms() {
+            let progress_ms = self.current_progress_ms();
+            if !self.is_playing || duration_ms == 0 || progress_ms < duration_ms {
+                break;
+            }
+            let overflow = progress_ms - duration_ms;
+            if self.repeat == "track" {
+                self.set_progress(overflow);
+            } else if self.position + 1 < self.queue.len() {
+                self.position += 1;
+                self.set_progress(overflow);
+            } else if self.repeat == "context" {
+                self.position = 0;
+                self.set_progress(overflow);
+            } else {
+                self.is_playing = false;
+                self.set_progress(duration_ms);
+            }
+        }
+    }
+
+    /// Use `device_id` from now on, or check that a device is already active
+    fn activate(&mut self, device_id: Option<&str>) -> Result<(), String> {
+        match device_id {
+            Some(device_id) => {
+                let index = self
+                    .devices
+                    .iter()
+                    .position(|device| device["id"] == json!(device_id))
+                    .ok_or_else(|| format!("Device not found: {}", device_id))?;
+                self.active_device = Some(index);
+                Ok(())
+            }
+            None if self.active_device.is_some() => Ok(()),
+            None => Err(NO_ACTIVE_DEVICE.to_string()),
+        }
+    }
+
+    /// `GET me/player`, `None` when nothing is playing on an active device
+    pub fn state(&mut self) -> Option<Value> {
+        self.update();
+        let device = self.devices()["devices"][self.active_device?].clone();
+        let item = self.queue.get(self.position)?;
+        let context = self.context_uri.as_ref().map(|uri| {
+            json!({
+                "uri": uri,
+                "href": "",
+                "external_urls": {},
+                "type": uri.split(':').nth(1).unwrap_or("playlist"),
+            })
+        });
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_millis() as u64);
+
+        Some(json!({
+            "device": device,
+            "repeat_state": self.repeat,
+            "shuffle_state": self.shuffle,
+            "context": context,
+            "timestamp": timestamp,
+            "progress_ms": self.current_progress_ms(),
+            "is_playing": self.is_playing,
+            "item": item,
+        }))
+    }
+
+    pub fn devices(&self) -> Value {
+        let devices: Vec<Value> = self
+            .devices
+            .iter()
+            .enumerate()
+            .map(|(index, device)| {
+                let mut device = device.clone();
+                device["is_active"] = json!(Some(index) == self.active_device);
+                device
+            })
+            .collect();
+        json!({ "devices": devices })
+    }
+
+    /// `PUT me/player/play`. `tracks` are what the `uris` or context in `body` can refer to
+    pub fn play(
+        &mut self,
+        device_id: Option<&str>,
+        body: &Value,
+        tracks: &[Value],
+    ) -> Result<(), String> {
+        self.activate(device_id)?;
+        self.update();
+
+        let find = |uri: &Value| tracks.iter().find(|track| track["uri"] == *uri).cloned();
+        if let Some(uris) = body["uris"].as_array() {
+            self.queue = uris.iter().filter_map(find).collect();
+            self.context_uri = None;
+        } else if let Some(context_uri) = body["context_uri"].as_str() {
+            // Albums and artists hold their own tracks, every mock playlist holds them all
+            self.queue = tracks
+                .iter()
+                .filter(|track| match context_uri.split(':').nth(1) {
+                    Some("album") => track["album"]["uri"] == json!(context_uri),
+                    Some("artist") => track["artists"].as_array().is_some_and(|artists| {
+                        artists
+                            .iter()
+                            .any(|artist| artist["uri"] == json!(context_uri))
+                    }),
+                    _ => true,
+                })
+                .cloned()
+                .collect();
+            self.context_uri = Some(context_uri.to_string());
+        } else if self.queue.is_empty() {
+            return Err("Player command failed: Nothing to resume".to_string());
+        } else {
+            // Resume where it was paused
+            let progress_ms = self.current_progress_ms();
+            self.is_playing = true;
+            self.set_progress(progress_ms);
+            return Ok(());
+        }
+
+        if self.queue.is_empty() {
+            return Err("Player command failed: No tracks found to play".to_string());
+        }
+        let offset = &body["offset"];
+        self.position = match (offset["position"].as_u64(), offset["uri"].as_str()) {
+            (Some(position), _) => position as usize,
+            (None, Some(uri)) => self
+                .queue
+                .iter()
+                .position(|track| track["uri"] == json!(uri))
+                .unwrap_or(0),
+            _ => 0,
+        }
+        .min(self.queue.len() - 1);
+        self.is_playing = true;
+        self.set_progress(body["position_ms"].as_u64().unwrap_or(0));
+        Ok(())
+    }
+
+    pub fn pause(&mut self, device_id: Option<&str>) -> Result<(), String> {
+        self.activate(device_id)?;
+        self.update();
+        self.progress_ms = self.current_progress_ms();
+        self.is_playing = false;
+        Ok(())
+    }
+
+    pub fn next(&mut self, device_id: Option<&str>) -> Result<(), String> {
+        self.activate(device_id)?;
+        self.update();
+        if self.position + 1 < self.queue.len() {
+            self.position += 1;
+        } else if self.repeat == "context" {
+            self.position = 0;
+        } else {
+            self.is_playing = false;
+        }
+        self.set_progress(0);
+        Ok(())
+    }
+
+    pub fn previous(&mut self, device_id: Option<&str>) -> Result<(), String> {
+        self.activate(device_id)?;
+        self.update();
+        // Like Spotify, going back part way into a track restarts it
+        if self.current_progress_ms() < 3000 {
+            self.position = self.position.saturating_sub(1);
+        }
+        self.set_progress(0);
+        Ok(())
+    }
+
+    pub fn seek(&mut self, device_id: Option<&str>, position_ms: u64) -> Result<(), String> {
+        self.activate(device_id)?;
+        self.set_progress(position_ms);
+        self.update();
+        Ok(())
+    }
+
+    pub fn set_shuffle(&mut self, device_id: Option<&str>, shuffle: bool) -> Result<(), String> {
+        self.activate(device_id)?;
+        self.shuffle = shuffle;
+        Ok(())
+    }
+
+    pub fn set_repeat(&mut self, device_id: Option<&str>, repeat: &str) -> Result<(), String> {
+        self.activate(device_id)?;
+        match repeat {
+            "off" | "track" | "context" => {
+                self.repeat = repeat.to_string();
+                Ok(())
+            }
+            _ => Err(format!("Invalid repeat state: {}", repeat)),
+        }
+    }
+
+    pub fn set_volume(&mut self, device_id: Option<&str>, volume: u64) -> Result<(), String> {
+        self.activate(device_id)?;
+        if let Some(index) = self.active_device {
+            self.devices[index]["volume_percent"] = json!(volume.min(100));
+        }
+        Ok(())
+    }
+
+    /// `PUT me/player`, moving playback to another device
+    pub fn transfer(&mut self, device_id: &str, play: bool) -> Result<(), String> {
+        self.update();
+        self.progress_ms = self.current_progress_ms();
+        self.activate(Some(device_id))?;
+        if play {
+            self.is_playing = !self.queue.is_empty();
+        }
+        self.resumed_at = Instant::now();
+        Ok(())
+    }
+
+    /// `POST me/player/queue`, playing `track` after the current one
+    pub fn add_to_queue(&mut self, device_id: Option<&str>, track: Value) -> Result<(), String> {
+        self.activate(device_id)?;
+        self.update();
+        let index = (self.position + 1).min(self.queue.len());
+        self.queue.insert(index, track);
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+//! Stand-ins for the Last.fm and ListenBrainz scrobbling APIs. They check requests the way the
+//! services do and keep what they were sent, which `GET /mock/scrobbles` lists.
+use super::{
+    Request, Response, MOCK_LASTFM_API_KEY, MOCK_LASTFM_API_SECRET, MOCK_LASTFM_SESSION_KEY,
+    MOCK_LISTENBRAINZ_TOKEN,
+};
+use crate::scrobbler::lastfm_signature;
+use serde_json::{json, Value};
+
+// Handed out by `auth.getToken` and exchanged for the session by `auth.getSession`
+const LASTFM_TOKEN: &str = "mock-lastfm-token";
+
+#[derive(Default)]
+pub struct Scrobbles {
+    now_playing: Option<Value>,
+    scrobbles: Vec<Value>,
+}
+
+/// A Last.fm error, which comes with a code in the body
+fn lastfm_error(status: u16, code: u16, message: &str) -> Response {
+    Response {
+        status,
+        location: None,
+        body: json!({ "error": code, "message": message }).to_string(),
+    }
+}
+
+// Seconds since the Unix epoch, as a number
+fn timestamp(value: Option<&str>) -> Value {
+    value
+        .and_then(|value| value.parse::<i64>().ok())
+        .map_or(Value::Null, Value::from)
+}
+
+impl Scrobbles {
+    pub fn list(&self) -> Response {
+        Response::json(json!({
+            "now_playing": self.now_playing,
+            "scrobbles": self.scrobbles,
+        }))
+    }
+
+    /// Last.fm's single endpoint, with the method in a form field
+    pub fn lastfm(&mut self, request: &Request) -> Response {
+        let form = request.form();
+        let field = |key: &str| {
+            form.iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value.as_str())
+        };
+        if field("api_key") != Some(MOCK_LASTFM_API_KEY) {
+            return lastfm_error(
+                403,
+                10,
+                "Invalid API key - You must be granted a valid key by last.fm",
+            );
+        }
+        if field("api_sig") != Some(&lastfm_signature(&form, MOCK_LASTFM_API_SECRET)) {
+            return lastfm_error(403, 13, "Invalid method signature supplied");
+        }
+
+        let method = field("method").unwrap_or_default();
+        match method {
+            "auth.getToken" => return Response::json(json!({ "token": LASTFM_TOKEN })),
+            "auth.getSession" if field("token") == Some(LASTFM_TOKEN) => {
+                return Response::json(json!({
+                    "session": { "name": "mockuser", "key": MOCK_LASTFM_SESSION_KEY, "subscriber": 0 }
+                }))
+            }
+            "auth.getSession" => {
+                return lastfm_error(403, 4, "Invalid authentication token supplied")
+            }
+            _ => {}
+        }
+
+        if field("sk") != Some(MOCK_LASTFM_SESSION_KEY) {
+            return lastfm_error(403, 9, "Invalid session key - Please re-authenticate");
+        }
+        match method {
+            "track.updateNowPlaying" => {
+                self.now_playing = Some(json!({
+                    "artist": field("artist"),
+                    "track": field("track"),
+                    "album": field("album"),
+                }));
+                Response::json(json!({ "nowplaying": {} }))
+            }
+            "track.scrobble" => {
+                let mut accepted = 0;
+                while let Some(track) = field(&format!("track[{}]", accepted)) {
+                    let indexed = |name: &str| field(&format!("{}[{}]", name, accepted));
+                    self.scrobbles.push(json!({
+                        "artist": indexed("artist"),
+                        "track": track,
+                        "album": indexed("album"),
+                        "timestamp": timestamp(indexed("timestamp")),
+                    }));
+                    accepted += 1;
+                }
+                Response::json(json!({
+                    "scrobbles": { "@attr": { "accepted": accepted, "ignored": 0 } }
+                }))
+            }
+            _ => lastfm_error(
+                400,
+                3,
+                "Invalid Method - No method with that name in this package",
+            ),
+        }
+    }
+
+    pub fn submit_listens(&mut self, request: &Request) -> Response {
+        if request.authorization.as_deref() != Some(&format!("Token {}", MOCK_LISTENBRAINZ_TOKEN)) {
+            return Response {
+                status: 401,
+                location: None,
+                body: json!({ "code": 401, "error": "Invalid authorization token." }).to_string(),
+            };
+        }
+        let body = request.json_body();
+        let listens = match body["payload"].as_array() {
+            Some(listens) if !listens.is_empty() => listens,
+            _ => return Response::error(400, "JSON document must contain a non-empty payload"),
+        };
+        for listen in listens {
+            let metadata = &listen["track_metadata"];
+            let mut received = json!({
+                "artist": metadata["artist_name"],
+                "track": metadata["track_name"],
+                "album": metadata["release_name"],
+            });
+            if body["listen_type"] == "playing_now" {
+                self.now_playing = Some(received);
+            } else {
+                received["timestamp"] = listen["listened_at"].clone();
+                self.scrobbles.push(received);
+            }
+        }
+        Response::json(json!({ "status": "ok" }))
+    }
+}
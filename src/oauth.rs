@@ -0,0 +1,407 @@
+//! The Authorization Code flow, done here rather than in rspotify so the token can be cached in
+//! the keyring. Without a client secret it uses PKCE: the secret is replaced by a random
+//! verifier that never leaves this process, and refreshing the token needs neither.
+use super::credentials::TokenCache;
+use failure::format_err;
+use rspotify::spotify::{oauth2::TokenInfo, util::generate_random_string};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_ACCOUNTS_URL: &str = "https://accounts.spotify.com";
+// The spec allows 43-128 characters
+const VERIFIER_LENGTH: usize = 64;
+
+pub struct OAuth {
+    pub client_id: String,
+    // `None` logs in with PKCE
+    pub client_secret: Option<String>,
+    pub redirect_uri: String,
+    pub scope: String,
+    pub token_cache: TokenCache,
+    // Where `/authorize` and `/api/token` live
+    pub accounts_url: String,
+    verifier: String,
+    state: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    token_type: String,
+    expires_in: u32,
+    refresh_token: Option<String>,
+    #[serde(default)]
+    scope: String,
+}
+
+impl OAuth {
+    pub fn new(
+        client_id: &str,
+        client_secret: Option<&str>,
+        redirect_uri: &str,
+        scope: &str,
+        token_cache: TokenCache,
+    ) -> OAuth {
+        OAuth {
+            client_id: client_id.to_string(),
+            client_secret: client_secret.map(String::from),
+            redirect_uri: redirect_uri.to_string(),
+            scope: scope.to_string(),
+            token_cache,
+            accounts_url: DEFAULT_ACCOUNTS_URL.to_string(),
+            verifier: generate_random_string(VERIFIER_LENGTH),
+            state: generate_random_string(16),
+        }
+    }
+
+    pub fn get_authorize_url(&self) -> String {
+        let challenge = code_challenge(&self.verifier);
+        let mut params = vec![
+            ("client_id", self.client_id.as_str()),
+            ("response_type", "code"),
+            ("redirect_uri", self.redirect_uri.as_str()),
+            ("scope", self.scope.as_str()),
+            ("state", self.state.as_str()),
+        ];
+        if self.client_secret.is_none() {
+            params.push(("code_challenge_method", "S256"));
+            params.push(("code_challenge", &challenge));
+        }
+        let query = params
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, percent_encode(value)))
+            .collect::<Vec<String>>()
+            .join("&");
+
+        format!("{}/authorize?{}", self.accounts_url, query)
+    }
+
+    /// Exchange the code in the url Spotify redirected to for a token. A bare code is accepted
+    /// too, for when only that could be copied from the browser
+    pub fn process_redirect(&self, url: &str) -> Result<TokenInfo, failure::Error> {
+        let url = url.trim();
+        if url.is_empty() {
+            return Err(format_err!("No redirect url or code was entered"));
+        }
+        if !url.contains('?') && !url.contains('=') {
+            return self.request_authorization_token(url);
+        }
+
+        if url.starts_with("http") {
+            let redirected_to = url.split('?').next().unwrap_or(url).trim_end_matches('/');
+            if redirected_to != self.redirect_uri.trim_end_matches('/') {
+                return Err(format_err!(
+                    "Spotify redirected to {} but the configured redirect uri is {}, make sure both match the Redirect URI in your app's settings on the Spotify dashboard",
+                    redirected_to,
+                    self.redirect_uri
+                ));
+            }
+        }
+
+        let query = url.split_once('?').map_or(url, |(_, query)| query);
+        let param = |name: &str| {
+            query
+                .split('&')
+                .filter_map(|pair| {
+                    let mut parts = pair.splitn(2, '=');
+                    match (parts.next(), parts.next()) {
+                        (Some(key), Some(value)) if key == name => {
+                            Some(percent_decode(value.trim()))
+                        }
+                        _ => None,
+                    }
+                })
+                .next()
+        };
+
+        if let Some(error) = param("error") {
+            return Err(match error.as_str() {
+                "access_denied" => format_err!("The authorization was cancelled in the browser"),
+                _ => format_err!("Spotify denied the authorization: {}", error),
+            });
+        }
+        if param("state").as_ref() != Some(&self.state) {
+            return Err(format_err!(
+                "The authorization state does not match, the url is probably from an earlier login attempt. Please log in again"
+            ));
+        }
+        let code = param("code").ok_or_else(|| format_err!("No code in the redirect url"))?;
+
+        self.request_authorization_token(&code)
+    }
+
+    fn request_authorization_token(&self, code: &str) -> Result<TokenInfo, failure::Error> {
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &self.redirect_uri),
+        ];
+        if self.client_secret.is_none() {
+            form.push(("client_id", &self.client_id));
+            form.push(("code_verifier", &self.verifier));
+        }
+        let token_info = self.request_token(&form)?;
+        self.token_cache.save(&token_info)?;
+
+        Ok(token_info)
+    }
+
+    /// The cached token, refreshed first when it has expired. `None` when there is no saved
+    /// login or it could not be refreshed, so the user has to log in again
+    pub fn get_cached_token(&self) -> Result<Option<TokenInfo>, failure::Error> {
+        let token_info = match self.token_cache.load() {
+            Some(token_info) => token_info,
+            None => return Ok(None),
+        };
+
+        if !is_token_expired(&token_info) {
+            return Ok(Some(token_info));
+        }
+
+        let refresh_token = match token_info.refresh_token {
+            Some(refresh_token) => refresh_token,
+            None => return Ok(None),
+        };
+        let mut form = vec![
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token.as_str()),
+        ];
+        if self.client_secret.is_none() {
+            form.push(("client_id", &self.client_id));
+        }
+        let mut new_token_info = match self.request_token(&form) {
+            Ok(new_token_info) => new_token_info,
+            Err(_) => return Ok(None),
+        };
+        // Spotify only sometimes rotates the refresh token
+        if new_token_info.refresh_token.is_none() {
+            new_token_info.refresh_token = Some(refresh_token.clone());
+        }
+        self.token_cache.save(&new_token_info)?;
+
+        Ok(Some(new_token_info))
+    }
+
+    fn request_token(&self, form: &[(&str, &str)]) -> Result<TokenInfo, failure::Error> {
+        let mut request = reqwest::blocking::Client::new()
+            .post(&format!("{}/api/token", self.accounts_url))
+            .form(form);
+        if let Some(client_secret) = &self.client_secret {
+            request = request.basic_auth(&self.client_id, Some(client_secret));
+        }
+        let response = request.send()?;
+        let status = response.status();
+        let body = response.text()?;
+        if !status.is_success() {
+            return Err(describe_token_error(&body, &self.redirect_uri));
+        }
+        let response: TokenResponse = serde_json::from_str(&body)?;
+
+        let token_info = TokenInfo {
+            access_token: response.access_token,
+            token_type: response.token_type,
+            expires_in: response.expires_in,
+            expires_at: Some(now() + i64::from(response.expires_in)),
+            refresh_token: response.refresh_token,
+            scope: response.scope,
+        };
+
+        Ok(token_info)
+    }
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: String,
+    #[serde(default)]
+    error_description: String,
+}
+
+/// Turn the token endpoint's error into something that says what to fix
+fn describe_token_error(body: &str, redirect_uri: &str) -> failure::Error {
+    let response = match serde_json::from_str::<ErrorResponse>(body) {
+        Ok(response) => response,
+        Err(_) => return format_err!("Spotify rejected the login: {}", body),
+    };
+    let description = response.error_description.to_lowercase();
+
+    if description.contains("redirect") {
+        format_err!(
+            "Spotify rejected the redirect uri {}, add it exactly as is to the Redirect URIs in your app's settings on the Spotify dashboard",
+            redirect_uri
+        )
+    } else if response.error == "invalid_client" {
+        format_err!(
+            "Spotify rejected the client ID or secret ({}), check them in client.yml",
+            response.error_description
+        )
+    } else if response.error == "invalid_grant" {
+        format_err!(
+            "The authorization code is invalid or has already been used ({}), please log in again",
+            response.error_description
+        )
+    } else {
+        format_err!(
+            "Spotify rejected the login: {} {}",
+            response.error,
+            response.error_description
+        )
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() as i64)
+}
+
+fn is_token_expired(token_info: &TokenInfo) -> bool {
+    // 10s as buffer time, like rspotify
+    token_info
+        .expires_at
+        .is_none_or(|expires_at| now() > expires_at - 10)
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Undo the encoding of a query value, leaving malformed escapes as they are
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], escaped) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (b'+', _) => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+pub fn code_challenge(verifier: &str) -> String {
+    base64::encode_config(
+        &Sha256::digest(verifier.as_bytes()),
+        base64::URL_SAFE_NO_PAD,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_challenge() {
+        // Base64url without padding, so it can go straight into the authorize url
+        assert_eq!(
+            code_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gXWFOEjXk"),
+            "FXejttrNfkgImX5-C0uWavhThVBfXj-sbX1JvQiFa_s"
+        );
+    }
+
+    #[test]
+    fn test_process_redirect_checks_state() {
+        let oauth = OAuth::new(
+            "id",
+            None,
+            "http://localhost:8888/callback",
+            "",
+            TokenCache::File(Default::default()),
+        );
+        assert!(oauth
+            .process_redirect("/callback?code=abc&state=wrong")
+            .is_err());
+        assert!(oauth
+            .process_redirect("/callback?error=access_denied")
+            .is_err());
+
+        let error = oauth
+            .process_redirect("http://127.0.0.1:8888/callback?code=abc&state=wrong")
+            .unwrap_err();
+        assert!(error.to_string().contains("configured redirect uri"));
+        assert!(oauth.process_redirect("  \n").is_err());
+
+        let mut oauth = oauth;
+        oauth.state = "a/b c".to_string();
+        let error = oauth
+            .process_redirect("/callback?code=abc&state=a%2Fb+c")
+            .unwrap_err();
+        assert!(!error.to_string().contains("state does not match"));
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("AQB%2Fx-y_z"), "AQB/x-y_z");
+        assert_eq!(percent_decode("a+b%20c"), "a b c");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz"), "%zz");
+        assert_eq!(percent_decode(&percent_encode("é ~/")), "é ~/");
+    }
+
+    #[test]
+    fn test_describe_token_error() {
+        let redirect_uri = "http://localhost:8888/callback";
+        let error = describe_token_error(
+            r#"{"error":"invalid_grant","error_description":"Invalid redirect URI"}"#,
+            redirect_uri,
+        );
+        assert!(error.to_string().contains(redirect_uri));
+
+        let error = describe_token_error(
+            r#"{"error":"invalid_client","error_description":"Invalid client secret"}"#,
+            redirect_uri,
+        );
+        assert!(error.to_string().contains("client.yml"));
+
+        let error = describe_token_error("Bad gateway", redirect_uri);
+        assert!(error.to_string().contains("Bad gateway"));
+    }
+
+    #[test]
+    fn test_authorize_url_only_uses_pkce_without_secret() {
+        let pkce = OAuth::new(
+            "id",
+            None,
+            "http://localhost:8888/callback",
+            "user-read-private streaming",
+            TokenCache::File(Default::default()),
+        );
+        let url = pkce.get_authorize_url();
+        assert!(url.contains("code_challenge_method=S256"));
+        assert!(url.contains("redirect_uri=http%3A%2F%2Flocalhost%3A8888%2Fcallback"));
+        assert!(url.contains("scope=user-read-private%20streaming"));
+
+        let with_secret = OAuth::new(
+            "id",
+            Some("secret"),
+            "http://localhost:8888/callback",
+            "",
+            TokenCache::File(Default::default()),
+        );
+        assert!(!with_secret.get_authorize_url().contains("code_challenge"));
+    }
+}
@@ -0,0 +1,120 @@
+//! Playback commands for one device, without the rest of the app's state. `App` sends its
+//! commands through this, with the device saved in client.yml.
+use crate::spotify_api::SpotifyApi;
+use reqwest::Method;
+use rspotify::spotify::{
+    model::{context::FullPlayingContext, offset::for_position},
+    senum::RepeatState,
+};
+use serde_json::json;
+use std::rc::Rc;
+
+/// Controls playback on `device_id`, or on whichever device is active when it is `None`
+pub struct PlaybackController {
+    spotify: Rc<dyn SpotifyApi>,
+    device_id: Option<String>,
+}
+
+impl PlaybackController {
+    pub fn new(spotify: Rc<dyn SpotifyApi>, device_id: Option<String>) -> PlaybackController {
+        PlaybackController { spotify, device_id }
+    }
+
+    pub fn device_id(&self) -> Option<&str> {
+        self.device_id.as_deref()
+    }
+
+    /// What is playing, `None` when nothing is
+    pub fn current_playback(&self) -> Result<Option<FullPlayingContext>, failure::Error> {
+        self.spotify.current_playback(None)
+    }
+
+    /// Play a context (album, artist or playlist URI) or a list of track URIs, starting at the
+    /// `offset`th track. A context wins over `uris`, and with neither playback is resumed
+    pub fn play(
+        &self,
+        context_uri: Option<String>,
+        uris: Option<Vec<String>>,
+        offset: Option<usize>,
+    ) -> Result<(), failure::Error> {
+        let uris = if context_uri.is_some() { None } else { uris };
+        let offset = offset.and_then(|o| for_position(o as u32));
+        self.spotify
+            .start_playback(self.device_id.clone(), context_uri, uris, offset, None)
+    }
+
+    pub fn resume(&self) -> Result<(), failure::Error> {
+        self.play(None, None, None)
+    }
+
+    pub fn pause(&self) -> Result<(), failure::Error> {
+        self.spotify.pause_playback(self.device_id.clone())
+    }
+
+    pub fn next(&self) -> Result<(), failure::Error> {
+        self.spotify.next_track(self.device_id.clone())
+    }
+
+    pub fn previous(&self) -> Result<(), failure::Error> {
+        self.spotify.previous_track(self.device_id.clone())
+    }
+
+    pub fn seek(&self, position_ms: u32) -> Result<(), failure::Error> {
+        self.spotify.seek_track(position_ms, self.device_id.clone())
+    }
+
+    pub fn set_shuffle(&self, shuffle: bool) -> Result<(), failure::Error> {
+        self.spotify.shuffle(shuffle, self.device_id.clone())
+    }
+
+    pub fn set_repeat(&self, repeat: RepeatState) -> Result<(), failure::Error> {
+        self.spotify.repeat(repeat, self.device_id.clone())
+    }
+
+    pub fn set_volume(&self, volume_percent: u8) -> Result<(), failure::Error> {
+        self.spotify.volume(volume_percent, self.device_id.clone())
+    }
+
+    /// Move playback to `device_id`, which only starts playing there if `play` is set
+    pub fn transfer(&self, device_id: &str, play: bool) -> Result<(), failure::Error> {
+        // rspotify misspells `device_ids` in its transfer payload, so build the request ourselves
+        let body = json!({ "device_ids": [device_id], "play": play });
+        self.spotify
+            .web_api_call(Method::PUT, "me/player", Some(&body))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spotify_api::fake::FakeSpotify;
+
+    #[test]
+    fn test_commands_go_to_the_device() {
+        let spotify = Rc::new(FakeSpotify::new());
+        let playback = PlaybackController::new(spotify.clone(), Some("laptop".to_string()));
+
+        playback
+            .play(
+                Some("spotify:album:a".to_string()),
+                Some(vec!["spotify:track:1".to_string()]),
+                Some(2),
+            )
+            .unwrap();
+        playback.set_volume(40).unwrap();
+        playback.transfer("phone", true).unwrap();
+
+        assert_eq!(
+            spotify.calls_to("start_playback"),
+            vec![
+                "Some(\"laptop\"), Some(\"spotify:album:a\"), None, \
+                 Some(Offset { position: Some(2), uri: None }), None"
+            ]
+        );
+        assert_eq!(spotify.calls_to("volume"), vec!["40, Some(\"laptop\")"]);
+        let transfer = &spotify.calls_to("PUT me/player")[0];
+        assert!(transfer.contains("phone"));
+        assert!(transfer.contains("Bool(true)"));
+    }
+}
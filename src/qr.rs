@@ -0,0 +1,61 @@
+//! Drawing a QR code of the authorize url in the terminal, so it can be opened on a phone when
+//! logging in over SSH.
+use qrcode::{Color, EcLevel, QrCode};
+
+// Scanners need a light border around the code, the spec asks for 4 modules
+const QUIET_ZONE: usize = 4;
+
+/// Two rows of modules per line of half-blocks, light modules drawn as blocks like
+/// `qrencode -t UTF8`. `None` when `data` is too long for a QR code
+pub fn to_terminal_string(data: &[u8]) -> Option<String> {
+    let code = QrCode::with_error_correction_level(data, EcLevel::L).ok()?;
+    let size = code.width();
+    let total = size + QUIET_ZONE * 2;
+    let is_light = |x: usize, y: usize| {
+        x < QUIET_ZONE
+            || y < QUIET_ZONE
+            || x >= size + QUIET_ZONE
+            || y >= size + QUIET_ZONE
+            || code[(x - QUIET_ZONE, y - QUIET_ZONE)] == Color::Light
+    };
+
+    let mut output = String::new();
+    for y in (0..total).step_by(2) {
+        for x in 0..total {
+            let top = is_light(x, y);
+            let bottom = y + 1 >= total || is_light(x, y + 1);
+            output.push(match (top, bottom) {
+                (true, true) => '█',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (false, false) => ' ',
+            });
+        }
+        output.push('\n');
+    }
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_terminal_string() {
+        let output = to_terminal_string(b"https://accounts.spotify.com/authorize").unwrap();
+        let lines = output.lines().collect::<Vec<&str>>();
+
+        // 38 bytes need version 3, 29 modules and the quiet zone on both sides
+        assert_eq!(lines.len(), (29 + QUIET_ZONE * 2).div_ceil(2));
+        assert!(lines
+            .iter()
+            .all(|line| line.chars().count() == 29 + QUIET_ZONE * 2));
+        // The quiet zone, then the dark finder pattern in the corner
+        assert!(lines[..QUIET_ZONE / 2]
+            .iter()
+            .all(|line| line.chars().all(|c| c == '█')));
+        assert!(lines[QUIET_ZONE / 2].starts_with("████ "));
+
+        assert!(to_terminal_string(&[0; 3000]).is_none());
+    }
+}
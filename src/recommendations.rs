@@ -0,0 +1,428 @@
+use serde_json::{map::Map, Value};
+
+// Spotify accepts at most five seeds in total, across artists, tracks and genres
+pub const MAX_SEEDS: usize = 5;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum RecommendationSeed {
+    Artist { id: String, name: String },
+    Track { id: String, name: String },
+    Genre(String),
+}
+
+impl RecommendationSeed {
+    pub fn name(&self) -> &str {
+        match self {
+            RecommendationSeed::Artist { name, .. } | RecommendationSeed::Track { name, .. } => {
+                name
+            }
+            RecommendationSeed::Genre(genre) => genre,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RecommendationSeed::Artist { .. } => "Artist",
+            RecommendationSeed::Track { .. } => "Track",
+            RecommendationSeed::Genre(_) => "Genre",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Tunable {
+    Acousticness,
+    Danceability,
+    Energy,
+    Instrumentalness,
+    Liveness,
+    Loudness,
+    Popularity,
+    Speechiness,
+    Tempo,
+    Valence,
+}
+
+pub const TUNABLES: [Tunable; 10] = [
+    Tunable::Acousticness,
+    Tunable::Danceability,
+    Tunable::Energy,
+    Tunable::Instrumentalness,
+    Tunable::Liveness,
+    Tunable::Loudness,
+    Tunable::Popularity,
+    Tunable::Speechiness,
+    Tunable::Tempo,
+    Tunable::Valence,
+];
+
+impl Tunable {
+    // The attribute name used in the `min_`, `max_` and `target_` query parameters
+    pub fn name(self) -> &'static str {
+        match self {
+            Tunable::Acousticness => "acousticness",
+            Tunable::Danceability => "danceability",
+            Tunable::Energy => "energy",
+            Tunable::Instrumentalness => "instrumentalness",
+            Tunable::Liveness => "liveness",
+            Tunable::Loudness => "loudness",
+            Tunable::Popularity => "popularity",
+            Tunable::Speechiness => "speechiness",
+            Tunable::Tempo => "tempo",
+            Tunable::Valence => "valence",
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Tunable::Acousticness => "Acousticness",
+            Tunable::Danceability => "Danceability",
+            Tunable::Energy => "Energy",
+            Tunable::Instrumentalness => "Instrumentalness",
+            Tunable::Liveness => "Liveness",
+            Tunable::Loudness => "Loudness (dB)",
+            Tunable::Popularity => "Popularity",
+            Tunable::Speechiness => "Speechiness",
+            Tunable::Tempo => "Tempo (BPM)",
+            Tunable::Valence => "Valence",
+        }
+    }
+
+    fn range(self) -> (f32, f32) {
+        match self {
+            Tunable::Loudness => (-60.0, 0.0),
+            Tunable::Popularity => (0.0, 100.0),
+            Tunable::Tempo => (40.0, 220.0),
+            _ => (0.0, 1.0),
+        }
+    }
+
+    fn step(self) -> f32 {
+        match self {
+            Tunable::Loudness => 1.0,
+            Tunable::Popularity | Tunable::Tempo => 5.0,
+            _ => 0.05,
+        }
+    }
+
+    fn is_integer(self) -> bool {
+        self == Tunable::Popularity
+    }
+
+    pub fn format(self, value: f32) -> String {
+        match self {
+            Tunable::Loudness | Tunable::Popularity | Tunable::Tempo => format!("{:.0}", value),
+            _ => format!("{:.2}", value),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Bound {
+    Min,
+    Target,
+    Max,
+}
+
+pub const BOUNDS: [Bound; 3] = [Bound::Min, Bound::Target, Bound::Max];
+
+impl Bound {
+    pub fn title(self) -> &'static str {
+        match self {
+            Bound::Min => "Min",
+            Bound::Target => "Target",
+            Bound::Max => "Max",
+        }
+    }
+
+    fn prefix(self) -> &'static str {
+        match self {
+            Bound::Min => "min_",
+            Bound::Target => "target_",
+            Bound::Max => "max_",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct TunableValues {
+    pub min: Option<f32>,
+    pub target: Option<f32>,
+    pub max: Option<f32>,
+}
+
+impl TunableValues {
+    pub fn get(&self, bound: Bound) -> Option<f32> {
+        match bound {
+            Bound::Min => self.min,
+            Bound::Target => self.target,
+            Bound::Max => self.max,
+        }
+    }
+
+    fn get_mut(&mut self, bound: Bound) -> &mut Option<f32> {
+        match bound {
+            Bound::Min => &mut self.min,
+            Bound::Target => &mut self.target,
+            Bound::Max => &mut self.max,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BuilderSection {
+    Seeds,
+    Genres,
+    Tunables,
+}
+
+pub struct RecommendationsBuilder {
+    pub seeds: Vec<RecommendationSeed>,
+    // Genres that can be used as seeds, fetched the first time the builder is opened
+    pub genres: Vec<String>,
+    pub values: [TunableValues; 10],
+    pub section: BuilderSection,
+    pub selected_seed_index: usize,
+    pub selected_genre_index: usize,
+    pub selected_tunable_index: usize,
+    pub selected_bound: Bound,
+}
+
+impl Default for RecommendationsBuilder {
+    fn default() -> Self {
+        RecommendationsBuilder {
+            seeds: vec![],
+            genres: vec![],
+            values: [TunableValues::default(); 10],
+            section: BuilderSection::Seeds,
+            selected_seed_index: 0,
+            selected_genre_index: 0,
+            selected_tunable_index: 0,
+            selected_bound: Bound::Target,
+        }
+    }
+}
+
+impl RecommendationsBuilder {
+    pub fn is_full(&self) -> bool {
+        self.seeds.len() >= MAX_SEEDS
+    }
+
+    // Returns false when the seed is already used or there is no room for it
+    pub fn add_seed(&mut self, seed: RecommendationSeed) -> bool {
+        if self.is_full() || self.seeds.contains(&seed) {
+            return false;
+        }
+        self.seeds.push(seed);
+        true
+    }
+
+    pub fn remove_selected_seed(&mut self) {
+        if self.selected_seed_index < self.seeds.len() {
+            self.seeds.remove(self.selected_seed_index);
+        }
+        if self.selected_seed_index >= self.seeds.len() && self.selected_seed_index > 0 {
+            self.selected_seed_index = self.seeds.len().saturating_sub(1);
+        }
+    }
+
+    pub fn next_section(&mut self) {
+        self.section = match self.section {
+            BuilderSection::Seeds => BuilderSection::Genres,
+            BuilderSection::Genres => BuilderSection::Tunables,
+            BuilderSection::Tunables => BuilderSection::Seeds,
+        };
+    }
+
+    pub fn next_bound(&mut self) {
+        self.selected_bound = match self.selected_bound {
+            Bound::Min => Bound::Target,
+            Bound::Target | Bound::Max => Bound::Max,
+        };
+    }
+
+    pub fn previous_bound(&mut self) {
+        self.selected_bound = match self.selected_bound {
+            Bound::Max => Bound::Target,
+            Bound::Target | Bound::Min => Bound::Min,
+        };
+    }
+
+    // Steps the selected value up or down. An unset value starts from the middle of the range
+    pub fn adjust_selected_value(&mut self, steps: i32) {
+        let tunable = TUNABLES[self.selected_tunable_index];
+        let (low, high) = tunable.range();
+        let value = self.values[self.selected_tunable_index].get_mut(self.selected_bound);
+
+        let next = match value {
+            Some(current) => *current + tunable.step() * steps as f32,
+            None => (low + high) / 2.0,
+        };
+        // Round away float drift so repeated steps land on clean values
+        let next = (next / tunable.step()).round() * tunable.step();
+        *value = Some(next.max(low).min(high));
+    }
+
+    pub fn clear_selected_value(&mut self) {
+        *self.values[self.selected_tunable_index].get_mut(self.selected_bound) = None;
+    }
+
+    fn seed_ids<F>(&self, select: F) -> Option<Vec<String>>
+    where
+        F: Fn(&RecommendationSeed) -> Option<String>,
+    {
+        let ids = self
+            .seeds
+            .iter()
+            .filter_map(select)
+            .collect::<Vec<String>>();
+        if ids.is_empty() {
+            None
+        } else {
+            Some(ids)
+        }
+    }
+
+    pub fn seed_artists(&self) -> Option<Vec<String>> {
+        self.seed_ids(|seed| match seed {
+            RecommendationSeed::Artist { id, .. } => Some(id.to_owned()),
+            _ => None,
+        })
+    }
+
+    pub fn seed_tracks(&self) -> Option<Vec<String>> {
+        self.seed_ids(|seed| match seed {
+            RecommendationSeed::Track { id, .. } => Some(id.to_owned()),
+            _ => None,
+        })
+    }
+
+    pub fn seed_genres(&self) -> Option<Vec<String>> {
+        self.seed_ids(|seed| match seed {
+            RecommendationSeed::Genre(genre) => Some(genre.to_owned()),
+            _ => None,
+        })
+    }
+
+    pub fn seed_names(&self) -> String {
+        self.seeds
+            .iter()
+            .map(|seed| seed.name())
+            .collect::<Vec<&str>>()
+            .join(", ")
+    }
+
+    // The `min_`, `max_` and `target_` parameters for every value that has been set
+    pub fn payload(&self) -> Map<String, Value> {
+        let mut payload = Map::new();
+        for (tunable, values) in TUNABLES.iter().zip(self.values.iter()) {
+            for bound in BOUNDS.iter() {
+                if let Some(value) = values.get(*bound) {
+                    let value = if tunable.is_integer() {
+                        Value::from(value.round() as i64)
+                    } else {
+                        // Avoid sending f32 noise such as 0.6000000238
+                        Value::from((f64::from(value) * 100.0).round() / 100.0)
+                    };
+                    payload.insert(format!("{}{}", bound.prefix(), tunable.name()), value);
+                }
+            }
+        }
+        payload
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn genre(name: &str) -> RecommendationSeed {
+        RecommendationSeed::Genre(name.to_string())
+    }
+
+    #[test]
+    fn test_add_seed() {
+        let mut builder = RecommendationsBuilder::default();
+
+        assert!(builder.add_seed(genre("house")));
+        assert!(!builder.add_seed(genre("house")));
+
+        for name in &["disco", "funk", "soul", "techno"] {
+            assert!(builder.add_seed(genre(name)));
+        }
+        assert!(builder.is_full());
+        assert!(!builder.add_seed(genre("jazz")));
+        assert_eq!(builder.seeds.len(), MAX_SEEDS);
+    }
+
+    #[test]
+    fn test_remove_selected_seed() {
+        let mut builder = RecommendationsBuilder::default();
+        builder.add_seed(genre("house"));
+        builder.add_seed(genre("disco"));
+        builder.selected_seed_index = 1;
+
+        builder.remove_selected_seed();
+        assert_eq!(builder.seeds, vec![genre("house")]);
+        assert_eq!(builder.selected_seed_index, 0);
+
+        builder.remove_selected_seed();
+        assert!(builder.seeds.is_empty());
+        assert_eq!(builder.selected_seed_index, 0);
+    }
+
+    #[test]
+    fn test_seed_ids() {
+        let mut builder = RecommendationsBuilder::default();
+        builder.add_seed(RecommendationSeed::Artist {
+            id: "artist".to_string(),
+            name: "Daft Punk".to_string(),
+        });
+        builder.add_seed(genre("house"));
+
+        assert_eq!(builder.seed_artists(), Some(vec!["artist".to_string()]));
+        assert_eq!(builder.seed_genres(), Some(vec!["house".to_string()]));
+        assert_eq!(builder.seed_tracks(), None);
+        assert_eq!(builder.seed_names(), "Daft Punk, house");
+    }
+
+    #[test]
+    fn test_adjust_selected_value() {
+        let mut builder = RecommendationsBuilder {
+            selected_tunable_index: TUNABLES
+                .iter()
+                .position(|tunable| *tunable == Tunable::Energy)
+                .unwrap(),
+            ..Default::default()
+        };
+
+        builder.adjust_selected_value(1);
+        assert_eq!(
+            builder.values[builder.selected_tunable_index].target,
+            Some(0.5)
+        );
+
+        for _ in 0..20 {
+            builder.adjust_selected_value(1);
+        }
+        assert_eq!(
+            builder.values[builder.selected_tunable_index].target,
+            Some(1.0)
+        );
+
+        builder.clear_selected_value();
+        assert_eq!(builder.values[builder.selected_tunable_index].target, None);
+    }
+
+    #[test]
+    fn test_payload() {
+        let mut builder = RecommendationsBuilder::default();
+        builder.values[2].min = Some(0.6);
+        builder.values[6].target = Some(70.0);
+
+        let payload = builder.payload();
+        assert_eq!(payload.len(), 2);
+        assert_eq!(payload.get("target_popularity"), Some(&Value::from(70)));
+        assert_eq!(payload.get("min_energy"), Some(&Value::from(0.6)));
+    }
+}
@@ -1,15 +1,18 @@
-use rspotify::spotify::{oauth2::SpotifyOAuth, util::request_token};
 use std::{
     io::prelude::*,
     net::{TcpListener, TcpStream},
 };
 
-pub fn redirect_uri_web_server(spotify_oauth: &mut SpotifyOAuth, port: u16) -> Result<String, ()> {
+/// Wait for Spotify to redirect back to us, `open_authorize_url` is called once we are listening
+pub fn listen_for_redirect<F>(port: u16, open_authorize_url: F) -> Result<String, ()>
+where
+    F: FnOnce(),
+{
     let listener = TcpListener::bind(format!("127.0.0.1:{}", port));
 
     match listener {
         Ok(listener) => {
-            request_token(spotify_oauth);
+            open_authorize_url();
 
             for stream in listener.incoming() {
                 match stream {
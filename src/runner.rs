@@ -0,0 +1,394 @@
+//! The terminal frontend: draws the app and feeds it key presses and ticks until it quits.
+use crate::{
+    album_art,
+    app::{ActiveBlock, App, RouteId},
+    auth::{build_oauth, get_spotify, Login},
+    config::ClientConfig,
+    credentials::PASSPHRASE_ENV,
+    event::{self, Key},
+    handlers,
+    history::History,
+    import::Import,
+    scrobbler::Scrobbler,
+    spotify_api::{traffic::Traffic, WebApiClient},
+    ui,
+    user_config::{load_user_config, UserConfig},
+};
+use backtrace::Backtrace;
+use crossterm::{
+    cursor::MoveTo,
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    style::Print,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    ExecutableCommand,
+};
+use failure::format_err;
+use std::{
+    cmp::{max, min},
+    io::{self, stdout, Write},
+    panic::PanicHookInfo,
+    rc::Rc,
+};
+use tui::{
+    backend::{Backend, CrosstermBackend},
+    Terminal,
+};
+
+/// Hand the configs and client to a fresh `App`, asking for a device when none is saved yet
+pub fn setup_app(
+    app: &mut App,
+    client_config: ClientConfig,
+    user_config: UserConfig,
+    spotify: WebApiClient,
+) {
+    app.album_art = album_art::AlbumArt::new(user_config.behavior.album_art_protocol);
+    app.client_config = client_config;
+    app.user_config = user_config;
+
+    app.spotify = Some(Rc::new(spotify));
+
+    if app.user_config.behavior.listening_history {
+        match app
+            .client_config
+            .get_or_build_paths()
+            .and_then(|paths| History::open(&paths.history_path))
+        {
+            Ok(history) => app.history = Some(history),
+            Err(e) => app.handle_error(e),
+        }
+    }
+
+    if let Some(scrobbler_config) = app.client_config.scrobbler.clone() {
+        match app
+            .client_config
+            .get_or_build_paths()
+            .and_then(|paths| Scrobbler::new(scrobbler_config, paths.scrobble_queue_path))
+        {
+            Ok(scrobbler) => app.scrobbler = Some(scrobbler),
+            Err(e) => app.handle_error(e),
+        }
+    }
+
+    app.clipboard_context = clipboard::ClipboardProvider::new().ok();
+
+    app.help_docs_size = ui::help::get_help_docs().len() as u32;
+
+    // Now that spotify is ready, check if the user has already selected a device_id to
+    // play music on, if not send them to the device selection view
+    if app.client_config.device_id.is_none() {
+        app.handle_get_devices();
+    }
+}
+
+/// Switch to another profile without restarting. Only profiles that have already been set up
+/// and logged in with `spt --profile <name>` can be used, as we cannot prompt inside the UI
+fn switch_profile(
+    app: &mut App,
+    profile: String,
+    traffic: &Traffic,
+) -> Result<Login, failure::Error> {
+    let mut client_config = ClientConfig::with_profile(Some(profile))?;
+    let profile_name = client_config.get_profile_name().to_string();
+    if !client_config
+        .get_or_build_paths()?
+        .config_file_path
+        .exists()
+    {
+        return Err(format_err!(
+            "Profile `{}` is not set up yet, run `spt --profile {}` first",
+            profile_name,
+            profile_name
+        ));
+    }
+    if client_config.needs_passphrase()? {
+        return Err(format_err!(
+            "Profile `{}` keeps its credentials encrypted, set {} to switch to it",
+            profile_name,
+            PASSPHRASE_ENV
+        ));
+    }
+    client_config.load_config()?;
+
+    let mut user_config = load_user_config(&client_config)?;
+    // The event loop keeps ticking at the rate it was started with
+    user_config.behavior.tick_rate_milliseconds = app.user_config.behavior.tick_rate_milliseconds;
+
+    let oauth = build_oauth(&client_config)?;
+    let token_info = oauth.get_cached_token()?.ok_or_else(|| {
+        format_err!(
+            "No saved login for profile `{}`, run `spt --profile {}` to log in",
+            profile_name,
+            profile_name
+        )
+    })?;
+    let (spotify, token_expiry) = get_spotify(token_info, client_config.get_api_url(), traffic);
+
+    let size = app.size;
+    *app = App::new();
+    app.size = size;
+    setup_app(app, client_config, user_config, spotify);
+
+    Ok(Login {
+        oauth,
+        token_expiry,
+    })
+}
+
+fn close_application() -> Result<(), failure::Error> {
+    disable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, LeaveAlternateScreen, DisableMouseCapture)?;
+    Ok(())
+}
+
+/// Restores the terminal before a panic message is printed, in debug builds
+pub fn panic_hook(info: &PanicHookInfo<'_>) {
+    if cfg!(debug_assertions) {
+        let location = info.location().unwrap();
+
+        let msg = match info.payload().downcast_ref::<&'static str>() {
+            Some(s) => *s,
+            None => match info.payload().downcast_ref::<String>() {
+                Some(s) => &s[..],
+                None => "Box<Any>",
+            },
+        };
+
+        let stacktrace: String = format!("{:?}", Backtrace::new()).replace('\n', "\n\r");
+
+        disable_raw_mode().unwrap();
+        execute!(
+            io::stdout(),
+            LeaveAlternateScreen,
+            Print(format!(
+                "thread '<unnamed>' panicked at '{}', {}\n\r{}",
+                msg, location, stacktrace
+            )),
+            DisableMouseCapture
+        )
+        .unwrap();
+    }
+}
+
+/// Run the app in the terminal until it is quit. Without a `login` (when replaying a recording)
+/// the token is never refreshed and profiles cannot be switched
+pub fn start_ui(
+    client_config: ClientConfig,
+    user_config: UserConfig,
+    spotify: WebApiClient,
+    traffic: Traffic,
+    login: Option<Login>,
+) -> Result<(), failure::Error> {
+    run(client_config, user_config, spotify, traffic, login, None)
+}
+
+/// Run the app on the Import screen, to review the uncertain matches of `import` before its
+/// playlist is created
+pub fn start_import_ui(
+    client_config: ClientConfig,
+    user_config: UserConfig,
+    spotify: WebApiClient,
+    traffic: Traffic,
+    login: Option<Login>,
+    import: Import,
+) -> Result<(), failure::Error> {
+    run(
+        client_config,
+        user_config,
+        spotify,
+        traffic,
+        login,
+        Some(import),
+    )
+}
+
+fn run(
+    client_config: ClientConfig,
+    user_config: UserConfig,
+    spotify: WebApiClient,
+    traffic: Traffic,
+    mut login: Option<Login>,
+    import: Option<Import>,
+) -> Result<(), failure::Error> {
+    // Terminal initialization
+    let mut stdout = stdout();
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    enable_raw_mode()?;
+
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+    terminal.hide_cursor()?;
+
+    let events = event::Events::new(user_config.behavior.tick_rate_milliseconds);
+
+    // Initialise app state
+    let mut app = App::new();
+
+    setup_app(&mut app, client_config, user_config, spotify);
+    // Plays from a recording did not happen now, keep them out of the history and unscrobbled
+    if let Traffic::Replay(_) = traffic {
+        app.history = None;
+        app.scrobbler = None;
+    }
+    if import.is_some() {
+        app.import = import;
+        app.push_navigation_stack(RouteId::Import, ActiveBlock::Import);
+    }
+
+    let mut is_first_render = true;
+
+    loop {
+        // Get the size of the screen on each loop to account for resize event
+        if let Ok(size) = terminal.backend().size() {
+            // Reset the help menu is the terminal was resized
+            if app.size != size {
+                app.help_menu_max_lines = 0;
+                app.help_menu_offset = 0;
+                app.help_menu_page = 0;
+            }
+
+            app.size = size;
+
+            // Based on the size of the terminal, adjust the search limit.
+            let potential_limit = max((app.size.height as i32) - 13, 0) as u32;
+            let max_limit = min(potential_limit, 50);
+            app.large_search_limit = min((f32::from(size.height) / 1.4) as u32, max_limit);
+            app.small_search_limit = min((f32::from(size.height) / 2.85) as u32, max_limit / 2);
+
+            // Based on the size of the terminal, adjust how many lines are
+            // dislayed in the help menu
+            if app.size.height > 8 {
+                app.help_menu_max_lines = (app.size.height as u32) - 8;
+            } else {
+                app.help_menu_max_lines = 0;
+            }
+        };
+
+        // Sixel images are only removed by redrawing the cells under them
+        if app.album_art.take_full_redraw() {
+            terminal.resize(app.size)?;
+        }
+
+        let current_route = app.get_current_route();
+        terminal.draw(|mut f| ui::draw(&mut f, &app))?;
+
+        if current_route.active_block == ActiveBlock::Input {
+            match terminal.show_cursor() {
+                Ok(_r) => {}
+                Err(_e) => {}
+            };
+        } else {
+            match terminal.hide_cursor() {
+                Ok(_r) => {}
+                Err(_e) => {}
+            };
+        }
+
+        app.album_art
+            .draw_graphics(terminal.backend_mut(), app.size)?;
+
+        let cursor_offset = if app.size.height > ui::util::SMALL_TERMINAL_HEIGHT {
+            2
+        } else {
+            1
+        };
+
+        // Put the cursor back inside the input box
+        terminal.backend_mut().execute(MoveTo(
+            cursor_offset + app.input_cursor_position,
+            cursor_offset,
+        ))?;
+
+        if let Some(login) = &mut login {
+            match login.refresh(app.client_config.get_api_url(), &traffic) {
+                Ok(Some(spotify)) => app.spotify = Some(Rc::new(spotify)),
+                Ok(None) => {}
+                Err(e) => {
+                    println!("\n{}", e);
+                    close_application()?;
+                    break;
+                }
+            }
+        }
+
+        match events.next()? {
+            event::Event::Input(key) => {
+                if key == Key::Ctrl('c') {
+                    close_application()?;
+                    break;
+                }
+
+                let current_active_block = app.get_current_route().active_block;
+
+                app.status_message = None;
+                // To avoid swallowing the global key presses `q` and `-` make a special
+                // case for the input handler
+                if current_active_block == ActiveBlock::Input {
+                    handlers::input_handler(key, &mut app);
+                } else if key == app.user_config.keys.back {
+                    if app.get_current_route().active_block != ActiveBlock::Input {
+                        // Go back through navigation stack when not in search input mode and exit the app if there are no more places to back to
+
+                        let pop_result = match app.pop_navigation_stack() {
+                            Some(ref x) if x.id == RouteId::Search => app.pop_navigation_stack(),
+                            Some(x) => Some(x),
+                            None => None,
+                        };
+                        if pop_result.is_none() {
+                            close_application()?;
+                            break; // Exit application
+                        }
+                    }
+                } else {
+                    handlers::handle_app(key, &mut app);
+                }
+            }
+            event::Event::Tick => {
+                app.update_on_tick();
+            }
+        }
+
+        if let Some(profile) = app.pending_profile.take() {
+            let switched = if login.is_some() {
+                switch_profile(&mut app, profile, &traffic)
+            } else {
+                Err(format_err!("Profiles can't be switched while replaying"))
+            };
+            match switched {
+                Ok(new_login) => {
+                    login = Some(new_login);
+                    // Load the new user's playlists as on startup
+                    is_first_render = true;
+                }
+                Err(e) => app.handle_error(e),
+            }
+        }
+
+        // Delay spotify request until first render, will have the effect of improving
+        // startup speed
+        if is_first_render {
+            if let Some(spotify) = &app.spotify {
+                let playlists = spotify.current_user_playlists(Some(app.large_search_limit), None);
+
+                match playlists {
+                    Ok(p) => {
+                        app.playlists = Some(p);
+                        // Select the first playlist
+                        app.selected_playlist_index = Some(0);
+                    }
+                    Err(e) => {
+                        app.handle_error(e);
+                    }
+                };
+
+                app.get_user();
+            }
+
+            app.get_current_playback();
+            is_first_render = false;
+        }
+    }
+
+    Ok(())
+}
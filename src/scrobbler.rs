@@ -0,0 +1,681 @@
+//! Scrobbling to Last.fm or ListenBrainz, set up with `scrobbler` in client.yml.
+//!
+//! The scrobbler follows the play in progress from `PlayTracker`: it sends "now playing" when a
+//! track starts and scrobbles it once it has been listened to for half its length or four
+//! minutes, whichever comes first. Scrobbles that could not be sent, e.g. while offline, wait in
+//! a queue on disk and are retried every minute, including after a restart. `url` points it at
+//! any server speaking the same API, such as the stand-in in `mock_api`.
+use crate::history::Play;
+use failure::{err_msg, format_err};
+use md5::{Digest, Md5};
+use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+pub const DEFAULT_LASTFM_URL: &str = "https://ws.audioscrobbler.com/2.0/";
+pub const DEFAULT_LISTENBRAINZ_URL: &str = "https://api.listenbrainz.org";
+const LASTFM_AUTH_URL: &str = "https://www.last.fm/api/auth/";
+// Both services ignore shorter tracks
+const MIN_DURATION_MS: u32 = 30_000;
+const MAX_SCROBBLE_POINT_MS: u32 = 4 * 60 * 1000;
+const RETRY_INTERVAL: Duration = Duration::from_secs(60);
+// Requests are made from the UI thread, so give up on a slow server quickly and retry later
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+// Last.fm takes at most 50 scrobbles per request
+const MAX_BATCH_SIZE: usize = 50;
+// Last.fm's "service offline", "temporarily unavailable" and "rate limit exceeded"
+const LASTFM_RETRY_CODES: [u64; 3] = [11, 16, 29];
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScrobbleService {
+    LastFm,
+    ListenBrainz,
+}
+
+impl ScrobbleService {
+    fn name(self) -> &'static str {
+        match self {
+            ScrobbleService::LastFm => "Last.fm",
+            ScrobbleService::ListenBrainz => "ListenBrainz",
+        }
+    }
+}
+
+/// The `scrobbler` section of client.yml
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScrobblerConfig {
+    pub service: ScrobbleService,
+    // Another server with the same API, e.g. a self-hosted ListenBrainz
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    // The ListenBrainz user token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    // A Last.fm API account, and the session saved by `spt lastfm-auth`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_secret: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_key: Option<String>,
+}
+
+impl ScrobblerConfig {
+    fn url(&self) -> &str {
+        self.url.as_deref().unwrap_or(match self.service {
+            ScrobbleService::LastFm => DEFAULT_LASTFM_URL,
+            ScrobbleService::ListenBrainz => DEFAULT_LISTENBRAINZ_URL,
+        })
+    }
+
+    fn lastfm_account(&self) -> Result<(&str, &str), failure::Error> {
+        match (&self.api_key, &self.api_secret) {
+            (Some(api_key), Some(api_secret)) => Ok((api_key, api_secret)),
+            _ => Err(err_msg(
+                "Scrobbling to Last.fm needs the `api_key` and `api_secret` of a Last.fm API account",
+            )),
+        }
+    }
+}
+
+/// A play as it is scrobbled
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Listen {
+    pub track_id: String,
+    pub track_name: String,
+    pub artist_name: String,
+    pub album_name: String,
+    pub duration_ms: u32,
+    // Seconds since the Unix epoch
+    pub started_at: i64,
+}
+
+impl Listen {
+    fn from_play(play: &Play) -> Listen {
+        Listen {
+            track_id: play.track_id.clone(),
+            track_name: play.track_name.clone(),
+            artist_name: play.artist_name.clone(),
+            album_name: play.album_name.clone(),
+            duration_ms: play.duration_ms,
+            started_at: play.started_at,
+        }
+    }
+}
+
+// Whether a request is worth trying again: the server could not be reached or was busy, as
+// opposed to turning the request down
+enum Failure {
+    Retry(failure::Error),
+    Rejected(failure::Error),
+}
+
+impl Failure {
+    fn into_error(self) -> failure::Error {
+        match self {
+            Failure::Retry(e) | Failure::Rejected(e) => e,
+        }
+    }
+}
+
+fn from_status(status: reqwest::StatusCode, body: &str) -> Failure {
+    let error = format_err!("{} {}", status, body);
+    if status.is_server_error() || status.as_u16() == 429 {
+        Failure::Retry(error)
+    } else {
+        Failure::Rejected(error)
+    }
+}
+
+struct ServiceClient {
+    config: ScrobblerConfig,
+    http: Client,
+}
+
+impl ServiceClient {
+    fn new(config: ScrobblerConfig) -> Result<ServiceClient, failure::Error> {
+        let http = Client::builder().timeout(REQUEST_TIMEOUT).build()?;
+        Ok(ServiceClient { config, http })
+    }
+
+    fn now_playing(&self, listen: &Listen) -> Result<(), Failure> {
+        match self.config.service {
+            ScrobbleService::LastFm => {
+                let mut params = vec![
+                    ("artist".to_string(), listen.artist_name.clone()),
+                    ("track".to_string(), listen.track_name.clone()),
+                    ("album".to_string(), listen.album_name.clone()),
+                    (
+                        "duration".to_string(),
+                        (listen.duration_ms / 1000).to_string(),
+                    ),
+                ];
+                self.add_session(&mut params)?;
+                self.lastfm_call("track.updateNowPlaying", params)
+                    .map(|_| ())
+            }
+            ScrobbleService::ListenBrainz => self.submit_listens("playing_now", &[listen]),
+        }
+    }
+
+    fn scrobble(&self, listens: &[Listen]) -> Result<(), Failure> {
+        match self.config.service {
+            ScrobbleService::LastFm => {
+                let mut params = vec![];
+                for (index, listen) in listens.iter().enumerate() {
+                    let field = |name: &str| format!("{}[{}]", name, index);
+                    params.push((field("artist"), listen.artist_name.clone()));
+                    params.push((field("track"), listen.track_name.clone()));
+                    params.push((field("album"), listen.album_name.clone()));
+                    params.push((field("timestamp"), listen.started_at.to_string()));
+                    params.push((field("duration"), (listen.duration_ms / 1000).to_string()));
+                }
+                self.add_session(&mut params)?;
+                self.lastfm_call("track.scrobble", params).map(|_| ())
+            }
+            ScrobbleService::ListenBrainz => {
+                let listen_type = if listens.len() == 1 {
+                    "single"
+                } else {
+                    "import"
+                };
+                self.submit_listens(listen_type, &listens.iter().collect::<Vec<&Listen>>())
+            }
+        }
+    }
+
+    fn add_session(&self, params: &mut Vec<(String, String)>) -> Result<(), Failure> {
+        let session_key = self.config.session_key.clone().ok_or_else(|| {
+            Failure::Rejected(err_msg(
+                "No Last.fm session, run `spt lastfm-auth` to log in to Last.fm",
+            ))
+        })?;
+        params.push(("sk".to_string(), session_key));
+        Ok(())
+    }
+
+    /// Call a Last.fm API method with signed `params`, returning the response
+    fn lastfm_call(
+        &self,
+        method: &str,
+        mut params: Vec<(String, String)>,
+    ) -> Result<Value, Failure> {
+        let (api_key, api_secret) = self.config.lastfm_account().map_err(Failure::Rejected)?;
+        params.push(("method".to_string(), method.to_string()));
+        params.push(("api_key".to_string(), api_key.to_string()));
+        let signature = lastfm_signature(&params, api_secret);
+        params.push(("api_sig".to_string(), signature));
+        params.push(("format".to_string(), "json".to_string()));
+
+        let response = self
+            .http
+            .post(self.config.url())
+            .form(&params)
+            .send()
+            .map_err(|e| Failure::Retry(e.into()))?;
+        let status = response.status();
+        let body = response.text().map_err(|e| Failure::Retry(e.into()))?;
+        let response: Value = serde_json::from_str(&body).unwrap_or(Value::Null);
+
+        // Errors come with a code in the body, sometimes along with a 200
+        if let Some(code) = response["error"].as_u64() {
+            let error = format_err!(
+                "{} (error {})",
+                response["message"].as_str().unwrap_or("Unknown error"),
+                code
+            );
+            return Err(if LASTFM_RETRY_CODES.contains(&code) {
+                Failure::Retry(error)
+            } else {
+                Failure::Rejected(error)
+            });
+        }
+        if !status.is_success() {
+            return Err(from_status(status, &body));
+        }
+        Ok(response)
+    }
+
+    fn submit_listens(&self, listen_type: &str, listens: &[&Listen]) -> Result<(), Failure> {
+        let token = self.config.token.as_deref().ok_or_else(|| {
+            Failure::Rejected(err_msg(
+                "Scrobbling to ListenBrainz needs the `token` from your ListenBrainz profile",
+            ))
+        })?;
+        let payload = listens
+            .iter()
+            .map(|listen| {
+                let mut item = json!({
+                    "track_metadata": {
+                        "artist_name": listen.artist_name,
+                        "track_name": listen.track_name,
+                        "release_name": listen.album_name,
+                        "additional_info": {
+                            "duration_ms": listen.duration_ms,
+                            "spotify_id": format!("https://open.spotify.com/track/{}", listen.track_id),
+                            "submission_client": "spotify-tui",
+                            "submission_client_version": env!("CARGO_PKG_VERSION"),
+                        },
+                    },
+                });
+                // Now playing has no time, it is now
+                if listen_type != "playing_now" {
+                    item["listened_at"] = json!(listen.started_at);
+                }
+                item
+            })
+            .collect::<Vec<Value>>();
+
+        let response = self
+            .http
+            .post(&format!(
+                "{}/1/submit-listens",
+                self.config.url().trim_end_matches('/')
+            ))
+            .header("Authorization", format!("Token {}", token))
+            .json(&json!({ "listen_type": listen_type, "payload": payload }))
+            .send()
+            .map_err(|e| Failure::Retry(e.into()))?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let body = response.text().unwrap_or_default();
+        Err(from_status(status, &body))
+    }
+}
+
+pub struct Scrobbler {
+    client: ServiceClient,
+    queue_path: PathBuf,
+    // Listens that have not been accepted yet, oldest first
+    queue: Vec<Listen>,
+    // The play in progress, by track id and start time, and whether it has been queued
+    current: Option<(String, i64)>,
+    is_current_scrobbled: bool,
+    last_attempt: Option<Instant>,
+    // Rejections are reported once, until a request goes through again
+    has_reported_error: bool,
+}
+
+impl Scrobbler {
+    /// A scrobbler keeping its queue at `queue_path`, with anything left there from before
+    pub fn new(config: ScrobblerConfig, queue_path: PathBuf) -> Result<Scrobbler, failure::Error> {
+        match config.service {
+            ScrobbleService::LastFm => {
+                config.lastfm_account()?;
+            }
+            ScrobbleService::ListenBrainz if config.token.is_none() => {
+                return Err(err_msg(
+                    "Scrobbling to ListenBrainz needs the `token` from your ListenBrainz profile",
+                ));
+            }
+            ScrobbleService::ListenBrainz => {}
+        }
+        let queue = if queue_path.exists() {
+            serde_json::from_str(&fs::read_to_string(&queue_path)?).map_err(|e| {
+                format_err!("Invalid scrobble queue {}: {}", queue_path.display(), e)
+            })?
+        } else {
+            vec![]
+        };
+
+        Ok(Scrobbler {
+            client: ServiceClient::new(config)?,
+            queue_path,
+            queue,
+            current: None,
+            is_current_scrobbled: false,
+            last_attempt: None,
+            has_reported_error: false,
+        })
+    }
+
+    pub fn queued(&self) -> &[Listen] {
+        &self.queue
+    }
+
+    /// Follow the play in progress from `PlayTracker::current`. Errors are only returned for
+    /// requests the service turned down, the first time it happens
+    pub fn update(&mut self, play: Option<&Play>, now: Instant) -> Result<(), failure::Error> {
+        if let Some(play) = play {
+            let key = (play.track_id.clone(), play.started_at);
+            if self.current.as_ref() != Some(&key) {
+                self.current = Some(key);
+                self.is_current_scrobbled = false;
+                // Now playing is only worth sending while it is true, so it is never queued
+                if let Err(Failure::Rejected(e)) = self.client.now_playing(&Listen::from_play(play))
+                {
+                    return self.report(e);
+                }
+            }
+
+            if !self.is_current_scrobbled && is_scrobble_due(play.duration_ms, play.listened_ms) {
+                self.is_current_scrobbled = true;
+                self.queue.push(Listen::from_play(play));
+                // Send it now rather than at the next retry
+                self.last_attempt = None;
+            }
+        }
+        self.flush(now)
+    }
+
+    // Send the queue, unless the last attempt failed less than a minute ago
+    fn flush(&mut self, now: Instant) -> Result<(), failure::Error> {
+        if self.queue.is_empty() {
+            return Ok(());
+        }
+        if let Some(last_attempt) = self.last_attempt {
+            if now.saturating_duration_since(last_attempt) < RETRY_INTERVAL {
+                return Ok(());
+            }
+        }
+        self.last_attempt = Some(now);
+
+        let mut rejection = None;
+        while !self.queue.is_empty() {
+            let batch_size = self.queue.len().min(MAX_BATCH_SIZE);
+            match self.client.scrobble(&self.queue[..batch_size]) {
+                Ok(()) => {
+                    self.queue.drain(..batch_size);
+                    self.has_reported_error = false;
+                }
+                Err(Failure::Retry(_)) => break,
+                Err(Failure::Rejected(e)) => {
+                    rejection = Some(e);
+                    break;
+                }
+            }
+        }
+        if self.queue.is_empty() {
+            // Only once the queue is gone from disk can the next attempt wait
+            self.last_attempt = None;
+        }
+        self.save_queue()?;
+        match rejection {
+            Some(e) => self.report(e),
+            None => Ok(()),
+        }
+    }
+
+    fn save_queue(&self) -> Result<(), failure::Error> {
+        if self.queue.is_empty() {
+            if self.queue_path.exists() {
+                fs::remove_file(&self.queue_path)?;
+            }
+            return Ok(());
+        }
+        fs::write(&self.queue_path, serde_json::to_string_pretty(&self.queue)?)?;
+        Ok(())
+    }
+
+    fn report(&mut self, e: failure::Error) -> Result<(), failure::Error> {
+        if self.has_reported_error {
+            return Ok(());
+        }
+        self.has_reported_error = true;
+        Err(format_err!(
+            "{} turned down a scrobble, they are kept and retried every minute: {}",
+            self.client.config.service.name(),
+            e
+        ))
+    }
+}
+
+fn is_scrobble_due(duration_ms: u32, listened_ms: u32) -> bool {
+    duration_ms > MIN_DURATION_MS && listened_ms >= (duration_ms / 2).min(MAX_SCROBBLE_POINT_MS)
+}
+
+/// Log in to Last.fm in the browser and return the session key to save in client.yml
+pub fn log_in_lastfm(config: Option<&ScrobblerConfig>) -> Result<String, failure::Error> {
+    let config = match config {
+        Some(config) if config.service == ScrobbleService::LastFm => config,
+        _ => {
+            return Err(err_msg(
+                "Add a `scrobbler` section with `service: lastfm`, `api_key` and `api_secret` \
+                 to client.yml first",
+            ))
+        }
+    };
+    let (api_key, _) = config.lastfm_account()?;
+    let client = ServiceClient::new(config.clone())?;
+
+    let token_response = client
+        .lastfm_call("auth.getToken", vec![])
+        .map_err(Failure::into_error)?;
+    let token = token_response["token"]
+        .as_str()
+        .ok_or_else(|| err_msg("Last.fm did not return a token"))?;
+
+    let auth_url = format!("{}?api_key={}&token={}", LASTFM_AUTH_URL, api_key, token);
+    if webbrowser::open(&auth_url).is_err() {
+        println!("Open this URL to allow spt to scrobble:");
+    }
+    println!("\n{}\n", auth_url);
+    print!("Press enter once you have allowed access on Last.fm ");
+    io::stdout().flush()?;
+    io::stdin().read_line(&mut String::new())?;
+
+    let session_response = client
+        .lastfm_call(
+            "auth.getSession",
+            vec![("token".to_string(), token.to_string())],
+        )
+        .map_err(Failure::into_error)?;
+    session_response["session"]["key"]
+        .as_str()
+        .map(String::from)
+        .ok_or_else(|| err_msg("Last.fm did not return a session"))
+}
+
+/// Last.fm's `api_sig`: the MD5 of the parameters sorted by name and joined without separators,
+/// followed by the API secret
+pub(crate) fn lastfm_signature(params: &[(String, String)], api_secret: &str) -> String {
+    let mut sorted = params
+        .iter()
+        .filter(|(name, _)| name != "format" && name != "api_sig")
+        .collect::<Vec<&(String, String)>>();
+    sorted.sort();
+    let mut text = String::new();
+    for (name, value) in sorted {
+        text.push_str(name);
+        text.push_str(value);
+    }
+    text.push_str(api_secret);
+    to_hex(&Md5::digest(text.as_bytes()))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    let mut hex = String::new();
+    for byte in bytes {
+        hex.push_str(&format!("{:02x}", byte));
+    }
+    hex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_api::{
+        MockApi, MOCK_LASTFM_API_KEY, MOCK_LASTFM_API_SECRET, MOCK_LASTFM_SESSION_KEY,
+        MOCK_LISTENBRAINZ_TOKEN,
+    };
+    use std::{env, net::TcpListener, thread};
+
+    fn serve_mock_api() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let mock_api = MockApi::new(None).unwrap();
+        thread::spawn(move || mock_api.serve(listener));
+        url
+    }
+
+    fn scrobbled(url: &str) -> Value {
+        serde_json::from_str(
+            &reqwest::blocking::get(&format!("{}/mock/scrobbles", url))
+                .unwrap()
+                .text()
+                .unwrap(),
+        )
+        .unwrap()
+    }
+
+    fn queue_path(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("spt-scrobbles-{}-{}", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn play(track_id: &str, listened_ms: u32) -> Play {
+        Play {
+            track_id: track_id.to_string(),
+            track_name: format!("Song {}", track_id),
+            artist_id: None,
+            artist_name: "Band".to_string(),
+            album_id: None,
+            album_name: "Album".to_string(),
+            context_uri: None,
+            device_name: "Laptop".to_string(),
+            started_at: 1_600_000_000,
+            duration_ms: 200_000,
+            listened_ms,
+            skipped: false,
+        }
+    }
+
+    fn lastfm_config(url: &str) -> ScrobblerConfig {
+        ScrobblerConfig {
+            service: ScrobbleService::LastFm,
+            url: Some(format!("{}/2.0/", url)),
+            token: None,
+            api_key: Some(MOCK_LASTFM_API_KEY.to_string()),
+            api_secret: Some(MOCK_LASTFM_API_SECRET.to_string()),
+            session_key: Some(MOCK_LASTFM_SESSION_KEY.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_lastfm_signature() {
+        let params = [
+            ("track", "Song"),
+            ("method", "track.scrobble"),
+            ("format", "json"),
+            ("api_key", "key"),
+        ]
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect::<Vec<(String, String)>>();
+        assert_eq!(
+            lastfm_signature(&params, "secret"),
+            "664fbd92a66042b81fc48c4aaba560b2"
+        );
+    }
+
+    #[test]
+    fn test_scrobble_point() {
+        assert!(!is_scrobble_due(200_000, 99_999));
+        assert!(is_scrobble_due(200_000, 100_000));
+        // Four minutes in for long tracks
+        assert!(is_scrobble_due(3_600_000, 240_000));
+        assert!(!is_scrobble_due(20_000, 20_000));
+    }
+
+    #[test]
+    fn test_lastfm() {
+        let url = serve_mock_api();
+        let mut scrobbler = Scrobbler::new(lastfm_config(&url), queue_path("lastfm")).unwrap();
+        let now = Instant::now();
+        scrobbler.update(Some(&play("1", 0)), now).unwrap();
+        assert_eq!(scrobbled(&url)["now_playing"]["track"], "Song 1");
+        assert!(scrobbled(&url)["scrobbles"].as_array().unwrap().is_empty());
+
+        scrobbler.update(Some(&play("1", 100_000)), now).unwrap();
+        // Only once per play
+        scrobbler.update(Some(&play("1", 150_000)), now).unwrap();
+        let scrobbles = scrobbled(&url)["scrobbles"].clone();
+        assert_eq!(
+            scrobbles,
+            json!([{
+                "artist": "Band",
+                "track": "Song 1",
+                "album": "Album",
+                "timestamp": 1_600_000_000,
+            }])
+        );
+        assert!(scrobbler.queued().is_empty());
+    }
+
+    #[test]
+    fn test_listenbrainz() {
+        let url = serve_mock_api();
+        let config = ScrobblerConfig {
+            service: ScrobbleService::ListenBrainz,
+            url: Some(url.clone()),
+            token: Some(MOCK_LISTENBRAINZ_TOKEN.to_string()),
+            api_key: None,
+            api_secret: None,
+            session_key: None,
+        };
+        let mut scrobbler = Scrobbler::new(config.clone(), queue_path("listenbrainz")).unwrap();
+        scrobbler
+            .update(Some(&play("1", 120_000)), Instant::now())
+            .unwrap();
+        assert_eq!(scrobbled(&url)["now_playing"]["track"], "Song 1");
+        assert_eq!(scrobbled(&url)["scrobbles"][0]["track"], "Song 1");
+
+        // A wrong token is reported once and the scrobble is kept
+        let path = queue_path("listenbrainz-rejected");
+        let mut scrobbler = Scrobbler::new(
+            ScrobblerConfig {
+                token: Some("wrong".to_string()),
+                ..config
+            },
+            path.clone(),
+        )
+        .unwrap();
+        assert!(scrobbler
+            .update(Some(&play("2", 0)), Instant::now())
+            .is_err());
+        assert!(scrobbler
+            .update(Some(&play("2", 120_000)), Instant::now())
+            .is_ok());
+        assert_eq!(scrobbler.queued().len(), 1);
+        assert!(path.exists());
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_offline_queue() {
+        let path = queue_path("offline");
+        // Nothing listens on the discard port
+        let offline = lastfm_config("http://127.0.0.1:9");
+        let mut scrobbler = Scrobbler::new(offline, path.clone()).unwrap();
+        let start = Instant::now();
+        scrobbler.update(Some(&play("1", 100_000)), start).unwrap();
+        scrobbler.update(Some(&play("2", 100_000)), start).unwrap();
+        assert_eq!(scrobbler.queued().len(), 2);
+
+        // The queue survives a restart and is sent once the server is back
+        let url = serve_mock_api();
+        let mut scrobbler = Scrobbler::new(lastfm_config(&url), path.clone()).unwrap();
+        assert_eq!(scrobbler.queued().len(), 2);
+        scrobbler.update(None, start).unwrap();
+        assert_eq!(scrobbled(&url)["scrobbles"].as_array().unwrap().len(), 2);
+        assert!(scrobbler.queued().is_empty());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_missing_credentials() {
+        let mut config = lastfm_config("http://127.0.0.1:9");
+        config.api_secret = None;
+        assert!(Scrobbler::new(config, queue_path("missing")).is_err());
+    }
+}
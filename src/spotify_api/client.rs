@@ -0,0 +1,781 @@
+//! The real `SpotifyApi`, talking to the Web API over HTTP. rspotify's client always calls
+//! api.spotify.com, so the requests are built here (matching rspotify's) and only its models
+//! are reused, which lets `api_url` in client.yml point the app at another server.
+use super::{
+    traffic::{Exchange, Traffic},
+    SpotifyApi,
+};
+use failure::format_err;
+use reqwest::{blocking::Client, Method, Url};
+use rspotify::spotify::{
+    client::ApiError,
+    model::{
+        album::{FullAlbum, PageSimpliedAlbums, SavedAlbum, SimplifiedAlbum},
+        artist::{CursorPageFullArtists, FullArtist, FullArtists},
+        audio::{AudioAnalysis, AudioFeatures},
+        category::PageCategory,
+        context::FullPlayingContext,
+        cud_result::CUDResult,
+        device::DevicePayload,
+        offset::Offset,
+        page::{CursorBasedPage, Page},
+        playing::PlayHistory,
+        playlist::{FeaturedPlaylists, FullPlaylist, PlaylistTrack, SimplifiedPlaylist},
+        recommend::Recommendations,
+        search::{SearchAlbums, SearchArtists, SearchPlaylists, SearchTracks},
+        track::{FullTrack, FullTracks, SavedTrack, SimplifiedTrack},
+        user::PrivateUser,
+    },
+    senum::{AlbumType, Country, RepeatState, TimeRange},
+};
+use serde::de::DeserializeOwned;
+use serde_json::{json, map::Map, Value};
+use std::time::Duration;
+
+pub const DEFAULT_API_URL: &str = "https://api.spotify.com/v1";
+// Requests run on the UI tick, so one that stalls must not hang the app
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// The tunable attributes rspotify forwards from the recommendations payload
+const RECOMMENDATION_ATTRIBUTES: [&str; 14] = [
+    "acousticness",
+    "danceability",
+    "duration_ms",
+    "energy",
+    "instrumentalness",
+    "key",
+    "liveness",
+    "loudness",
+    "mode",
+    "popularity",
+    "speechiness",
+    "tempo",
+    "time_signature",
+    "valence",
+];
+
+type Query<'a> = Vec<(&'a str, String)>;
+
+/// Talks to `api_url` with a bearer token, see `traffic` for recording and replaying
+pub struct WebApiClient {
+    api_url: String,
+    access_token: String,
+    http: Client,
+    traffic: Traffic,
+}
+
+impl WebApiClient {
+    pub fn new(api_url: &str, access_token: &str) -> WebApiClient {
+        WebApiClient {
+            api_url: api_url.trim_end_matches('/').to_string(),
+            access_token: access_token.to_string(),
+            // Like `Client::new`, this only panics when the TLS backend can't be set up
+            http: Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("Failed to set up the HTTP client"),
+            traffic: Traffic::Live,
+        }
+    }
+
+    /// Record the requests, or answer them from a recording, see `traffic`
+    pub fn with_traffic(mut self, traffic: Traffic) -> WebApiClient {
+        self.traffic = traffic;
+        self
+    }
+
+    /// Every request goes through here. `path` is relative to the API root and may already
+    /// have a query string, which `query` is appended to
+    fn send(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, String)],
+        body: Option<&Value>,
+    ) -> Result<String, failure::Error> {
+        let mut url = Url::parse(&format!("{}/{}", self.api_url, path))?;
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+        let relative_path = match url.query() {
+            Some(query) => format!("{}?{}", path.split('?').next().unwrap_or(path), query),
+            None => path.to_string(),
+        };
+
+        let recorder = match &self.traffic {
+            Traffic::Live => None,
+            Traffic::Record(recorder) => Some(recorder),
+            Traffic::Replay(replayer) => return replayer.respond(method.as_str(), &relative_path),
+        };
+
+        let request = self
+            .http
+            .request(method.clone(), url)
+            .bearer_auth(&self.access_token);
+        // Spotify rejects a GET with a body, and a POST or PUT without a content length
+        let request = match body {
+            Some(body) => request
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body.to_string()),
+            None if method == Method::GET => request,
+            None => request.body(""),
+        };
+
+        let response = request.send()?;
+        let error = if response.status().is_success() {
+            None
+        } else {
+            Some(ApiError::from(&response))
+        };
+        let status = response.status().as_u16();
+        let text = response.text()?;
+
+        if let Some(recorder) = recorder {
+            let exchange = Exchange {
+                method: method.to_string(),
+                path: relative_path,
+                body: body.cloned(),
+                status,
+                response: text.clone(),
+            };
+            recorder.save(&exchange, &self.access_token)?;
+        }
+
+        match error {
+            None => Ok(text),
+            Some(error) => Err(error.into()),
+        }
+    }
+
+    fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<T, failure::Error> {
+        let text = self.send(Method::GET, path, query, None)?;
+        serde_json::from_str(&text)
+            .map_err(|e| format_err!("Unexpected response from {}: {}", path, e))
+    }
+
+    fn put(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+        body: &Value,
+    ) -> Result<(), failure::Error> {
+        self.send(Method::PUT, path, query, Some(body)).map(|_| ())
+    }
+
+    fn post(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+        body: &Value,
+    ) -> Result<String, failure::Error> {
+        self.send(Method::POST, path, query, Some(body))
+    }
+
+    fn delete(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+        body: &Value,
+    ) -> Result<String, failure::Error> {
+        self.send(Method::DELETE, path, query, Some(body))
+    }
+
+    fn search<T: DeserializeOwned>(
+        &self,
+        q: &str,
+        search_type: &str,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        market: Option<Country>,
+    ) -> Result<T, failure::Error> {
+        let mut query = vec![
+            ("q", q.to_string()),
+            ("type", search_type.to_string()),
+            ("limit", limit.unwrap_or(10).to_string()),
+            ("offset", offset.unwrap_or(0).to_string()),
+        ];
+        push_country(&mut query, "market", market);
+        self.get("search", &query)
+    }
+}
+
+/// Strip `spotify:track:` (or the like) from a URI, ids are passed through
+// Like rspotify's `get_id`, accepts `spotify:track:<id>` URIs and
+// `https://open.spotify.com/track/<id>` links (with or without a `?si=` share query) as well as ids
+fn to_id(id: &str) -> &str {
+    let id = id.split(['?', '#']).next().unwrap_or(id);
+    id.rsplit([':', '/']).next().unwrap_or(id)
+}
+
+fn to_ids(ids: &[String]) -> String {
+    ids.iter().map(|id| to_id(id)).collect::<Vec<_>>().join(",")
+}
+
+fn device_query(device_id: Option<String>) -> Query<'static> {
+    device_id.map(|id| ("device_id", id)).into_iter().collect()
+}
+
+fn push_country(query: &mut Query, key: &'static str, country: Option<Country>) {
+    if let Some(country) = country {
+        query.push((key, country.as_str().to_string()));
+    }
+}
+
+fn page_query(limit: u32, offset: Option<u32>) -> Query<'static> {
+    vec![
+        ("limit", limit.to_string()),
+        ("offset", offset.unwrap_or(0).to_string()),
+    ]
+}
+
+impl SpotifyApi for WebApiClient {
+    fn current_user(&self) -> Result<PrivateUser, failure::Error> {
+        self.get("me", &[])
+    }
+
+    fn device(&self) -> Result<DevicePayload, failure::Error> {
+        self.get("me/player/devices", &[])
+    }
+
+    fn current_playback(
+        &self,
+        market: Option<Country>,
+    ) -> Result<Option<FullPlayingContext>, failure::Error> {
+        let mut query = vec![];
+        push_country(&mut query, "country", market);
+        // Nothing is playing when the body is empty
+        let text = self.send(Method::GET, "me/player", &query, None)?;
+        if text.is_empty() {
+            Ok(None)
+        } else {
+            Ok(serde_json::from_str(&text)?)
+        }
+    }
+
+    fn start_playback(
+        &self,
+        device_id: Option<String>,
+        context_uri: Option<String>,
+        uris: Option<Vec<String>>,
+        offset: Option<Offset>,
+        position_ms: Option<u32>,
+    ) -> Result<(), failure::Error> {
+        let mut body = Map::new();
+        if let Some(context_uri) = context_uri {
+            body.insert("context_uri".to_string(), context_uri.into());
+        }
+        if let Some(uris) = uris {
+            body.insert("uris".to_string(), uris.into());
+        }
+        if let Some(offset) = offset {
+            if let Some(position) = offset.position {
+                body.insert("offset".to_string(), json!({ "position": position }));
+            } else if let Some(uri) = offset.uri {
+                body.insert("offset".to_string(), json!({ "uri": uri }));
+            }
+        }
+        if let Some(position_ms) = position_ms {
+            body.insert("position_ms".to_string(), position_ms.into());
+        }
+        self.put(
+            "me/player/play",
+            &device_query(device_id),
+            &Value::Object(body),
+        )
+    }
+
+    fn pause_playback(&self, device_id: Option<String>) -> Result<(), failure::Error> {
+        self.put("me/player/pause", &device_query(device_id), &json!({}))
+    }
+
+    fn next_track(&self, device_id: Option<String>) -> Result<(), failure::Error> {
+        self.post("me/player/next", &device_query(device_id), &json!({}))
+            .map(|_| ())
+    }
+
+    fn previous_track(&self, device_id: Option<String>) -> Result<(), failure::Error> {
+        self.post("me/player/previous", &device_query(device_id), &json!({}))
+            .map(|_| ())
+    }
+
+    fn seek_track(
+        &self,
+        position_ms: u32,
+        device_id: Option<String>,
+    ) -> Result<(), failure::Error> {
+        let mut query = vec![("position_ms", position_ms.to_string())];
+        query.extend(device_query(device_id));
+        self.put("me/player/seek", &query, &json!({}))
+    }
+
+    fn shuffle(&self, state: bool, device_id: Option<String>) -> Result<(), failure::Error> {
+        let mut query = vec![("state", state.to_string())];
+        query.extend(device_query(device_id));
+        self.put("me/player/shuffle", &query, &json!({}))
+    }
+
+    fn repeat(&self, state: RepeatState, device_id: Option<String>) -> Result<(), failure::Error> {
+        let mut query = vec![("state", state.as_str().to_string())];
+        query.extend(device_query(device_id));
+        self.put("me/player/repeat", &query, &json!({}))
+    }
+
+    fn volume(&self, volume_percent: u8, device_id: Option<String>) -> Result<(), failure::Error> {
+        let mut query = vec![("volume_percent", volume_percent.min(100).to_string())];
+        query.extend(device_query(device_id));
+        self.put("me/player/volume", &query, &json!({}))
+    }
+
+    fn add_item_to_queue(
+        &self,
+        uri: &str,
+        device_id: Option<String>,
+    ) -> Result<(), failure::Error> {
+        let mut query = vec![("uri", uri.to_string())];
+        query.extend(device_query(device_id));
+        self.post("me/player/queue", &query, &json!({})).map(|_| ())
+    }
+
+    fn track(&self, track_id: &str) -> Result<FullTrack, failure::Error> {
+        self.get(&format!("tracks/{}", to_id(track_id)), &[])
+    }
+
+    fn tracks(
+        &self,
+        track_ids: Vec<&str>,
+        market: Option<Country>,
+    ) -> Result<FullTracks, failure::Error> {
+        let ids: Vec<&str> = track_ids.into_iter().map(to_id).collect();
+        let mut query = vec![("ids", ids.join(","))];
+        push_country(&mut query, "market", market);
+        self.get("tracks", &query)
+    }
+
+    fn album(&self, album_id: &str) -> Result<FullAlbum, failure::Error> {
+        self.get(&format!("albums/{}", to_id(album_id)), &[])
+    }
+
+    fn album_track(
+        &self,
+        album_id: &str,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<Page<SimplifiedTrack>, failure::Error> {
+        self.get(
+            &format!("albums/{}/tracks", to_id(album_id)),
+            &page_query(limit.unwrap_or(50), offset),
+        )
+    }
+
+    fn artist(&self, artist_id: &str) -> Result<FullArtist, failure::Error> {
+        self.get(&format!("artists/{}", to_id(artist_id)), &[])
+    }
+
+    fn artist_albums(
+        &self,
+        artist_id: &str,
+        album_type: Option<AlbumType>,
+        country: Option<Country>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<Page<SimplifiedAlbum>, failure::Error> {
+        let mut query = vec![];
+        if let Some(limit) = limit {
+            query.push(("limit", limit.to_string()));
+        }
+        if let Some(album_type) = album_type {
+            query.push(("album_type", album_type.as_str().to_string()));
+        }
+        if let Some(offset) = offset {
+            query.push(("offset", offset.to_string()));
+        }
+        push_country(&mut query, "country", country);
+        self.get(&format!("artists/{}/albums", to_id(artist_id)), &query)
+    }
+
+    fn artist_top_tracks(
+        &self,
+        artist_id: &str,
+        country: Option<Country>,
+    ) -> Result<FullTracks, failure::Error> {
+        let country = country.unwrap_or(Country::UnitedStates);
+        self.get(
+            &format!("artists/{}/top-tracks", to_id(artist_id)),
+            &[("country", country.as_str().to_string())],
+        )
+    }
+
+    fn artist_related_artists(&self, artist_id: &str) -> Result<FullArtists, failure::Error> {
+        self.get(
+            &format!("artists/{}/related-artists", to_id(artist_id)),
+            &[],
+        )
+    }
+
+    fn audio_analysis(&self, track: &str) -> Result<AudioAnalysis, failure::Error> {
+        self.get(&format!("audio-analysis/{}", to_id(track)), &[])
+    }
+
+    fn audio_features(&self, track: &str) -> Result<AudioFeatures, failure::Error> {
+        self.get(&format!("audio-features/{}", to_id(track)), &[])
+    }
+
+    fn search_track(
+        &self,
+        q: &str,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        market: Option<Country>,
+    ) -> Result<SearchTracks, failure::Error> {
+        self.search(q, "track", limit, offset, market)
+    }
+
+    fn search_artist(
+        &self,
+        q: &str,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        market: Option<Country>,
+    ) -> Result<SearchArtists, failure::Error> {
+        self.search(q, "artist", limit, offset, market)
+    }
+
+    fn search_album(
+        &self,
+        q: &str,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        market: Option<Country>,
+    ) -> Result<SearchAlbums, failure::Error> {
+        self.search(q, "album", limit, offset, market)
+    }
+
+    fn search_playlist(
+        &self,
+        q: &str,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        market: Option<Country>,
+    ) -> Result<SearchPlaylists, failure::Error> {
+        self.search(q, "playlist", limit, offset, market)
+    }
+
+    fn recommendations(
+        &self,
+        seed_artists: Option<Vec<String>>,
+        seed_genres: Option<Vec<String>>,
+        seed_tracks: Option<Vec<String>>,
+        limit: Option<u32>,
+        country: Option<Country>,
+        payload: &Map<String, Value>,
+    ) -> Result<Recommendations, failure::Error> {
+        let mut tunables = vec![];
+        for attribute in RECOMMENDATION_ATTRIBUTES.iter() {
+            for prefix in ["min_", "max_", "target_"].iter() {
+                let key = format!("{}{}", prefix, attribute);
+                if let Some(value) = payload.get(&key) {
+                    tunables.push((key, value.to_string()));
+                }
+            }
+        }
+
+        let mut query = vec![("limit", limit.unwrap_or(20).to_string())];
+        if let Some(seed_artists) = seed_artists {
+            query.push(("seed_artists", to_ids(&seed_artists)));
+        }
+        if let Some(seed_genres) = seed_genres {
+            query.push(("seed_genres", seed_genres.join(",")));
+        }
+        if let Some(seed_tracks) = seed_tracks {
+            query.push(("seed_tracks", to_ids(&seed_tracks)));
+        }
+        push_country(&mut query, "market", country);
+        query.extend(
+            tunables
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.clone())),
+        );
+
+        self.get("recommendations", &query)
+    }
+
+    fn current_user_playlists(
+        &self,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<Page<SimplifiedPlaylist>, failure::Error> {
+        self.get("me/playlists", &page_query(limit.unwrap_or(50), offset))
+    }
+
+    fn user_playlist_create(
+        &self,
+        user_id: &str,
+        name: &str,
+        public: Option<bool>,
+        description: Option<String>,
+    ) -> Result<FullPlaylist, failure::Error> {
+        let body = json!({
+            "name": name,
+            "public": public.unwrap_or(true),
+            "description": description.unwrap_or_default(),
+        });
+        let text = self.post(&format!("users/{}/playlists", user_id), &[], &body)?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    fn user_playlist_tracks(
+        &self,
+        user_id: &str,
+        playlist_id: &str,
+        fields: Option<&str>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        market: Option<Country>,
+    ) -> Result<Page<PlaylistTrack>, failure::Error> {
+        let mut query = page_query(limit.unwrap_or(50), offset);
+        push_country(&mut query, "market", market);
+        if let Some(fields) = fields {
+            query.push(("fields", fields.to_string()));
+        }
+        self.get(
+            &format!("users/{}/playlists/{}/tracks", user_id, to_id(playlist_id)),
+            &query,
+        )
+    }
+
+    fn user_playlist_add_tracks(
+        &self,
+        user_id: &str,
+        playlist_id: &str,
+        track_ids: &[String],
+        position: Option<i32>,
+    ) -> Result<CUDResult, failure::Error> {
+        let uris: Vec<String> = track_ids
+            .iter()
+            .map(|id| format!("spotify:track:{}", to_id(id)))
+            .collect();
+        let mut body = Map::new();
+        if let Some(position) = position {
+            body.insert("position".to_string(), position.into());
+        }
+        body.insert("uris".to_string(), uris.into());
+        let text = self.post(
+            &format!("users/{}/playlists/{}/tracks", user_id, to_id(playlist_id)),
+            &[],
+            &Value::Object(body),
+        )?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    fn user_playlist_follow_playlist(
+        &self,
+        playlist_owner_id: &str,
+        playlist_id: &str,
+        public: Option<bool>,
+    ) -> Result<(), failure::Error> {
+        self.put(
+            &format!(
+                "users/{}/playlists/{}/followers",
+                playlist_owner_id, playlist_id
+            ),
+            &[],
+            &json!({ "public": public.unwrap_or(true) }),
+        )
+    }
+
+    fn user_playlist_unfollow(
+        &self,
+        user_id: &str,
+        playlist_id: &str,
+    ) -> Result<String, failure::Error> {
+        self.delete(
+            &format!("users/{}/playlists/{}/followers", user_id, playlist_id),
+            &[],
+            &json!({}),
+        )
+    }
+
+    fn current_user_saved_tracks(
+        &self,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<Page<SavedTrack>, failure::Error> {
+        self.get("me/tracks", &page_query(limit.unwrap_or(20), offset))
+    }
+
+    fn current_user_saved_tracks_contains(
+        &self,
+        track_ids: &[String],
+    ) -> Result<Vec<bool>, failure::Error> {
+        self.get("me/tracks/contains", &[("ids", to_ids(track_ids))])
+    }
+
+    fn current_user_saved_tracks_add(&self, track_ids: &[String]) -> Result<(), failure::Error> {
+        self.put("me/tracks", &[("ids", to_ids(track_ids))], &json!({}))
+    }
+
+    fn current_user_saved_tracks_delete(&self, track_ids: &[String]) -> Result<(), failure::Error> {
+        self.delete("me/tracks", &[("ids", to_ids(track_ids))], &json!({}))
+            .map(|_| ())
+    }
+
+    fn current_user_saved_albums(
+        &self,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<Page<SavedAlbum>, failure::Error> {
+        self.get("me/albums", &page_query(limit.unwrap_or(20), offset))
+    }
+
+    fn current_user_saved_albums_add(&self, album_ids: &[String]) -> Result<(), failure::Error> {
+        self.put("me/albums", &[("ids", to_ids(album_ids))], &json!({}))
+    }
+
+    fn current_user_saved_albums_delete(&self, album_ids: &[String]) -> Result<(), failure::Error> {
+        self.delete("me/albums", &[("ids", to_ids(album_ids))], &json!({}))
+            .map(|_| ())
+    }
+
+    fn current_user_followed_artists(
+        &self,
+        limit: Option<u32>,
+        after: Option<String>,
+    ) -> Result<CursorPageFullArtists, failure::Error> {
+        let mut query = vec![
+            ("type", "artist".to_string()),
+            ("limit", limit.unwrap_or(20).to_string()),
+        ];
+        if let Some(after) = after {
+            query.push(("after", after));
+        }
+        self.get("me/following", &query)
+    }
+
+    fn user_follow_artists(&self, artist_ids: &[String]) -> Result<(), failure::Error> {
+        let query = [
+            ("type", "artist".to_string()),
+            ("ids", artist_ids.join(",")),
+        ];
+        self.put("me/following", &query, &json!({}))
+    }
+
+    fn user_unfollow_artists(&self, artist_ids: &[String]) -> Result<(), failure::Error> {
+        let query = [
+            ("type", "artist".to_string()),
+            ("ids", artist_ids.join(",")),
+        ];
+        self.delete("me/following", &query, &json!({})).map(|_| ())
+    }
+
+    fn current_user_recently_played(
+        &self,
+        limit: Option<u32>,
+    ) -> Result<CursorBasedPage<PlayHistory>, failure::Error> {
+        self.get(
+            "me/player/recently-played",
+            &[("limit", limit.unwrap_or(50).to_string())],
+        )
+    }
+
+    fn current_user_top_artists(
+        &self,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        time_range: Option<TimeRange>,
+    ) -> Result<Page<FullArtist>, failure::Error> {
+        let mut query = page_query(limit.unwrap_or(20), offset);
+        let time_range = time_range.unwrap_or(TimeRange::MediumTerm);
+        query.push(("time_range", time_range.as_str().to_string()));
+        self.get("me/top/artists", &query)
+    }
+
+    fn current_user_top_tracks(
+        &self,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        time_range: Option<TimeRange>,
+    ) -> Result<Page<FullTrack>, failure::Error> {
+        let mut query = page_query(limit.unwrap_or(20), offset);
+        let time_range = time_range.unwrap_or(TimeRange::MediumTerm);
+        query.push(("time_range", time_range.as_str().to_string()));
+        self.get("me/top/tracks", &query)
+    }
+
+    fn categories(
+        &self,
+        locale: Option<String>,
+        country: Option<Country>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<PageCategory, failure::Error> {
+        let mut query = page_query(limit.unwrap_or(20), offset);
+        if let Some(locale) = locale {
+            query.push(("locale", locale));
+        }
+        push_country(&mut query, "country", country);
+        self.get("browse/categories", &query)
+    }
+
+    fn new_releases(
+        &self,
+        country: Option<Country>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<PageSimpliedAlbums, failure::Error> {
+        let mut query = page_query(limit.unwrap_or(20), offset);
+        push_country(&mut query, "country", country);
+        self.get("browse/new-releases", &query)
+    }
+
+    fn featured_playlists(
+        &self,
+        locale: Option<String>,
+        country: Option<Country>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<FeaturedPlaylists, failure::Error> {
+        let mut query = page_query(limit.unwrap_or(20), offset);
+        if let Some(locale) = locale {
+            query.push(("locale", locale));
+        }
+        push_country(&mut query, "country", country);
+        self.get("browse/featured-playlists", &query)
+    }
+
+    fn web_api_call(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&Value>,
+    ) -> Result<String, failure::Error> {
+        self.send(method, path, &[], body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_id() {
+        assert_eq!(to_id("spotify:track:abc"), "abc");
+        assert_eq!(to_id("abc"), "abc");
+    }
+
+    #[test]
+    fn test_to_id_from_url() {
+        assert_eq!(to_id("https://open.spotify.com/track/abc"), "abc");
+        assert_eq!(
+            to_id("https://open.spotify.com/album/abc?si=xyz&utm_source=copy-link"),
+            "abc"
+        );
+        assert_eq!(
+            to_ids(&["spotify:album:a".to_string(), "b".to_string()]),
+            "a,b"
+        );
+    }
+}
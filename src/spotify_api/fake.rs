@@ -0,0 +1,763 @@
+//! A scriptable stand-in for the Spotify client. Responses are JSON fixtures keyed by method
+//! name, or by `METHOD path` for `web_api_call`, and every call is recorded so tests can check
+//! what the app asked for.
+use super::SpotifyApi;
+use crate::app::App;
+use failure::{err_msg, format_err};
+use reqwest::Method;
+use rspotify::spotify::{
+    model::{
+        album::{FullAlbum, PageSimpliedAlbums, SavedAlbum, SimplifiedAlbum},
+        artist::{CursorPageFullArtists, FullArtist, FullArtists},
+        audio::{AudioAnalysis, AudioFeatures},
+        category::PageCategory,
+        context::FullPlayingContext,
+        cud_result::CUDResult,
+        device::DevicePayload,
+        offset::Offset,
+        page::{CursorBasedPage, Page},
+        playing::PlayHistory,
+        playlist::{FeaturedPlaylists, FullPlaylist, PlaylistTrack, SimplifiedPlaylist},
+        recommend::Recommendations,
+        search::{SearchAlbums, SearchArtists, SearchPlaylists, SearchTracks},
+        track::{FullTrack, FullTracks, SavedTrack, SimplifiedTrack},
+        user::PrivateUser,
+    },
+    senum::{AlbumType, Country, RepeatState, TimeRange},
+};
+use serde::de::DeserializeOwned;
+use serde_json::{json, map::Map, Value};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+// Formats the arguments of a call the way they are recorded, e.g. `start_playback(None, ..)`
+macro_rules! args {
+    ($($arg:expr),*) => {{
+        let args: &[String] = &[$(format!("{:?}", $arg)),*];
+        args.join(", ")
+    }};
+}
+
+#[derive(Default)]
+pub struct FakeSpotify {
+    responses: RefCell<HashMap<String, Value>>,
+    errors: RefCell<HashMap<String, String>>,
+    calls: RefCell<Vec<String>>,
+}
+
+impl FakeSpotify {
+    pub fn new() -> FakeSpotify {
+        FakeSpotify::default()
+    }
+
+    /// Serve `response` every time `method` is called
+    pub fn respond(&self, method: &str, response: Value) -> &Self {
+        self.responses
+            .borrow_mut()
+            .insert(method.to_string(), response);
+        self
+    }
+
+    /// Make every call to `method` fail with `message`
+    pub fn fail(&self, method: &str, message: &str) -> &Self {
+        self.errors
+            .borrow_mut()
+            .insert(method.to_string(), message.to_string());
+        self
+    }
+
+    /// All calls so far, formatted as `method(args)`
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.borrow().clone()
+    }
+
+    /// The calls to `method`, with only their arguments
+    pub fn calls_to(&self, method: &str) -> Vec<String> {
+        let prefix = format!("{}(", method);
+        self.calls
+            .borrow()
+            .iter()
+            .filter(|call| call.starts_with(&prefix))
+            .map(|call| call[prefix.len()..call.len() - 1].to_string())
+            .collect()
+    }
+
+    fn record(&self, method: &str, args: String) -> Result<(), failure::Error> {
+        self.calls
+            .borrow_mut()
+            .push(format!("{}({})", method, args));
+        match self.errors.borrow().get(method) {
+            Some(message) => Err(err_msg(message.clone())),
+            None => Ok(()),
+        }
+    }
+
+    // A request that returns data, which needs a fixture
+    fn call<T: DeserializeOwned>(&self, method: &str, args: String) -> Result<T, failure::Error> {
+        self.record(method, args)?;
+        match self.responses.borrow().get(method) {
+            Some(response) => Ok(serde_json::from_value(response.clone())?),
+            None => Err(format_err!("No fixture for {}", method)),
+        }
+    }
+
+    // A request that only changes something, which succeeds unless told to fail
+    fn command(&self, method: &str, args: String) -> Result<(), failure::Error> {
+        self.record(method, args)
+    }
+}
+
+impl SpotifyApi for FakeSpotify {
+    fn current_user(&self) -> Result<PrivateUser, failure::Error> {
+        self.call("current_user", args!())
+    }
+
+    fn device(&self) -> Result<DevicePayload, failure::Error> {
+        self.call("device", args!())
+    }
+
+    fn current_playback(
+        &self,
+        market: Option<Country>,
+    ) -> Result<Option<FullPlayingContext>, failure::Error> {
+        self.call("current_playback", args!(market))
+    }
+
+    fn start_playback(
+        &self,
+        device_id: Option<String>,
+        context_uri: Option<String>,
+        uris: Option<Vec<String>>,
+        offset: Option<Offset>,
+        position_ms: Option<u32>,
+    ) -> Result<(), failure::Error> {
+        self.command(
+            "start_playback",
+            args!(device_id, context_uri, uris, offset, position_ms),
+        )
+    }
+
+    fn pause_playback(&self, device_id: Option<String>) -> Result<(), failure::Error> {
+        self.command("pause_playback", args!(device_id))
+    }
+
+    fn next_track(&self, device_id: Option<String>) -> Result<(), failure::Error> {
+        self.command("next_track", args!(device_id))
+    }
+
+    fn previous_track(&self, device_id: Option<String>) -> Result<(), failure::Error> {
+        self.command("previous_track", args!(device_id))
+    }
+
+    fn seek_track(
+        &self,
+        position_ms: u32,
+        device_id: Option<String>,
+    ) -> Result<(), failure::Error> {
+        self.command("seek_track", args!(position_ms, device_id))
+    }
+
+    fn shuffle(&self, state: bool, device_id: Option<String>) -> Result<(), failure::Error> {
+        self.command("shuffle", args!(state, device_id))
+    }
+
+    fn repeat(&self, state: RepeatState, device_id: Option<String>) -> Result<(), failure::Error> {
+        self.command("repeat", args!(state, device_id))
+    }
+
+    fn volume(&self, volume_percent: u8, device_id: Option<String>) -> Result<(), failure::Error> {
+        self.command("volume", args!(volume_percent, device_id))
+    }
+
+    fn add_item_to_queue(
+        &self,
+        uri: &str,
+        device_id: Option<String>,
+    ) -> Result<(), failure::Error> {
+        self.command("add_item_to_queue", args!(uri, device_id))
+    }
+
+    fn track(&self, track_id: &str) -> Result<FullTrack, failure::Error> {
+        self.call("track", args!(track_id))
+    }
+
+    fn tracks(
+        &self,
+        track_ids: Vec<&str>,
+        market: Option<Country>,
+    ) -> Result<FullTracks, failure::Error> {
+        self.call("tracks", args!(track_ids, market))
+    }
+
+    fn album(&self, album_id: &str) -> Result<FullAlbum, failure::Error> {
+        self.call("album", args!(album_id))
+    }
+
+    fn album_track(
+        &self,
+        album_id: &str,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<Page<SimplifiedTrack>, failure::Error> {
+        self.call("album_track", args!(album_id, limit, offset))
+    }
+
+    fn artist(&self, artist_id: &str) -> Result<FullArtist, failure::Error> {
+        self.call("artist", args!(artist_id))
+    }
+
+    fn artist_albums(
+        &self,
+        artist_id: &str,
+        album_type: Option<AlbumType>,
+        country: Option<Country>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<Page<SimplifiedAlbum>, failure::Error> {
+        self.call(
+            "artist_albums",
+            args!(artist_id, album_type, country, limit, offset),
+        )
+    }
+
+    fn artist_top_tracks(
+        &self,
+        artist_id: &str,
+        country: Option<Country>,
+    ) -> Result<FullTracks, failure::Error> {
+        self.call("artist_top_tracks", args!(artist_id, country))
+    }
+
+    fn artist_related_artists(&self, artist_id: &str) -> Result<FullArtists, failure::Error> {
+        self.call("artist_related_artists", args!(artist_id))
+    }
+
+    fn audio_analysis(&self, track: &str) -> Result<AudioAnalysis, failure::Error> {
+        self.call("audio_analysis", args!(track))
+    }
+
+    fn audio_features(&self, track: &str) -> Result<AudioFeatures, failure::Error> {
+        self.call("audio_features", args!(track))
+    }
+
+    fn search_track(
+        &self,
+        q: &str,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        market: Option<Country>,
+    ) -> Result<SearchTracks, failure::Error> {
+        self.call("search_track", args!(q, limit, offset, market))
+    }
+
+    fn search_artist(
+        &self,
+        q: &str,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        market: Option<Country>,
+    ) -> Result<SearchArtists, failure::Error> {
+        self.call("search_artist", args!(q, limit, offset, market))
+    }
+
+    fn search_album(
+        &self,
+        q: &str,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        market: Option<Country>,
+    ) -> Result<SearchAlbums, failure::Error> {
+        self.call("search_album", args!(q, limit, offset, market))
+    }
+
+    fn search_playlist(
+        &self,
+        q: &str,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        market: Option<Country>,
+    ) -> Result<SearchPlaylists, failure::Error> {
+        self.call("search_playlist", args!(q, limit, offset, market))
+    }
+
+    fn recommendations(
+        &self,
+        seed_artists: Option<Vec<String>>,
+        seed_genres: Option<Vec<String>>,
+        seed_tracks: Option<Vec<String>>,
+        limit: Option<u32>,
+        country: Option<Country>,
+        payload: &Map<String, Value>,
+    ) -> Result<Recommendations, failure::Error> {
+        self.call(
+            "recommendations",
+            args!(
+                seed_artists,
+                seed_genres,
+                seed_tracks,
+                limit,
+                country,
+                payload
+            ),
+        )
+    }
+
+    fn current_user_playlists(
+        &self,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<Page<SimplifiedPlaylist>, failure::Error> {
+        self.call("current_user_playlists", args!(limit, offset))
+    }
+
+    fn user_playlist_create(
+        &self,
+        user_id: &str,
+        name: &str,
+        public: Option<bool>,
+        description: Option<String>,
+    ) -> Result<FullPlaylist, failure::Error> {
+        self.call(
+            "user_playlist_create",
+            args!(user_id, name, public, description),
+        )
+    }
+
+    fn user_playlist_tracks(
+        &self,
+        user_id: &str,
+        playlist_id: &str,
+        fields: Option<&str>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        market: Option<Country>,
+    ) -> Result<Page<PlaylistTrack>, failure::Error> {
+        self.call(
+            "user_playlist_tracks",
+            args!(user_id, playlist_id, fields, limit, offset, market),
+        )
+    }
+
+    fn user_playlist_add_tracks(
+        &self,
+        user_id: &str,
+        playlist_id: &str,
+        track_ids: &[String],
+        position: Option<i32>,
+    ) -> Result<CUDResult, failure::Error> {
+        self.call(
+            "user_playlist_add_tracks",
+            args!(user_id, playlist_id, track_ids, position),
+        )
+    }
+
+    fn user_playlist_follow_playlist(
+        &self,
+        playlist_owner_id: &str,
+        playlist_id: &str,
+        public: Option<bool>,
+    ) -> Result<(), failure::Error> {
+        self.command(
+            "user_playlist_follow_playlist",
+            args!(playlist_owner_id, playlist_id, public),
+        )
+    }
+
+    fn user_playlist_unfollow(
+        &self,
+        user_id: &str,
+        playlist_id: &str,
+    ) -> Result<String, failure::Error> {
+        self.command("user_playlist_unfollow", args!(user_id, playlist_id))
+            .map(|_| String::new())
+    }
+
+    fn current_user_saved_tracks(
+        &self,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<Page<SavedTrack>, failure::Error> {
+        self.call("current_user_saved_tracks", args!(limit, offset))
+    }
+
+    fn current_user_saved_tracks_contains(
+        &self,
+        track_ids: &[String],
+    ) -> Result<Vec<bool>, failure::Error> {
+        self.call("current_user_saved_tracks_contains", args!(track_ids))
+    }
+
+    fn current_user_saved_tracks_add(&self, track_ids: &[String]) -> Result<(), failure::Error> {
+        self.command("current_user_saved_tracks_add", args!(track_ids))
+    }
+
+    fn current_user_saved_tracks_delete(&self, track_ids: &[String]) -> Result<(), failure::Error> {
+        self.command("current_user_saved_tracks_delete", args!(track_ids))
+    }
+
+    fn current_user_saved_albums(
+        &self,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<Page<SavedAlbum>, failure::Error> {
+        self.call("current_user_saved_albums", args!(limit, offset))
+    }
+
+    fn current_user_saved_albums_add(&self, album_ids: &[String]) -> Result<(), failure::Error> {
+        self.command("current_user_saved_albums_add", args!(album_ids))
+    }
+
+    fn current_user_saved_albums_delete(&self, album_ids: &[String]) -> Result<(), failure::Error> {
+        self.command("current_user_saved_albums_delete", args!(album_ids))
+    }
+
+    fn current_user_followed_artists(
+        &self,
+        limit: Option<u32>,
+        after: Option<String>,
+    ) -> Result<CursorPageFullArtists, failure::Error> {
+        self.call("current_user_followed_artists", args!(limit, after))
+    }
+
+    fn user_follow_artists(&self, artist_ids: &[String]) -> Result<(), failure::Error> {
+        self.command("user_follow_artists", args!(artist_ids))
+    }
+
+    fn user_unfollow_artists(&self, artist_ids: &[String]) -> Result<(), failure::Error> {
+        self.command("user_unfollow_artists", args!(artist_ids))
+    }
+
+    fn current_user_recently_played(
+        &self,
+        limit: Option<u32>,
+    ) -> Result<CursorBasedPage<PlayHistory>, failure::Error> {
+        self.call("current_user_recently_played", args!(limit))
+    }
+
+    fn current_user_top_artists(
+        &self,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        time_range: Option<TimeRange>,
+    ) -> Result<Page<FullArtist>, failure::Error> {
+        self.call("current_user_top_artists", args!(limit, offset, time_range))
+    }
+
+    fn current_user_top_tracks(
+        &self,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        time_range: Option<TimeRange>,
+    ) -> Result<Page<FullTrack>, failure::Error> {
+        self.call("current_user_top_tracks", args!(limit, offset, time_range))
+    }
+
+    fn categories(
+        &self,
+        locale: Option<String>,
+        country: Option<Country>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<PageCategory, failure::Error> {
+        self.call("categories", args!(locale, country, limit, offset))
+    }
+
+    fn new_releases(
+        &self,
+        country: Option<Country>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<PageSimpliedAlbums, failure::Error> {
+        self.call("new_releases", args!(country, limit, offset))
+    }
+
+    fn featured_playlists(
+        &self,
+        locale: Option<String>,
+        country: Option<Country>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<FeaturedPlaylists, failure::Error> {
+        self.call("featured_playlists", args!(locale, country, limit, offset))
+    }
+
+    fn web_api_call(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&Value>,
+    ) -> Result<String, failure::Error> {
+        let key = format!("{} {}", method, path);
+        self.record(&key, args!(body))?;
+        match self.responses.borrow().get(&key) {
+            Some(response) => Ok(response.to_string()),
+            None if method == Method::GET => Err(format_err!("No fixture for {}", key)),
+            None => Ok(String::new()),
+        }
+    }
+}
+
+/// An app that talks to a new fake, which is handed back to script responses and check calls
+pub fn app_with_fake() -> (App, Rc<FakeSpotify>) {
+    let spotify = Rc::new(FakeSpotify::new());
+    let mut app = App::new();
+    app.spotify = Some(spotify.clone());
+    (app, spotify)
+}
+
+// Fixtures with just enough fields for rspotify's models to deserialize
+
+pub fn user(id: &str) -> Value {
+    json!({
+        "country": "GB",
+        "display_name": id,
+        "external_urls": {},
+        "href": format!("https://api.spotify.com/v1/users/{}", id),
+        "id": id,
+        "type": "user",
+        "uri": format!("spotify:user:{}", id),
+    })
+}
+
+pub fn device(id: &str, name: &str, is_active: bool) -> Value {
+    json!({
+        "id": id,
+        "is_active": is_active,
+        "is_restricted": false,
+        "name": name,
+        "type": "Computer",
+        "volume_percent": 50,
+    })
+}
+
+pub fn artist(id: &str, name: &str) -> Value {
+    json!({
+        "external_urls": {},
+        "href": null,
+        "id": id,
+        "name": name,
+        "type": "artist",
+        "uri": format!("spotify:artist:{}", id),
+    })
+}
+
+// An artist as the artist and top artists endpoints return them
+pub fn full_artist(id: &str, name: &str) -> Value {
+    json!({
+        "external_urls": {},
+        "followers": { "href": null, "total": 0 },
+        "genres": [],
+        "href": format!("https://api.spotify.com/v1/artists/{}", id),
+        "id": id,
+        "images": [],
+        "name": name,
+        "popularity": 50,
+        "type": "artist",
+        "uri": format!("spotify:artist:{}", id),
+    })
+}
+
+pub fn album(id: &str, name: &str, artist_name: &str) -> Value {
+    json!({
+        "album_type": "album",
+        "artists": [artist(&format!("{}-artist", id), artist_name)],
+        "external_urls": {},
+        "href": null,
+        "id": id,
+        "images": [],
+        "name": name,
+        "type": "album",
+        "uri": format!("spotify:album:{}", id),
+    })
+}
+
+pub fn track(id: &str, name: &str, artist_name: &str) -> Value {
+    json!({
+        "album": album(&format!("{}-album", id), &format!("{} album", name), artist_name),
+        "artists": [artist(&format!("{}-artist", id), artist_name)],
+        "disc_number": 1,
+        "duration_ms": 180_000,
+        "explicit": false,
+        "external_ids": {},
+        "external_urls": {},
+        "href": null,
+        "id": id,
+        "is_local": false,
+        "name": name,
+        "popularity": 50,
+        "preview_url": null,
+        "track_number": 1,
+        "type": "track",
+        "uri": format!("spotify:track:{}", id),
+    })
+}
+
+// An album as the album endpoints and saved albums return it, with its first page of tracks
+pub fn full_album(id: &str, name: &str, artist_name: &str, tracks: Vec<Value>) -> Value {
+    json!({
+        "album_type": "album",
+        "artists": [artist(&format!("{}-artist", id), artist_name)],
+        "available_markets": [],
+        "copyrights": [],
+        "external_ids": {},
+        "external_urls": {},
+        "genres": [],
+        "href": "",
+        "id": id,
+        "images": [],
+        "name": name,
+        "popularity": 50,
+        "release_date": "2020",
+        "release_date_precision": "year",
+        "tracks": page(tracks),
+        "type": "album",
+        "uri": format!("spotify:album:{}", id),
+    })
+}
+
+/// A track with the fields that `track` leaves at their defaults set, e.g.
+/// `TrackBuilder::new("1", "Song", "Band").duration_ms(150_000).build()`
+pub struct TrackBuilder {
+    track: Value,
+}
+
+impl TrackBuilder {
+    pub fn new(id: &str, name: &str, artist_name: &str) -> TrackBuilder {
+        TrackBuilder {
+            track: track(id, name, artist_name),
+        }
+    }
+
+    pub fn duration_ms(mut self, duration_ms: u32) -> TrackBuilder {
+        self.track["duration_ms"] = json!(duration_ms);
+        self
+    }
+
+    pub fn isrc(mut self, isrc: &str) -> TrackBuilder {
+        self.track["external_ids"] = json!({ "isrc": isrc });
+        self
+    }
+
+    pub fn build(self) -> Value {
+        self.track
+    }
+
+    // As an album's tracks come, without the album
+    pub fn build_simplified(mut self) -> Value {
+        if let Some(track) = self.track.as_object_mut() {
+            track.remove("album");
+        }
+        self.track
+    }
+}
+
+pub fn playlist(id: &str, name: &str, owner_id: &str) -> Value {
+    json!({
+        "collaborative": false,
+        "external_urls": {},
+        "href": format!("https://api.spotify.com/v1/playlists/{}", id),
+        "id": id,
+        "images": [],
+        "name": name,
+        "owner": user(owner_id),
+        "public": true,
+        "snapshot_id": "snapshot",
+        "tracks": {"total": 0},
+        "type": "playlist",
+        "uri": format!("spotify:playlist:{}", id),
+    })
+}
+
+// A playlist as creating one returns it, with a description and a page of tracks
+pub fn full_playlist(id: &str, name: &str, owner_id: &str) -> Value {
+    let mut playlist = playlist(id, name, owner_id);
+    playlist["description"] = json!("");
+    playlist["tracks"] = page(vec![]);
+    playlist
+}
+
+/// A saved track or playlist item, added on the first day of 2020
+pub fn saved_track(track: Value) -> Value {
+    json!({
+        "added_at": "2020-01-01T00:00:00Z",
+        "added_by": null,
+        "is_local": false,
+        "track": track,
+    })
+}
+
+pub fn playing_context(track: Value, device: Value, is_playing: bool) -> Value {
+    json!({
+        "device": device,
+        "repeat_state": "off",
+        "shuffle_state": false,
+        "context": null,
+        "timestamp": 0,
+        "progress_ms": 0,
+        "is_playing": is_playing,
+        "item": track,
+    })
+}
+
+pub fn page(items: Vec<Value>) -> Value {
+    json!({
+        "href": "",
+        "limit": 20,
+        "next": null,
+        "offset": 0,
+        "previous": null,
+        "total": items.len(),
+        "items": items,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixtures_deserialize() {
+        let spotify = FakeSpotify::new();
+        spotify
+            .respond("current_user", user("alice"))
+            .respond("track", track("1", "Song", "Band"))
+            .respond(
+                "current_user_playlists",
+                page(vec![playlist("p", "Mix", "alice")]),
+            )
+            .respond(
+                "device",
+                json!({ "devices": [device("d", "Laptop", true)] }),
+            );
+
+        assert_eq!(spotify.current_user().unwrap().id, "alice");
+        assert_eq!(spotify.track("1").unwrap().name, "Song");
+        assert_eq!(
+            spotify
+                .current_user_playlists(Some(20), None)
+                .unwrap()
+                .items[0]
+                .name,
+            "Mix"
+        );
+        assert_eq!(spotify.device().unwrap().devices[0].name, "Laptop");
+    }
+
+    #[test]
+    fn test_records_calls_and_errors() {
+        let spotify = FakeSpotify::new();
+        spotify.fail("next_track", "No active device");
+
+        assert!(spotify.pause_playback(Some("d".to_string())).is_ok());
+        assert!(spotify.next_track(None).is_err());
+        assert!(spotify.album("missing").is_err());
+        assert_eq!(
+            spotify.calls(),
+            vec![
+                "pause_playback(Some(\"d\"))",
+                "next_track(None)",
+                "album(\"missing\")"
+            ]
+        );
+        assert_eq!(spotify.calls_to("pause_playback"), vec!["Some(\"d\")"]);
+    }
+}
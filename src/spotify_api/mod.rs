@@ -0,0 +1,245 @@
+//! Every Spotify Web API endpoint the app uses, behind a trait so `App` can be driven by the
+//! in-memory fake in tests instead of the network.
+mod client;
+#[cfg(test)]
+pub mod fake;
+pub mod traffic;
+
+pub use client::{WebApiClient, DEFAULT_API_URL};
+
+use reqwest::Method;
+use rspotify::spotify::{
+    model::{
+        album::{FullAlbum, PageSimpliedAlbums, SavedAlbum, SimplifiedAlbum},
+        artist::{CursorPageFullArtists, FullArtist, FullArtists},
+        audio::{AudioAnalysis, AudioFeatures},
+        category::PageCategory,
+        context::FullPlayingContext,
+        cud_result::CUDResult,
+        device::DevicePayload,
+        offset::Offset,
+        page::{CursorBasedPage, Page},
+        playing::PlayHistory,
+        playlist::{FeaturedPlaylists, FullPlaylist, PlaylistTrack, SimplifiedPlaylist},
+        recommend::Recommendations,
+        search::{SearchAlbums, SearchArtists, SearchPlaylists, SearchTracks},
+        track::{FullTrack, FullTracks, SavedTrack, SimplifiedTrack},
+        user::PrivateUser,
+    },
+    senum::{AlbumType, Country, RepeatState, TimeRange},
+};
+use serde_json::{map::Map, Value};
+
+/// The arguments match rspotify's, with its `Into<Option<_>>` generics spelled out so the trait
+/// can be used as `dyn SpotifyApi`. `featured_playlists` leaves out the timestamp, which the app
+/// never sets
+pub trait SpotifyApi {
+    fn current_user(&self) -> Result<PrivateUser, failure::Error>;
+    fn device(&self) -> Result<DevicePayload, failure::Error>;
+    fn current_playback(
+        &self,
+        market: Option<Country>,
+    ) -> Result<Option<FullPlayingContext>, failure::Error>;
+
+    fn start_playback(
+        &self,
+        device_id: Option<String>,
+        context_uri: Option<String>,
+        uris: Option<Vec<String>>,
+        offset: Option<Offset>,
+        position_ms: Option<u32>,
+    ) -> Result<(), failure::Error>;
+    fn pause_playback(&self, device_id: Option<String>) -> Result<(), failure::Error>;
+    fn next_track(&self, device_id: Option<String>) -> Result<(), failure::Error>;
+    fn previous_track(&self, device_id: Option<String>) -> Result<(), failure::Error>;
+    fn seek_track(&self, position_ms: u32, device_id: Option<String>)
+        -> Result<(), failure::Error>;
+    fn shuffle(&self, state: bool, device_id: Option<String>) -> Result<(), failure::Error>;
+    fn repeat(&self, state: RepeatState, device_id: Option<String>) -> Result<(), failure::Error>;
+    fn volume(&self, volume_percent: u8, device_id: Option<String>) -> Result<(), failure::Error>;
+    fn add_item_to_queue(&self, uri: &str, device_id: Option<String>)
+        -> Result<(), failure::Error>;
+
+    fn track(&self, track_id: &str) -> Result<FullTrack, failure::Error>;
+    fn tracks(
+        &self,
+        track_ids: Vec<&str>,
+        market: Option<Country>,
+    ) -> Result<FullTracks, failure::Error>;
+    fn album(&self, album_id: &str) -> Result<FullAlbum, failure::Error>;
+    fn album_track(
+        &self,
+        album_id: &str,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<Page<SimplifiedTrack>, failure::Error>;
+    fn artist(&self, artist_id: &str) -> Result<FullArtist, failure::Error>;
+    fn artist_albums(
+        &self,
+        artist_id: &str,
+        album_type: Option<AlbumType>,
+        country: Option<Country>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<Page<SimplifiedAlbum>, failure::Error>;
+    fn artist_top_tracks(
+        &self,
+        artist_id: &str,
+        country: Option<Country>,
+    ) -> Result<FullTracks, failure::Error>;
+    fn artist_related_artists(&self, artist_id: &str) -> Result<FullArtists, failure::Error>;
+    fn audio_analysis(&self, track: &str) -> Result<AudioAnalysis, failure::Error>;
+    fn audio_features(&self, track: &str) -> Result<AudioFeatures, failure::Error>;
+
+    fn search_track(
+        &self,
+        q: &str,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        market: Option<Country>,
+    ) -> Result<SearchTracks, failure::Error>;
+    fn search_artist(
+        &self,
+        q: &str,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        market: Option<Country>,
+    ) -> Result<SearchArtists, failure::Error>;
+    fn search_album(
+        &self,
+        q: &str,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        market: Option<Country>,
+    ) -> Result<SearchAlbums, failure::Error>;
+    fn search_playlist(
+        &self,
+        q: &str,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        market: Option<Country>,
+    ) -> Result<SearchPlaylists, failure::Error>;
+
+    fn recommendations(
+        &self,
+        seed_artists: Option<Vec<String>>,
+        seed_genres: Option<Vec<String>>,
+        seed_tracks: Option<Vec<String>>,
+        limit: Option<u32>,
+        country: Option<Country>,
+        payload: &Map<String, Value>,
+    ) -> Result<Recommendations, failure::Error>;
+
+    fn current_user_playlists(
+        &self,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<Page<SimplifiedPlaylist>, failure::Error>;
+    fn user_playlist_create(
+        &self,
+        user_id: &str,
+        name: &str,
+        public: Option<bool>,
+        description: Option<String>,
+    ) -> Result<FullPlaylist, failure::Error>;
+    fn user_playlist_tracks(
+        &self,
+        user_id: &str,
+        playlist_id: &str,
+        fields: Option<&str>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        market: Option<Country>,
+    ) -> Result<Page<PlaylistTrack>, failure::Error>;
+    fn user_playlist_add_tracks(
+        &self,
+        user_id: &str,
+        playlist_id: &str,
+        track_ids: &[String],
+        position: Option<i32>,
+    ) -> Result<CUDResult, failure::Error>;
+    fn user_playlist_follow_playlist(
+        &self,
+        playlist_owner_id: &str,
+        playlist_id: &str,
+        public: Option<bool>,
+    ) -> Result<(), failure::Error>;
+    fn user_playlist_unfollow(
+        &self,
+        user_id: &str,
+        playlist_id: &str,
+    ) -> Result<String, failure::Error>;
+
+    fn current_user_saved_tracks(
+        &self,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<Page<SavedTrack>, failure::Error>;
+    fn current_user_saved_tracks_contains(
+        &self,
+        track_ids: &[String],
+    ) -> Result<Vec<bool>, failure::Error>;
+    fn current_user_saved_tracks_add(&self, track_ids: &[String]) -> Result<(), failure::Error>;
+    fn current_user_saved_tracks_delete(&self, track_ids: &[String]) -> Result<(), failure::Error>;
+    fn current_user_saved_albums(
+        &self,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<Page<SavedAlbum>, failure::Error>;
+    fn current_user_saved_albums_add(&self, album_ids: &[String]) -> Result<(), failure::Error>;
+    fn current_user_saved_albums_delete(&self, album_ids: &[String]) -> Result<(), failure::Error>;
+    fn current_user_followed_artists(
+        &self,
+        limit: Option<u32>,
+        after: Option<String>,
+    ) -> Result<CursorPageFullArtists, failure::Error>;
+    fn user_follow_artists(&self, artist_ids: &[String]) -> Result<(), failure::Error>;
+    fn user_unfollow_artists(&self, artist_ids: &[String]) -> Result<(), failure::Error>;
+
+    fn current_user_recently_played(
+        &self,
+        limit: Option<u32>,
+    ) -> Result<CursorBasedPage<PlayHistory>, failure::Error>;
+    fn current_user_top_artists(
+        &self,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        time_range: Option<TimeRange>,
+    ) -> Result<Page<FullArtist>, failure::Error>;
+    fn current_user_top_tracks(
+        &self,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        time_range: Option<TimeRange>,
+    ) -> Result<Page<FullTrack>, failure::Error>;
+
+    fn categories(
+        &self,
+        locale: Option<String>,
+        country: Option<Country>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<PageCategory, failure::Error>;
+    fn new_releases(
+        &self,
+        country: Option<Country>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<PageSimpliedAlbums, failure::Error>;
+    fn featured_playlists(
+        &self,
+        locale: Option<String>,
+        country: Option<Country>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<FeaturedPlaylists, failure::Error>;
+
+    /// Endpoints rspotify does not cover (or gets wrong), relative to the API root, returning
+    /// the response body
+    fn web_api_call(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&Value>,
+    ) -> Result<String, failure::Error>;
+}
@@ -0,0 +1,293 @@
+//! Recording Web API traffic for bug reports, and replaying it in place of the network.
+//!
+//! `spt --record <dir>` writes every request with its response to `<dir>` as numbered JSON
+//! files, without the Authorization header and with the access token scrubbed from everything
+//! else. `spt --replay <dir>` answers requests from those files: the next unused response to the
+//! same request, or the last one when they have all been used (the app polls `me/player`). When
+//! the query differs, e.g. a page size that depends on the terminal height, a response for the
+//! same path is used instead.
+use failure::format_err;
+use rspotify::spotify::client::ApiError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    cell::{Cell, RefCell},
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+const SESSION_FILE: &str = "session.json";
+const REDACTED: &str = "<redacted>";
+
+/// Where requests made by `WebApiClient` go
+#[derive(Clone)]
+pub enum Traffic {
+    Live,
+    Record(Rc<Recorder>),
+    Replay(Rc<Replayer>),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Exchange {
+    pub method: String,
+    // Relative to the API root, with the query string
+    pub path: String,
+    pub body: Option<Value>,
+    pub status: u16,
+    pub response: String,
+}
+
+impl Exchange {
+    fn path_without_query(&self) -> &str {
+        self.path.split('?').next().unwrap_or(&self.path)
+    }
+
+    /// The response as `WebApiClient` would have returned it
+    pub fn to_result(&self) -> Result<String, failure::Error> {
+        if (200..300).contains(&self.status) {
+            return Ok(self.response.clone());
+        }
+        Err(match self.status {
+            401 => ApiError::Unauthorized,
+            429 => ApiError::RateLimited(None),
+            status => ApiError::Other(status),
+        }
+        .into())
+    }
+}
+
+// Anything about the session that changes which requests the app makes
+#[derive(Default, Serialize, Deserialize)]
+struct Session {
+    device_id: Option<String>,
+}
+
+pub struct Recorder {
+    dir: PathBuf,
+    count: Cell<usize>,
+}
+
+impl Recorder {
+    pub fn create(dir: &Path, device_id: Option<String>) -> Result<Recorder, failure::Error> {
+        if dir.join(SESSION_FILE).exists() {
+            return Err(format_err!(
+                "{} already has a recording, pick another directory",
+                dir.display()
+            ));
+        }
+        fs::create_dir_all(dir)?;
+        let session = Session { device_id };
+        fs::write(
+            dir.join(SESSION_FILE),
+            serde_json::to_string_pretty(&session)?,
+        )?;
+        Ok(Recorder {
+            dir: dir.to_path_buf(),
+            count: Cell::new(0),
+        })
+    }
+
+    /// Save an exchange, replacing `access_token` wherever it appears
+    pub fn save(&self, exchange: &Exchange, access_token: &str) -> Result<(), failure::Error> {
+        let mut text = serde_json::to_string_pretty(exchange)?;
+        if !access_token.is_empty() {
+            text = text.replace(access_token, REDACTED);
+        }
+        let count = self.count.get() + 1;
+        self.count.set(count);
+        fs::write(self.dir.join(format!("{:05}.json", count)), text)?;
+        Ok(())
+    }
+}
+
+pub struct Replayer {
+    exchanges: Vec<Exchange>,
+    used: RefCell<Vec<bool>>,
+    pub device_id: Option<String>,
+}
+
+impl Replayer {
+    pub fn load(dir: &Path) -> Result<Replayer, failure::Error> {
+        let session_path = dir.join(SESSION_FILE);
+        let session: Session = serde_json::from_str(
+            &fs::read_to_string(&session_path)
+                .map_err(|e| format_err!("Could not read {}: {}", session_path.display(), e))?,
+        )?;
+
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .is_some_and(|stem| stem.chars().all(|c| c.is_ascii_digit()))
+            })
+            .collect();
+        paths.sort();
+
+        let mut exchanges = vec![];
+        for path in paths {
+            let exchange = serde_json::from_str(&fs::read_to_string(&path)?)
+                .map_err(|e| format_err!("Invalid recording {}: {}", path.display(), e))?;
+            exchanges.push(exchange);
+        }
+
+        Ok(Replayer {
+            used: RefCell::new(vec![false; exchanges.len()]),
+            exchanges,
+            device_id: session.device_id,
+        })
+    }
+
+    pub fn respond(&self, method: &str, path: &str) -> Result<String, failure::Error> {
+        let path_without_query = path.split('?').next().unwrap_or(path);
+        let exchange = self
+            .next_match(|exchange| exchange.method == method && exchange.path == path)
+            .or_else(|| {
+                self.next_match(|exchange| {
+                    exchange.method == method && exchange.path_without_query() == path_without_query
+                })
+            })
+            .ok_or_else(|| format_err!("No recorded response for {} {}", method, path))?;
+        exchange.to_result()
+    }
+
+    /// The first unused exchange that matches, or the last used one
+    fn next_match<F>(&self, matches: F) -> Option<&Exchange>
+    where
+        F: Fn(&Exchange) -> bool,
+    {
+        let mut used = self.used.borrow_mut();
+        let mut last_used = None;
+        for (index, exchange) in self.exchanges.iter().enumerate() {
+            if !matches(exchange) {
+                continue;
+            }
+            if !used[index] {
+                used[index] = true;
+                return Some(exchange);
+            }
+            last_used = Some(exchange);
+        }
+        last_used
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock_api::MockApi,
+        spotify_api::{SpotifyApi, WebApiClient},
+    };
+    use std::{env, net::TcpListener, thread};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("spt-traffic-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn exchange(path: &str, response: &str) -> Exchange {
+        Exchange {
+            method: "GET".to_string(),
+            path: path.to_string(),
+            body: None,
+            status: 200,
+            response: response.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_replay_order() {
+        let replayer = Replayer {
+            exchanges: vec![
+                exchange("me/player", "first"),
+                exchange("me/player", "second"),
+                exchange("me/playlists?limit=20&offset=0", "playlists"),
+            ],
+            used: RefCell::new(vec![false; 3]),
+            device_id: None,
+        };
+
+        assert_eq!(replayer.respond("GET", "me/player").unwrap(), "first");
+        assert_eq!(replayer.respond("GET", "me/player").unwrap(), "second");
+        // Polling carries on with the last response
+        assert_eq!(replayer.respond("GET", "me/player").unwrap(), "second");
+        assert_eq!(
+            replayer
+                .respond("GET", "me/playlists?limit=31&offset=0")
+                .unwrap(),
+            "playlists"
+        );
+        assert!(replayer.respond("PUT", "me/player").is_err());
+    }
+
+    #[test]
+    fn test_error_responses() {
+        let mut not_found = exchange("tracks/missing", "");
+        not_found.status = 404;
+        assert_eq!(
+            not_found.to_result().unwrap_err().to_string(),
+            "Spotify API reported error code 404"
+        );
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let api_url = format!("http://{}/v1", listener.local_addr().unwrap());
+        let mock_api = MockApi::new(None).unwrap();
+        thread::spawn(move || mock_api.serve(listener));
+
+        let dir = temp_dir("record");
+        let recorder = Recorder::create(&dir, Some("mockdevice1".to_string())).unwrap();
+        let spotify = WebApiClient::new(&api_url, "mock-access-token")
+            .with_traffic(Traffic::Record(Rc::new(recorder)));
+        let user = spotify.current_user().unwrap();
+        spotify
+            .start_playback(
+                Some("mockdevice1".to_string()),
+                None,
+                Some(vec!["spotify:track:mocktrack3".to_string()]),
+                None,
+                None,
+            )
+            .unwrap();
+        let playing = spotify.current_playback(None).unwrap().unwrap().item;
+        let track_id = playing.unwrap().id;
+        assert!(spotify.track("missing").is_err());
+
+        assert!(Recorder::create(&dir, None).is_err());
+        for entry in fs::read_dir(&dir).unwrap() {
+            let text = fs::read_to_string(entry.unwrap().path()).unwrap();
+            assert!(!text.contains("mock-access-token"));
+        }
+
+        // Nothing listens on the discard port, so everything has to come from the recording
+        let replayer = Replayer::load(&dir).unwrap();
+        assert_eq!(replayer.device_id.as_deref(), Some("mockdevice1"));
+        assert_eq!(replayer.exchanges.len(), 4);
+        let spotify = WebApiClient::new("http://127.0.0.1:9/v1", "")
+            .with_traffic(Traffic::Replay(Rc::new(replayer)));
+        assert_eq!(spotify.current_user().unwrap().id, user.id);
+        spotify
+            .start_playback(
+                Some("mockdevice1".to_string()),
+                None,
+                Some(vec!["spotify:track:mocktrack3".to_string()]),
+                None,
+                None,
+            )
+            .unwrap();
+        let replayed = spotify.current_playback(None).unwrap().unwrap().item;
+        assert_eq!(replayed.unwrap().id, track_id);
+        assert_eq!(
+            spotify.track("missing").unwrap_err().to_string(),
+            "Spotify API reported error code 404"
+        );
+        assert!(spotify.device().is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
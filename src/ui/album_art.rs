@@ -0,0 +1,57 @@
+use super::util::Clear;
+use crate::album_art::Protocol;
+use crate::app::App;
+use image::RgbImage;
+use tui::{backend::Backend, buffer::Buffer, layout::Rect, style::Color, widgets::Widget, Frame};
+
+// Each cell shows two pixels: the upper half block in the foreground colour and the lower one
+// in the background colour. The image is already scaled to fit the area
+struct HalfBlockImage<'a> {
+    image: &'a RgbImage,
+}
+
+impl<'a> Widget for HalfBlockImage<'a> {
+    fn draw(&mut self, area: Rect, buf: &mut Buffer) {
+        let image = self.image;
+        let (width, height) = image.dimensions();
+        let to_color = |pixel: &image::Rgb<u8>| Color::Rgb(pixel[0], pixel[1], pixel[2]);
+        let left = area.x + (area.width - width as u16) / 2;
+
+        for y in 0..height.div_ceil(2) {
+            for x in 0..width {
+                let top = to_color(image.get_pixel(x, y * 2));
+                let bottom = if y * 2 + 1 < height {
+                    to_color(image.get_pixel(x, y * 2 + 1))
+                } else {
+                    Color::Reset
+                };
+
+                buf.get_mut(left + x as u16, area.y + y as u16)
+                    .set_symbol("▀")
+                    .set_fg(top)
+                    .set_bg(bottom);
+            }
+        }
+    }
+}
+
+pub fn draw<B>(f: &mut Frame<B>, app: &App, url: &str, area: Rect)
+where
+    B: Backend,
+{
+    match app.album_art.protocol() {
+        Protocol::Kitty | Protocol::Sixel => {
+            if app.album_art.get(url).is_some() {
+                // Keep the area blank so the image is not drawn over
+                Clear.render(f, area);
+                app.album_art.place(url, area);
+            }
+        }
+        _ => {
+            let bounds = (u32::from(area.width), u32::from(area.height) * 2);
+            if let Some(image) = app.album_art.get_resized(url, bounds) {
+                HalfBlockImage { image: &image }.render(f, area);
+            }
+        }
+    }
+}
@@ -1,15 +1,149 @@
 use super::util;
-use crate::app::App;
+use crate::app::{AnalysisViewMode, App, ANALYSIS_VIEW_MODES};
+use rspotify::spotify::model::audio::{
+    AudioAnalysis, AudioAnalysisMeasure, AudioAnalysisSection, AudioAnalysisSegment,
+};
 use tui::{
     backend::Backend,
-    layout::{Constraint, Direction, Layout},
-    style::Style,
-    widgets::{BarChart, Block, Borders, Paragraph, Text, Widget},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Modifier,
+    widgets::{BarChart, Block, Borders, Gauge, Paragraph, Sparkline, Text, Widget},
     Frame,
 };
 const PITCHES: [&str; 12] = [
     "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
 ];
+const TIMBRES: [&str; 12] = [
+    "1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11", "12",
+];
+// Timbre coefficients are unbounded but mostly sit within this range around 0
+const TIMBRE_OFFSET: f32 = 150.0;
+// Loudness is given in dB, anything quieter than this is drawn as silence
+const MIN_LOUDNESS: f32 = -60.0;
+const LOUDNESS_WINDOW_SECONDS: f32 = 10.0;
+
+// Index of the item playing at `seconds`
+fn get_current_index<T, F>(items: &[T], seconds: f32, start: F) -> Option<usize>
+where
+    F: Fn(&T) -> f32,
+{
+    items.iter().rposition(|item| start(item) <= seconds)
+}
+
+fn lerp(from: f32, to: f32, fraction: f32) -> f32 {
+    from + (to - from) * fraction
+}
+
+// Blends the values of the playing segment into the next one so the bars move smoothly
+pub fn interpolate_segments<F>(
+    segments: &[AudioAnalysisSegment],
+    seconds: f32,
+    values: F,
+) -> Vec<f32>
+where
+    F: Fn(&AudioAnalysisSegment) -> &Vec<f32>,
+{
+    let index = match get_current_index(segments, seconds, |segment| segment.start) {
+        Some(index) => index,
+        None => {
+            return segments
+                .first()
+                .map(|s| values(s).clone())
+                .unwrap_or_default()
+        }
+    };
+
+    let segment = &segments[index];
+    match segments.get(index + 1) {
+        Some(next) if segment.duration > 0.0 => {
+            let fraction = ((seconds - segment.start) / segment.duration).clamp(0.0, 1.0);
+            values(segment)
+                .iter()
+                .zip(values(next).iter())
+                .map(|(from, to)| lerp(*from, *to, fraction))
+                .collect()
+        }
+        _ => values(segment).clone(),
+    }
+}
+
+// Follows the envelope of the segment: from `loudness_start` up to `loudness_max`, then down to
+// where the next segment starts
+pub fn get_loudness(segments: &[AudioAnalysisSegment], seconds: f32) -> f32 {
+    let index = match get_current_index(segments, seconds, |segment| segment.start) {
+        Some(index) => index,
+        None => return MIN_LOUDNESS,
+    };
+
+    let segment = &segments[index];
+    let offset = seconds - segment.start;
+    if offset < segment.loudness_max_time && segment.loudness_max_time > 0.0 {
+        return lerp(
+            segment.loudness_start,
+            segment.loudness_max,
+            offset / segment.loudness_max_time,
+        );
+    }
+
+    let end = segment
+        .loudness_end
+        .or_else(|| segments.get(index + 1).map(|next| next.loudness_start))
+        .unwrap_or(segment.loudness_max);
+    let decay_duration = segment.duration - segment.loudness_max_time;
+    if decay_duration > 0.0 {
+        let fraction = ((offset - segment.loudness_max_time) / decay_duration).min(1.0);
+        lerp(segment.loudness_max, end, fraction)
+    } else {
+        segment.loudness_max
+    }
+}
+
+// 1.0 right on a beat, fading to 0.0 by the next one
+pub fn get_beat_pulse(beats: &[AudioAnalysisMeasure], seconds: f32) -> f32 {
+    match get_current_index(beats, seconds, |beat| beat.start) {
+        Some(index) if beats[index].duration > 0.0 => {
+            let beat = &beats[index];
+            (1.0 - (seconds - beat.start) / beat.duration).max(0.0)
+        }
+        _ => 0.0,
+    }
+}
+
+fn get_key_name(key: i32) -> &'static str {
+    if key < 0 {
+        "?"
+    } else {
+        PITCHES.get(key as usize).unwrap_or(&PITCHES[0])
+    }
+}
+
+fn get_mode_name(mode: f32) -> &'static str {
+    if mode >= 0.5 {
+        "major"
+    } else if mode >= 0.0 {
+        "minor"
+    } else {
+        ""
+    }
+}
+
+fn format_seconds(seconds: f32) -> String {
+    let seconds = seconds.max(0.0) as u32;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+fn to_bar_data<'a>(
+    labels: &[&'a str],
+    values: &[f32],
+    scale: f32,
+    offset: f32,
+) -> Vec<(&'a str, u64)> {
+    labels
+        .iter()
+        .zip(values.iter())
+        .map(|(label, value)| (*label, ((value + offset) * scale).max(0.0) as u64))
+        .collect()
+}
 
 pub fn draw<B>(f: &mut Frame<B>, app: &App)
 where
@@ -19,119 +153,329 @@ where
 
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Min(5), Constraint::Length(95)].as_ref())
+        .constraints([Constraint::Length(7), Constraint::Min(5)].as_ref())
         .margin(margin)
         .split(f.size());
 
     let analysis_block = Block::default()
         .title("Analysis")
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(app.user_config.theme.inactive))
-        .title_style(Style::default().fg(app.user_config.theme.inactive));
+        .border_style(app.user_config.theme.inactive)
+        .title_style(app.user_config.theme.inactive);
 
-    let white = Style::default().fg(app.user_config.theme.text);
-    let gray = Style::default().fg(app.user_config.theme.inactive);
-    let width = (chunks[1].width) as f32 / (1 + PITCHES.len()) as f32;
+    let white = app.user_config.theme.text;
+    let gray = app.user_config.theme.inactive;
     let tick_rate = app.user_config.behavior.tick_rate_milliseconds;
-    let bar_chart_title = &format!("Pitches | Tick Rate {} {}FPS", tick_rate, 1000 / tick_rate);
+    let mode = app.analysis_view_mode;
+    let visualizer_title = &format!(
+        "{} | Tick Rate {} {}FPS",
+        mode.title(),
+        tick_rate,
+        1000 / tick_rate
+    );
 
-    let bar_chart_block = Block::default()
+    let mut visualizer_block = Block::default()
         .borders(Borders::ALL)
         .style(white)
-        .title(bar_chart_title)
+        .title(visualizer_title)
         .title_style(gray)
         .border_style(gray);
 
-    let analysis_text = [Text::raw("No analysis available")];
-    let empty_analysis_block = || {
-        Paragraph::new(analysis_text.iter())
-            .block(analysis_block)
-            .style(Style::default().fg(app.user_config.theme.text))
+    let mut mode_text = vec![Text::raw("Mode (h/l): ")];
+    for (index, view_mode) in ANALYSIS_VIEW_MODES.iter().enumerate() {
+        let style = if *view_mode == mode {
+            util::with_modifier(app.user_config.theme.selected, Modifier::BOLD)
+        } else {
+            app.user_config.theme.inactive
+        };
+        mode_text.push(Text::styled(
+            format!("{} {}  ", index + 1, view_mode.title()),
+            style,
+        ));
+    }
+
+    let analysis = match &app.audio_analysis {
+        Some(analysis) => analysis,
+        None => {
+            let mut text = vec![Text::raw("No analysis available\n")];
+            text.extend(mode_text);
+            Paragraph::new(text.iter())
+                .block(analysis_block)
+                .style(app.user_config.theme.text)
+                .render(f, chunks[0]);
+            visualizer_block.render(f, chunks[1]);
+            return;
+        }
     };
-    let pitch_text = [Text::raw("No pitch information available")];
-    let empty_pitches_block = || {
-        Paragraph::new(pitch_text.iter())
-            .block(bar_chart_block)
-            .style(Style::default().fg(app.user_config.theme.text))
+
+    let progress_seconds = (app.song_progress_ms as f32) / 1000.0;
+    let section = get_current_index(&analysis.sections, progress_seconds, |section| {
+        section.start
+    })
+    .map(|index| &analysis.sections[index]);
+
+    let mut text = match section {
+        Some(section) => vec![
+            Text::raw(format!(
+                "Tempo: {} (confidence {:.0}%)\n",
+                section.tempo,
+                section.tempo_confidence * 100.0
+            )),
+            Text::raw(format!(
+                "Key: {} {} (confidence {:.0}%)\n",
+                get_key_name(section.key),
+                get_mode_name(section.mode),
+                section.key_confidence * 100.0
+            )),
+            Text::raw(format!(
+                "Time Signature: {}/4 (confidence {:.0}%)\n",
+                section.time_signature,
+                section.time_signature_confidence * 100.0
+            )),
+        ],
+        None => vec![Text::raw("No section information available\n\n\n")],
     };
+    text.push(Text::raw("\n"));
+    text.extend(mode_text);
 
-    if let Some(analysis) = &app.audio_analysis {
-        let progress_seconds = (app.song_progress_ms as f32) / 1000.0;
-
-        let beat = analysis
-            .beats
-            .iter()
-            .find(|beat| beat.start >= progress_seconds);
-
-        let beat_offset = beat
-            .map(|beat| beat.start - progress_seconds)
-            .unwrap_or(0.0);
-        let segment = analysis
-            .segments
-            .iter()
-            .find(|segment| segment.start >= progress_seconds);
-        let section = analysis
-            .sections
-            .iter()
-            .find(|section| section.start >= progress_seconds);
-
-        if let (Some(segment), Some(section)) = (segment, section) {
-            Paragraph::new(
-                [
-                    Text::raw(format!(
-                        "Tempo: {} (confidence {:.0}%)\n",
-                        section.tempo,
-                        section.tempo_confidence * 100.0
-                    )),
-                    Text::raw(format!(
-                        "Key: {} (confidence {:.0}%)\n",
-                        PITCHES.get(section.key as usize).unwrap_or(&PITCHES[0]),
-                        section.key_confidence * 100.0
-                    )),
-                    Text::raw(format!(
-                        "Time Signature: {}/4 (confidence {:.0}%)\n",
-                        section.time_signature,
-                        section.time_signature_confidence * 100.0
-                    )),
-                ]
-                .iter(),
-            )
-            .block(analysis_block)
-            .style(Style::default().fg(app.user_config.theme.text))
-            .render(f, chunks[0]);
+    Paragraph::new(text.iter())
+        .block(analysis_block)
+        .style(app.user_config.theme.text)
+        .render(f, chunks[0]);
 
-            let data: Vec<(&str, u64)> = segment
-                .pitches
-                .iter()
-                .enumerate()
-                .map(|(index, pitch)| {
-                    let display_pitch = *PITCHES.get(index).unwrap_or(&PITCHES[0]);
-                    let bar_value = ((pitch * 1000.0) as u64)
-                        // Add a beat offset to make the bar animate between beats
-                        .checked_add((beat_offset * 3000.0) as u64)
-                        .unwrap_or(0);
-
-                    (display_pitch, bar_value)
-                })
-                .collect();
+    match mode {
+        AnalysisViewMode::Pitches | AnalysisViewMode::Timbre => {
+            let (labels, values, scale, offset) = if mode == AnalysisViewMode::Pitches {
+                let values =
+                    interpolate_segments(&analysis.segments, progress_seconds, |s| &s.pitches);
+                (&PITCHES, values, 1000.0, 0.0)
+            } else {
+                let values =
+                    interpolate_segments(&analysis.segments, progress_seconds, |s| &s.timbre);
+                (&TIMBRES, values, 10.0, TIMBRE_OFFSET)
+            };
+
+            if values.is_empty() {
+                draw_unavailable(f, app, visualizer_block, chunks[1]);
+                return;
+            }
+
+            let data = to_bar_data(labels, &values, scale, offset);
+            let width = (chunks[1].width) as f32 / (1 + labels.len()) as f32;
 
             BarChart::default()
-                .block(bar_chart_block)
+                .block(visualizer_block)
                 .data(&data)
                 .bar_width(width as u16)
-                .style(Style::default().fg(app.user_config.theme.analysis_bar))
+                .style(app.user_config.theme.analysis_bar)
                 .value_style(
-                    Style::default()
-                        .fg(app.user_config.theme.analysis_bar_text)
-                        .bg(app.user_config.theme.analysis_bar),
+                    app.user_config.theme.analysis_bar_text.bg(app
+                        .user_config
+                        .theme
+                        .analysis_bar
+                        .fg),
                 )
                 .render(f, chunks[1]);
-        } else {
-            empty_analysis_block().render(f, chunks[0]);
-            empty_pitches_block().render(f, chunks[1]);
-        };
-    } else {
-        empty_analysis_block().render(f, chunks[0]);
-        empty_pitches_block().render(f, chunks[1]);
+        }
+        AnalysisViewMode::Loudness => {
+            // One sample per column, ending at the current position
+            let columns = chunks[1].width.saturating_sub(2).max(1);
+            let step = LOUDNESS_WINDOW_SECONDS / f32::from(columns);
+            let data = (0..columns)
+                .map(|column| {
+                    let seconds = progress_seconds - step * f32::from(columns - 1 - column);
+                    if seconds < 0.0 {
+                        return 0;
+                    }
+                    let loudness = get_loudness(&analysis.segments, seconds).max(MIN_LOUDNESS);
+                    ((loudness - MIN_LOUDNESS) * 10.0) as u64
+                })
+                .collect::<Vec<u64>>();
+            let title = format!(
+                "Loudness over the last {}s: {:.1} dB",
+                LOUDNESS_WINDOW_SECONDS,
+                get_loudness(&analysis.segments, progress_seconds)
+            );
+
+            Sparkline::default()
+                .block(visualizer_block.title(&title))
+                .data(&data)
+                .max((-MIN_LOUDNESS * 10.0) as u64)
+                .style(app.user_config.theme.analysis_bar)
+                .render(f, chunks[1]);
+        }
+        AnalysisViewMode::Sections => {
+            draw_sections(f, app, analysis, section, visualizer_block, chunks[1]);
+        }
+        AnalysisViewMode::BeatPulse => {
+            let pulse = get_beat_pulse(&analysis.beats, progress_seconds);
+            let beat_number = get_current_index(&analysis.beats, progress_seconds, |b| b.start)
+                .map(|index| index + 1)
+                .unwrap_or(0);
+            let label = format!(
+                "Beat {}/{} | {:.0} BPM",
+                beat_number,
+                analysis.beats.len(),
+                section.map_or(analysis.track.tempo, |section| section.tempo)
+            );
+
+            Gauge::default()
+                .block(visualizer_block)
+                .style(
+                    app.user_config.theme.analysis_bar.bg(app
+                        .user_config
+                        .theme
+                        .playbar_background
+                        .fg),
+                )
+                .percent((pulse * 100.0) as u16)
+                .label(&label)
+                .render(f, chunks[1]);
+        }
+    }
+}
+
+// Lists every section, marking where the key, mode or tempo changes
+fn draw_sections<B>(
+    f: &mut Frame<B>,
+    app: &App,
+    analysis: &AudioAnalysis,
+    current_section: Option<&AudioAnalysisSection>,
+    block: Block,
+    layout_chunk: Rect,
+) where
+    B: Backend,
+{
+    let mut previous: Option<&AudioAnalysisSection> = None;
+    let mut current_line = 0;
+    let lines = analysis
+        .sections
+        .iter()
+        .enumerate()
+        .map(|(index, section)| {
+            let mut changes = vec![];
+            if let Some(previous) = previous {
+                if previous.key != section.key
+                    || get_mode_name(previous.mode) != get_mode_name(section.mode)
+                {
+                    changes.push("key change");
+                }
+                if (previous.tempo - section.tempo).abs() >= 1.0 {
+                    changes.push("tempo change");
+                }
+            }
+            previous = Some(section);
+
+            let is_current = current_section.is_some_and(|current| std::ptr::eq(current, section));
+            let style = if is_current {
+                current_line = index;
+                util::with_modifier(app.user_config.theme.selected, Modifier::BOLD)
+            } else {
+                app.user_config.theme.text
+            };
+
+            Text::styled(
+                format!(
+                    "{:>6}  {:<2} {:<5}  {:>5.1} BPM  {}/4  {:>5.1} dB  {}\n",
+                    format_seconds(section.start),
+                    get_key_name(section.key),
+                    get_mode_name(section.mode),
+                    section.tempo,
+                    section.time_signature,
+                    section.loudness,
+                    changes.join(", ")
+                ),
+                style,
+            )
+        })
+        .collect::<Vec<Text>>();
+
+    let middle = layout_chunk.height.saturating_sub(2) / 2;
+    Paragraph::new(lines.iter())
+        .block(block)
+        .style(app.user_config.theme.text)
+        .scroll((current_line as u16).saturating_sub(middle))
+        .render(f, layout_chunk);
+}
+
+fn draw_unavailable<B>(f: &mut Frame<B>, app: &App, block: Block, layout_chunk: Rect)
+where
+    B: Backend,
+{
+    let text = [Text::raw("No segment information available")];
+    Paragraph::new(text.iter())
+        .block(block)
+        .style(app.user_config.theme.text)
+        .render(f, layout_chunk);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start: f32, pitch: f32, loudness_max: f32) -> AudioAnalysisSegment {
+        AudioAnalysisSegment {
+            start,
+            duration: 1.0,
+            confidence: 1.0,
+            loudness_start: -40.0,
+            loudness_max_time: 0.5,
+            loudness_max,
+            loudness_end: None,
+            pitches: vec![pitch],
+            timbre: vec![],
+        }
+    }
+
+    #[test]
+    fn interpolate_segments_test() {
+        let segments = vec![segment(0.0, 0.0, -10.0), segment(1.0, 1.0, -10.0)];
+
+        assert_eq!(
+            interpolate_segments(&segments, 0.0, |s| &s.pitches),
+            vec![0.0]
+        );
+        assert_eq!(
+            interpolate_segments(&segments, 0.25, |s| &s.pitches),
+            vec![0.25]
+        );
+        // The last segment has nothing to blend into
+        assert_eq!(
+            interpolate_segments(&segments, 1.5, |s| &s.pitches),
+            vec![1.0]
+        );
+    }
+
+    #[test]
+    fn get_loudness_test() {
+        let segments = vec![segment(0.0, 0.0, -10.0), segment(1.0, 0.0, -20.0)];
+
+        assert_eq!(get_loudness(&segments, 0.0), -40.0);
+        assert_eq!(get_loudness(&segments, 0.25), -25.0);
+        assert_eq!(get_loudness(&segments, 0.5), -10.0);
+        // Decays towards the start of the next segment
+        assert_eq!(get_loudness(&segments, 0.75), -25.0);
+        assert_eq!(get_loudness(&segments, -1.0), MIN_LOUDNESS);
+    }
+
+    #[test]
+    fn get_beat_pulse_test() {
+        let beats = vec![
+            AudioAnalysisMeasure {
+                start: 1.0,
+                duration: 0.5,
+                confidence: 1.0,
+            },
+            AudioAnalysisMeasure {
+                start: 1.5,
+                duration: 0.5,
+                confidence: 1.0,
+            },
+        ];
+
+        assert_eq!(get_beat_pulse(&beats, 0.5), 0.0);
+        assert_eq!(get_beat_pulse(&beats, 1.0), 1.0);
+        assert_eq!(get_beat_pulse(&beats, 1.25), 0.5);
+        assert_eq!(get_beat_pulse(&beats, 1.5), 1.0);
     }
 }
@@ -0,0 +1,106 @@
+use super::draw_selectable_list;
+use crate::app::{ActiveBlock, App, BrowseSection};
+use tui::{backend::Backend, layout::Rect, Frame};
+
+pub fn draw<B>(f: &mut Frame<B>, app: &App, layout_chunk: Rect)
+where
+    B: Backend,
+{
+    let current_route = app.get_current_route();
+    let highlight_state = (
+        current_route.active_block == ActiveBlock::Browse,
+        current_route.hovered_block == ActiveBlock::Browse,
+    );
+
+    let page = match app.browse.section {
+        BrowseSection::Categories => app
+            .browse
+            .categories
+            .get_results(None)
+            .map(|page| (page.offset, page.total)),
+        BrowseSection::NewReleases => app
+            .browse
+            .new_releases
+            .get_results(None)
+            .map(|page| (page.offset, page.total)),
+        BrowseSection::FeaturedPlaylists => app
+            .browse
+            .featured_playlists
+            .get_results(None)
+            .map(|page| (page.offset, page.total)),
+    };
+
+    let items = app.get_browse_items();
+    let section_title = match (&app.browse.section, &app.browse.featured_message) {
+        (BrowseSection::FeaturedPlaylists, Some(message)) => {
+            format!("{}: {}", app.browse.section.title(), message)
+        }
+        _ => app.browse.section.title().to_string(),
+    };
+    let title = match page {
+        Some((offset, total)) if !items.is_empty() => format!(
+            "Browse - {} ({}-{} of {}, <Tab> to switch)",
+            section_title,
+            offset + 1,
+            offset as usize + items.len(),
+            total
+        ),
+        _ => format!("Browse - {} (<Tab> to switch)", section_title),
+    };
+
+    draw_selectable_list(
+        f,
+        app,
+        layout_chunk,
+        &title,
+        &items,
+        highlight_state,
+        Some(app.browse.selected_index),
+    );
+}
+
+pub fn draw_category_playlists<B>(f: &mut Frame<B>, app: &App, layout_chunk: Rect)
+where
+    B: Backend,
+{
+    let current_route = app.get_current_route();
+    let highlight_state = (
+        current_route.active_block == ActiveBlock::CategoryPlaylists,
+        current_route.hovered_block == ActiveBlock::CategoryPlaylists,
+    );
+
+    let page = app.browse.category_playlists.get_results(None);
+    let items = page
+        .map(|page| {
+            page.items
+                .iter()
+                .map(|playlist| playlist.name.clone())
+                .collect::<Vec<String>>()
+        })
+        .unwrap_or_default();
+    let category_name = app
+        .browse
+        .selected_category
+        .as_ref()
+        .map_or("Category", |category| &category.name);
+    let title = match page {
+        Some(page) if !items.is_empty() => format!(
+            "{} ({}-{} of {})",
+            category_name,
+            page.offset + 1,
+            page.offset as usize + items.len(),
+            page.total
+        ),
+        _ => category_name.to_string(),
+    };
+
+    draw_selectable_list(
+        f,
+        app,
+        layout_chunk,
+        &title,
+        &items,
+        highlight_state,
+        Some(app.browse.category_playlist_index),
+    );
+}
@@ -43,12 +43,34 @@ pub fn get_help_docs() -> Vec<Vec<&'static str>> {
         vec!["Pause/Resume playback", "<Space>", "General"],
         vec!["Enter active mode", "<Enter>", "General"],
         vec!["Go to audio analysis screen", "v", "General"],
+        vec!["Show lyrics of the current track", "y", "General"],
+        vec![
+            "Show details and audio features of the selected track",
+            "i",
+            "Selected block",
+        ],
+        vec!["Open the recommendations builder", "R", "General"],
+        vec![
+            "Toggle radio, which keeps recommendations playing",
+            "o",
+            "General",
+        ],
+        vec![
+            "Add the selected artist or track as a recommendations seed",
+            "S",
+            "Selected block",
+        ],
         vec![
             "Go back or exit when nowhere left to back to",
             "q",
             "General",
         ],
-        vec!["Select device to play music on", "d", "General"],
+        vec![
+            "Select device to play music on (transfers playback)",
+            "d",
+            "General",
+        ],
+        vec!["Switch profile", "P", "General"],
         vec!["Enter hover mode", "<Esc>", "Selected block"],
         vec!["Save track in list or table", "s", "Selected block"],
         vec![
@@ -91,6 +113,64 @@ pub fn get_help_docs() -> Vec<Vec<&'static str>> {
         vec!["Jump to end of playlist", "<Ctrl+e>", "Pagination"],
         vec!["Delete saved album", "D", "Library -> Albums"],
         vec!["Delete saved playist", "D", "Playlist"],
+        vec!["Export playlist to a file", "E", "Playlist"],
+        vec![
+            "Export liked songs to a file",
+            "E",
+            "Library -> Liked Songs",
+        ],
+        vec![
+            "Export every saved album's tracks to a file",
+            "E",
+            "Library -> Albums",
+        ],
         vec!["Follow an artists/playlist", "w", "Search result"],
+        vec!["Next visualizer mode", "l | <Tab>", "Audio analysis"],
+        vec!["Previous visualizer mode", "h", "Audio analysis"],
+        vec!["Jump to visualizer mode", "1-5", "Audio analysis"],
+        vec![
+            "Switch between categories, new releases and featured",
+            "<Tab>",
+            "Browse",
+        ],
+        vec!["Open album, category or playlist", "<Enter>", "Browse"],
+        vec![
+            "Switch between last week, last month and all time",
+            "<Tab>",
+            "Stats",
+        ],
+        vec![
+            "Switch between last 4 weeks, last 6 months and all time",
+            "<Tab>",
+            "Top",
+        ],
+        vec!["Move between top artists and top tracks", "h | l", "Top"],
+        vec![
+            "Open artist or play from the selected track",
+            "<Enter>",
+            "Top",
+        ],
+        vec!["Pick another match", "k | j", "Import"],
+        vec!["Add the selected match", "<Enter>", "Import"],
+        vec!["Skip the track", "s", "Import"],
+        vec!["Switch section", "<Tab>", "Recommendations builder"],
+        vec![
+            "Add the selected genre as a seed",
+            "<Enter>",
+            "Recommendations builder",
+        ],
+        vec!["Remove seed or clear value", "x", "Recommendations builder"],
+        vec![
+            "Select min, target or max",
+            "h | l",
+            "Recommendations builder",
+        ],
+        vec![
+            "Decrease or increase value",
+            "[ | ]",
+            "Recommendations builder",
+        ],
+        vec!["Preview recommendations", "g", "Recommendations builder"],
+        vec!["Save recommendations as a playlist", "w", "Recommendations"],
     ]
 }
@@ -0,0 +1,142 @@
+use super::{
+    draw_table,
+    util::{create_artist_string, get_color, get_percentage_width, millis_to_minutes},
+    TableHeader, TableHeaderItem, TableId, TableItem,
+};
+use crate::app::{ActiveBlock, App};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    widgets::{Block, Borders, Paragraph, Text, Widget},
+    Frame,
+};
+
+pub fn draw<B>(f: &mut Frame<B>, app: &App, layout_chunk: Rect)
+where
+    B: Backend,
+{
+    let import = match &app.import {
+        Some(import) => import,
+        None => return,
+    };
+    let current_route = app.get_current_route();
+    let highlight_state = (
+        current_route.active_block == ActiveBlock::Import,
+        current_route.hovered_block == ActiveBlock::Import,
+    );
+    let title = format!("Import - {}", import.name);
+
+    let item = match import.reviewing() {
+        Some(item) => item,
+        None => {
+            // The report, or a note while the playlist is being created
+            let lines = match &import.report {
+                Some(report) => report.lines(),
+                None => vec!["Creating the playlist...".to_string()],
+            };
+            let text = lines
+                .iter()
+                .map(|line| Text::raw(format!("{}\n", line)))
+                .collect::<Vec<Text>>();
+            Paragraph::new(text.iter())
+                .block(
+                    Block::default()
+                        .title(&title)
+                        .borders(Borders::ALL)
+                        .title_style(get_color(highlight_state, app.user_config.theme))
+                        .border_style(get_color(highlight_state, app.user_config.theme)),
+                )
+                .style(app.user_config.theme.text)
+                .wrap(true)
+                .render(f, layout_chunk);
+            return;
+        }
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(4), Constraint::Min(4)].as_ref())
+        .split(layout_chunk);
+
+    let (reviewed, total) = import.review_progress();
+    let mut wanted = item.entry.describe();
+    if let Some(album) = &item.entry.album {
+        wanted.push_str(&format!(" ({})", album));
+    }
+    if let Some(duration_ms) = item.entry.duration_ms {
+        wanted.push_str(&format!(" {}", millis_to_minutes(u128::from(duration_ms))));
+    }
+    let text = [
+        Text::raw(format!("{}\n", wanted)),
+        Text::styled(
+            "<Enter> to add the selected track, <s> to skip it",
+            app.user_config.theme.inactive,
+        ),
+    ];
+    Paragraph::new(text.iter())
+        .block(
+            Block::default()
+                .title(&format!("Not sure about {} of {}", reviewed, total))
+                .borders(Borders::ALL)
+                .title_style(app.user_config.theme.text)
+                .border_style(app.user_config.theme.inactive),
+        )
+        .style(app.user_config.theme.text)
+        .render(f, chunks[0]);
+
+    let width = chunks[1].width;
+    let header = TableHeader {
+        id: TableId::Import,
+        items: vec![
+            TableHeaderItem {
+                text: "Title",
+                width: get_percentage_width(width, 0.35),
+                ..Default::default()
+            },
+            TableHeaderItem {
+                text: "Artist",
+                width: get_percentage_width(width, 0.25),
+                ..Default::default()
+            },
+            TableHeaderItem {
+                text: "Album",
+                width: get_percentage_width(width, 0.25),
+                ..Default::default()
+            },
+            TableHeaderItem {
+                text: "Length",
+                width: get_percentage_width(width, 0.07),
+                ..Default::default()
+            },
+            TableHeaderItem {
+                text: "Match",
+                width: get_percentage_width(width, 0.07),
+                ..Default::default()
+            },
+        ],
+    };
+    let items = item
+        .candidates
+        .iter()
+        .map(|candidate| TableItem {
+            id: candidate.track.id.clone().unwrap_or_default(),
+            format: vec![
+                candidate.track.name.to_owned(),
+                create_artist_string(&candidate.track.artists),
+                candidate.track.album.name.to_owned(),
+                millis_to_minutes(u128::from(candidate.track.duration_ms)),
+                format!("{:.0}%", candidate.score * 100.0),
+            ],
+        })
+        .collect::<Vec<TableItem>>();
+
+    draw_table(
+        f,
+        app,
+        chunks[1],
+        (&title, &header),
+        &items,
+        import.selected_candidate,
+        highlight_state,
+    );
+}
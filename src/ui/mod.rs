@@ -1,5 +1,14 @@
+pub mod album_art;
 pub mod audio_analysis;
+pub mod browse;
 pub mod help;
+pub mod import;
+pub mod recommendations_builder;
+#[cfg(test)]
+mod snapshot_tests;
+pub mod stats;
+pub mod top;
+pub mod track_info;
 pub mod util;
 use super::{
     app::{
@@ -10,17 +19,18 @@ use super::{
 };
 use help::get_help_docs;
 use rspotify::spotify::senum::RepeatState;
+use std::cmp::max;
 use tui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Modifier, Style},
+    style::Modifier,
     widgets::{Block, Borders, Gauge, Paragraph, Row, SelectableList, Table, Text, Widget},
     Frame,
 };
 use util::{
-    create_artist_string, display_track_progress, get_artist_highlight_state, get_color,
-    get_percentage_width, get_search_results_highlight_state, get_track_progress_percentage,
-    millis_to_minutes,
+    create_artist_string, create_device_string, display_track_progress, get_artist_highlight_state,
+    get_color, get_percentage_width, get_search_results_highlight_state,
+    get_track_progress_percentage, millis_to_minutes, with_modifier,
 };
 
 pub enum TableId {
@@ -30,6 +40,7 @@ pub enum TableId {
     Song,
     RecentlyPlayed,
     MadeForYou,
+    Import,
 }
 
 #[derive(PartialEq)]
@@ -68,6 +79,37 @@ pub struct TableItem {
     format: Vec<String>,
 }
 
+/// Draw the screen for the current route
+pub fn draw<B>(f: &mut Frame<B>, app: &App)
+where
+    B: Backend,
+{
+    match app.get_current_route().active_block {
+        ActiveBlock::HelpMenu => {
+            draw_help_menu(f, app);
+        }
+        ActiveBlock::Error => {
+            draw_error_screen(f, app);
+        }
+        ActiveBlock::SelectDevice => {
+            draw_device_list(f, app);
+        }
+        ActiveBlock::SelectProfile => {
+            draw_profile_list(f, app);
+        }
+        ActiveBlock::Analysis => {
+            audio_analysis::draw(f, app);
+        }
+        ActiveBlock::TrackInfo => {
+            draw_main_layout(f, app);
+            track_info::draw(f, app);
+        }
+        _ => {
+            draw_main_layout(f, app);
+        }
+    }
+}
+
 pub fn draw_help_menu<B>(f: &mut Frame<B>, app: &App)
 where
     B: Backend,
@@ -78,8 +120,8 @@ where
         .margin(2)
         .split(f.size());
 
-    let white = Style::default().fg(app.user_config.theme.text);
-    let gray = Style::default().fg(app.user_config.theme.text);
+    let white = app.user_config.theme.text;
+    let gray = app.user_config.theme.text;
     let header = ["Description", "Event", "Context"];
 
     let help_docs = get_help_docs();
@@ -98,7 +140,7 @@ where
                 .title_style(gray)
                 .border_style(gray),
         )
-        .style(Style::default().fg(app.user_config.theme.text))
+        .style(app.user_config.theme.text)
         .widths(&[
             Constraint::Length(50),
             Constraint::Length(40),
@@ -124,7 +166,13 @@ where
     );
 
     let input_string: String = app.input.iter().collect();
-    Paragraph::new([Text::raw(&input_string)].iter())
+    let input_text = match &app.status_message {
+        Some(message) if input_string.is_empty() => {
+            Text::styled(message, app.user_config.theme.inactive)
+        }
+        _ => Text::raw(&input_string),
+    };
+    Paragraph::new([input_text].iter())
         .block(
             Block::default()
                 .borders(Borders::ALL)
@@ -137,12 +185,12 @@ where
     let block = Block::default()
         .title("Help")
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(app.user_config.theme.inactive))
-        .title_style(Style::default().fg(app.user_config.theme.inactive));
+        .border_style(app.user_config.theme.inactive)
+        .title_style(app.user_config.theme.inactive);
 
     Paragraph::new([Text::raw("Type ?")].iter())
         .block(block)
-        .style(Style::default().fg(app.user_config.theme.inactive))
+        .style(app.user_config.theme.inactive)
         .render(f, chunks[1]);
 }
 
@@ -151,13 +199,18 @@ where
     B: Backend,
 {
     let margin = util::get_main_layout_margin(app);
+    let playbar_height = if app.user_config.behavior.show_album_art {
+        max(6, app.user_config.behavior.album_art_size / 2)
+    } else {
+        6
+    };
     let parent_layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints(
             [
                 Constraint::Length(3),
                 Constraint::Min(1),
-                Constraint::Length(6),
+                Constraint::Length(playbar_height),
             ]
             .as_ref(),
         )
@@ -221,8 +274,30 @@ where
         RouteId::Recommendations => {
             draw_recommendations_table(f, app, chunks[1]);
         }
+        RouteId::Lyrics => {
+            draw_lyrics(f, app, chunks[1]);
+        }
+        RouteId::Browse => {
+            browse::draw(f, app, chunks[1]);
+        }
+        RouteId::CategoryPlaylists => {
+            browse::draw_category_playlists(f, app, chunks[1]);
+        }
+        RouteId::RecommendationsBuilder => {
+            recommendations_builder::draw(f, app, chunks[1]);
+        }
+        RouteId::Stats => {
+            stats::draw(f, app, chunks[1]);
+        }
+        RouteId::Top => {
+            top::draw(f, app, chunks[1]);
+        }
+        RouteId::Import => {
+            import::draw(f, app, chunks[1]);
+        }
         RouteId::Error => {} // This is handled as a "full screen" route in main.rs
         RouteId::SelectedDevice => {} // This is handled as a "full screen" route in main.rs
+        RouteId::SelectProfile => {} // This is handled as a "full screen" route in main.rs
         RouteId::Analysis => {} // This is handled as a "full screen" route in main.rs
     };
 }
@@ -445,6 +520,29 @@ pub fn draw_album_table<B>(f: &mut Frame<B>, app: &App, layout_chunk: Rect)
 where
     B: Backend,
 {
+    let layout_chunk = match (
+        app.user_config.behavior.show_album_art,
+        app.get_selected_album_art_url(),
+    ) {
+        (true, Some(url)) => {
+            let album_art_size = app.user_config.behavior.album_art_size;
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Min(1), Constraint::Length(album_art_size + 2)].as_ref())
+                .split(layout_chunk);
+
+            let art_chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(album_art_size / 2), Constraint::Min(0)].as_ref())
+                .margin(1)
+                .split(chunks[1]);
+
+            album_art::draw(f, app, url, art_chunks[0]);
+            chunks[0]
+        }
+        _ => layout_chunk,
+    };
+
     let header = TableHeader {
         id: TableId::Album,
         items: vec![
@@ -610,6 +708,10 @@ where
             "Recommendations based on Artist \'{}\'",
             &app.recommendations_seed
         ),
+        Some(RecommendationsContext::Builder) => format!(
+            "Recommendations based on {} (press w to save as a playlist)",
+            &app.recommendations_seed
+        ),
         None => "Recommendations".to_string(),
     };
     draw_table(
@@ -695,6 +797,28 @@ pub fn draw_playbar<B>(f: &mut Frame<B>, app: &App, layout_chunk: Rect)
 where
     B: Backend,
 {
+    let layout_chunk = match (
+        app.user_config.behavior.show_album_art,
+        app.get_playing_album_art_url(),
+    ) {
+        (true, Some(url)) => {
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(
+                    [
+                        Constraint::Length(app.user_config.behavior.album_art_size),
+                        Constraint::Min(1),
+                    ]
+                    .as_ref(),
+                )
+                .split(layout_chunk);
+
+            album_art::draw(f, app, url, chunks[0]);
+            chunks[1]
+        }
+        _ => layout_chunk,
+    };
+
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
@@ -723,13 +847,16 @@ where
                 RepeatState::Context => "All",
             };
 
+            let radio_text = if app.radio { " | Radio: On" } else { "" };
+
             let title = format!(
-                "{:-7} ({} | Shuffle: {:-3} | Repeat: {:-5} | Volume: {:-2}%)",
+                "{:-7} ({} | Shuffle: {:-3} | Repeat: {:-5} | Volume: {:-2}%{})",
                 play_title,
                 current_playback_context.device.name,
                 shuffle_text,
                 repeat_text,
-                current_playback_context.device.volume_percent
+                current_playback_context.device.volume_percent,
+                radio_text
             );
 
             let current_route = app.get_current_route();
@@ -757,29 +884,32 @@ where
             Paragraph::new(
                 [Text::styled(
                     create_artist_string(&track_item.artists),
-                    Style::default().fg(app.user_config.theme.text),
+                    app.user_config.theme.text,
                 )]
                 .iter(),
             )
-            .style(Style::default().fg(app.user_config.theme.text))
+            .style(app.user_config.theme.text)
             .block(
-                Block::default().title(&track_name).title_style(
-                    Style::default()
-                        .fg(app.user_config.theme.selected)
-                        .modifier(Modifier::BOLD),
-                ),
+                Block::default()
+                    .title(&track_name)
+                    .title_style(with_modifier(
+                        app.user_config.theme.selected,
+                        Modifier::BOLD,
+                    )),
             )
             .render(f, chunks[0]);
             let perc = get_track_progress_percentage(app.song_progress_ms, track_item.duration_ms);
 
             Gauge::default()
                 .block(Block::default().title(""))
-                .style(
-                    Style::default()
-                        .fg(app.user_config.theme.playbar_progress)
-                        .bg(app.user_config.theme.playbar_background)
-                        .modifier(Modifier::ITALIC | Modifier::BOLD),
-                )
+                .style(with_modifier(
+                    app.user_config.theme.playbar_progress.bg(app
+                        .user_config
+                        .theme
+                        .playbar_background
+                        .fg),
+                    Modifier::ITALIC | Modifier::BOLD,
+                ))
                 .percent(perc)
                 .label(&display_track_progress(
                     app.song_progress_ms,
@@ -802,7 +932,7 @@ where
 
     let mut playing_text = vec![
         Text::raw("Api response: "),
-        Text::styled(&app.api_error, Style::default().fg(app.user_config.theme.error_text)),
+        Text::styled(&app.api_error, app.user_config.theme.error_text),
         Text::styled(
             "
 
@@ -811,34 +941,34 @@ If you are trying to play a track, please check that
     2. Your playback device is active and selected - press `d` to go to device selection menu
     3. If you're using spotifyd as a playback device, your device name must not contain spaces
             ",
-            Style::default().fg(app.user_config.theme.text),
+            app.user_config.theme.text,
         ),
         Text::styled("
 Hint: a playback device must be either an official spotify client or a light weight alternative such as spotifyd
         ",
-        Style::default().fg(app.user_config.theme.hint)),
+        app.user_config.theme.hint),
         Text::styled(
             "\nPress <Esc> to return",
-            Style::default().fg(app.user_config.theme.inactive),
+            app.user_config.theme.inactive,
         ),
     ];
 
     if app.client_config.device_id.is_none() {
         playing_text.push(Text::styled(
             "\nNo playback device is selected - follow point 2 above",
-            Style::default().fg(app.user_config.theme.hint),
+            app.user_config.theme.hint,
         ))
     }
 
     Paragraph::new(playing_text.iter())
         .wrap(true)
-        .style(Style::default().fg(app.user_config.theme.text))
+        .style(app.user_config.theme.text)
         .block(
             Block::default()
                 .borders(Borders::ALL)
                 .title("Error")
-                .title_style(Style::default().fg(app.user_config.theme.error_border))
-                .border_style(Style::default().fg(app.user_config.theme.error_border)),
+                .title_style(app.user_config.theme.error_border)
+                .border_style(app.user_config.theme.error_border),
         )
         .render(f, chunks[0]);
 }
@@ -876,31 +1006,118 @@ where
         changelog.replace("\n## [Unreleased]\n", "")
     };
 
-    let top_text = vec![Text::styled(
-        BANNER,
-        Style::default().fg(app.user_config.theme.banner),
-    )];
+    let top_text = [Text::styled(BANNER, app.user_config.theme.banner)];
 
-    let bottom_text = vec![
+    let bottom_text = [
         Text::raw("\nPlease report any bugs or missing features to https://github.com/Rigellute/spotify-tui\n\n"),
         Text::raw(clean_changelog)
     ];
 
     // Contains the banner
     Paragraph::new(top_text.iter())
-        .style(Style::default().fg(app.user_config.theme.text))
+        .style(app.user_config.theme.text)
         .block(Block::default())
         .render(f, chunks[0]);
 
     // CHANGELOG
     Paragraph::new(bottom_text.iter())
-        .style(Style::default().fg(app.user_config.theme.text))
+        .style(app.user_config.theme.text)
         .block(Block::default())
         .wrap(true)
         .scroll(app.home_scroll)
         .render(f, chunks[1]);
 }
 
+fn draw_lyrics<B>(f: &mut Frame<B>, app: &App, layout_chunk: Rect)
+where
+    B: Backend,
+{
+    let current_route = app.get_current_route();
+    let highlight_state = (
+        current_route.active_block == ActiveBlock::Lyrics,
+        current_route.hovered_block == ActiveBlock::Lyrics,
+    );
+
+    let track = app
+        .current_playback_context
+        .as_ref()
+        .and_then(|context| context.item.as_ref());
+    let title = match track {
+        Some(track) => format!(
+            "Lyrics - {} by {}",
+            track.name,
+            create_artist_string(&track.artists)
+        ),
+        None => "Lyrics".to_string(),
+    };
+
+    let block = Block::default()
+        .title(&title)
+        .borders(Borders::ALL)
+        .title_style(get_color(highlight_state, app.user_config.theme))
+        .border_style(get_color(highlight_state, app.user_config.theme));
+
+    match &app.lyrics {
+        Some(lyrics) if lyrics.is_synced() => {
+            let current_line = lyrics.get_current_line_index(app.song_progress_ms);
+            let lines = lyrics
+                .lines
+                .iter()
+                .enumerate()
+                .map(|(index, line)| {
+                    let style = if Some(index) == current_line {
+                        with_modifier(app.user_config.theme.selected, Modifier::BOLD)
+                    } else {
+                        app.user_config.theme.text
+                    };
+                    Text::styled(format!("{}\n", line.text), style)
+                })
+                .collect::<Vec<Text>>();
+
+            // Keep the current line in the middle of the block
+            let middle = layout_chunk.height.saturating_sub(2) / 2;
+            let scroll = current_line
+                .map(|index| (index as u16).saturating_sub(middle))
+                .unwrap_or(0);
+
+            Paragraph::new(lines.iter())
+                .block(block)
+                .style(app.user_config.theme.text)
+                .scroll(scroll)
+                .render(f, layout_chunk);
+        }
+        Some(lyrics) => {
+            let text = lyrics
+                .lines
+                .iter()
+                .map(|line| line.text.as_str())
+                .collect::<Vec<&str>>()
+                .join("\n");
+
+            Paragraph::new([Text::raw(text)].iter())
+                .block(block)
+                .style(app.user_config.theme.text)
+                .wrap(true)
+                .scroll(app.lyrics_scroll)
+                .render(f, layout_chunk);
+        }
+        None => {
+            let text = if app.is_loading_lyrics() {
+                "Looking up lyrics..."
+            } else if track.is_some() {
+                "No lyrics found for this track"
+            } else {
+                "Nothing is playing"
+            };
+
+            Paragraph::new([Text::raw(text)].iter())
+                .block(block)
+                .style(app.user_config.theme.inactive)
+                .render(f, layout_chunk);
+        }
+    }
+}
+
 fn draw_not_implemented_yet<B>(
     f: &mut Frame<B>,
     app: &App,
@@ -921,10 +1138,10 @@ fn draw_not_implemented_yet<B>(
         .title_style(get_color(highlight_state, app.user_config.theme))
         .border_style(get_color(highlight_state, app.user_config.theme));
 
-    let text = vec![Text::raw("Not implemented yet!")];
+    let text = [Text::raw("Not implemented yet!")];
 
     Paragraph::new(text.iter())
-        .style(Style::default().fg(app.user_config.theme.text))
+        .style(app.user_config.theme.text)
         .block(display_block)
         .wrap(true)
         .render(f, layout_chunk);
@@ -1008,25 +1225,30 @@ where
         .margin(5)
         .split(f.size());
 
-    let device_instructions = vec![
+    let device_instructions = [
         "To play tracks, please select a device.",
         "Use `j/k` or up/down arrow keys to move up and down and <Enter> to select",
         "Your choice here will be cached so you can jump straight back in when you next open `spotify-tui`.",
-        "You can change the playback device at any time by pressing `d`.",
+        "You can change the playback device at any time by pressing `d`, anything playing will move to the new device.",
     ];
 
-    Paragraph::new([Text::raw(device_instructions.join("\n"))].iter())
-        .style(Style::default().fg(app.user_config.theme.text))
+    let mut instructions = vec![];
+    if app.missing_device_id.is_some() {
+        instructions.push(Text::styled(
+            "Your saved device is no longer available. Pick another device, or wait and playback will reconnect when it comes back.\n",
+            app.user_config.theme.error_text,
+        ));
+    }
+    instructions.push(Text::raw(device_instructions.join("\n")));
+
+    Paragraph::new(instructions.iter())
+        .style(app.user_config.theme.text)
         .wrap(true)
         .block(
             Block::default()
                 .borders(Borders::NONE)
                 .title("Welcome to spotify-tui!")
-                .title_style(
-                    Style::default()
-                        .fg(app.user_config.theme.active)
-                        .modifier(Modifier::BOLD),
-                ),
+                .title_style(with_modifier(app.user_config.theme.active, Modifier::BOLD)),
         )
         .render(f, chunks[0]);
 
@@ -1037,11 +1259,7 @@ where
             if items.devices.is_empty() {
                 no_device_message
             } else {
-                items
-                    .devices
-                    .iter()
-                    .map(|device| device.name.to_owned())
-                    .collect()
+                items.devices.iter().map(create_device_string).collect()
             }
         }
         None => no_device_message,
@@ -1052,17 +1270,68 @@ where
             Block::default()
                 .title("Devices")
                 .borders(Borders::ALL)
-                .title_style(Style::default().fg(app.user_config.theme.active))
-                .border_style(Style::default().fg(app.user_config.theme.inactive)),
+                .title_style(app.user_config.theme.active)
+                .border_style(app.user_config.theme.inactive),
         )
         .items(&items)
-        .style(Style::default().fg(app.user_config.theme.text))
+        .style(app.user_config.theme.text)
         .select(app.selected_device_index)
-        .highlight_style(
-            Style::default()
-                .fg(app.user_config.theme.active)
-                .modifier(Modifier::BOLD),
+        .highlight_style(with_modifier(app.user_config.theme.active, Modifier::BOLD))
+        .render(f, chunks[1]);
+}
+
+pub fn draw_profile_list<B>(f: &mut Frame<B>, app: &App)
+where
+    B: Backend,
+{
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(20), Constraint::Percentage(80)].as_ref())
+        .margin(5)
+        .split(f.size());
+
+    let profile_instructions = [
+        "Each profile has its own Spotify login, device and config.yml overrides.",
+        "Use `j/k` or up/down arrow keys to move up and down and <Enter> to switch.",
+        "To add a profile run `spt --profile <name>` once to set up its client and log in.",
+    ];
+
+    Paragraph::new([Text::raw(profile_instructions.join("\n"))].iter())
+        .style(app.user_config.theme.text)
+        .wrap(true)
+        .block(
+            Block::default()
+                .borders(Borders::NONE)
+                .title("Switch profile")
+                .title_style(with_modifier(app.user_config.theme.active, Modifier::BOLD)),
+        )
+        .render(f, chunks[0]);
+
+    let current_profile = app.client_config.get_profile_name();
+    let items = app
+        .profiles
+        .iter()
+        .map(|profile| {
+            if profile == current_profile {
+                format!("{} (current)", profile)
+            } else {
+                profile.to_owned()
+            }
+        })
+        .collect::<Vec<String>>();
+
+    SelectableList::default()
+        .block(
+            Block::default()
+                .title("Profiles")
+                .borders(Borders::ALL)
+                .title_style(app.user_config.theme.active)
+                .border_style(app.user_config.theme.inactive),
         )
+        .items(&items)
+        .style(app.user_config.theme.text)
+        .select(Some(app.selected_profile_index))
+        .highlight_style(with_modifier(app.user_config.theme.active, Modifier::BOLD))
         .render(f, chunks[1]);
 }
 
@@ -1255,9 +1524,12 @@ fn draw_selectable_list<B, S>(
                 .border_style(get_color(highlight_state, app.user_config.theme)),
         )
         .items(items)
-        .style(Style::default().fg(app.user_config.theme.text))
+        .style(app.user_config.theme.text)
         .select(selected_index)
-        .highlight_style(get_color(highlight_state, app.user_config.theme).modifier(Modifier::BOLD))
+        .highlight_style(with_modifier(
+            get_color(highlight_state, app.user_config.theme),
+            Modifier::BOLD,
+        ))
         .render(f, layout_chunk);
 }
 
@@ -1272,7 +1544,10 @@ fn draw_table<B>(
 ) where
     B: Backend,
 {
-    let selected_style = get_color(highlight_state, app.user_config.theme).modifier(Modifier::BOLD);
+    let selected_style = with_modifier(
+        get_color(highlight_state, app.user_config.theme),
+        Modifier::BOLD,
+    );
 
     let track_playing_index = match &app.current_playback_context {
         Some(ctx) => items.iter().position(|t| match &ctx.item {
@@ -1295,7 +1570,7 @@ fn draw_table<B>(
 
     let rows = items.iter().skip(offset).enumerate().map(|(i, item)| {
         let mut formatted_row = item.format.clone();
-        let mut style = Style::default().fg(app.user_config.theme.text); // default styling
+        let mut style = app.user_config.theme.text; // default styling
 
         // if table displays songs
         match header.id {
@@ -1307,9 +1582,7 @@ fn draw_table<B>(
                     {
                         if i == track_playing_offset_index {
                             formatted_row[title_idx] = format!("|> {}", &formatted_row[title_idx]);
-                            style = Style::default()
-                                .fg(app.user_config.theme.active)
-                                .modifier(Modifier::BOLD);
+                            style = with_modifier(app.user_config.theme.active, Modifier::BOLD);
                         }
                     }
                 }
@@ -1343,12 +1616,12 @@ fn draw_table<B>(
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .style(Style::default().fg(app.user_config.theme.text))
+                .style(app.user_config.theme.text)
                 .title(title)
                 .title_style(get_color(highlight_state, app.user_config.theme))
                 .border_style(get_color(highlight_state, app.user_config.theme)),
         )
-        .style(Style::default().fg(app.user_config.theme.text))
+        .style(app.user_config.theme.text)
         .widths(&widths)
         .render(f, layout_chunk);
 }
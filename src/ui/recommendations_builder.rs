@@ -0,0 +1,149 @@
+use super::{
+    draw_selectable_list,
+    util::{get_color, with_modifier},
+};
+use crate::{
+    app::{ActiveBlock, App},
+    recommendations::{BuilderSection, BOUNDS, MAX_SEEDS, TUNABLES},
+};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Modifier,
+    widgets::{Block, Borders, Paragraph, Row, Table, Text, Widget},
+    Frame,
+};
+
+pub fn draw<B>(f: &mut Frame<B>, app: &App, layout_chunk: Rect)
+where
+    B: Backend,
+{
+    let builder = &app.recommendations_builder;
+    let current_route = app.get_current_route();
+    let highlight_state = |section: BuilderSection| {
+        (
+            current_route.active_block == ActiveBlock::RecommendationsBuilder
+                && builder.section == section,
+            current_route.hovered_block == ActiveBlock::RecommendationsBuilder,
+        )
+    };
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)].as_ref())
+        .split(layout_chunk);
+
+    let left = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(MAX_SEEDS as u16 + 2), Constraint::Min(3)].as_ref())
+        .split(columns[0]);
+
+    let right = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Length(TUNABLES.len() as u16 + 3),
+                Constraint::Min(3),
+            ]
+            .as_ref(),
+        )
+        .split(columns[1]);
+
+    let seeds = builder
+        .seeds
+        .iter()
+        .map(|seed| format!("{}: {}", seed.kind(), seed.name()))
+        .collect::<Vec<String>>();
+    draw_selectable_list(
+        f,
+        app,
+        left[0],
+        &format!("Seeds ({}/{})", builder.seeds.len(), MAX_SEEDS),
+        &seeds,
+        highlight_state(BuilderSection::Seeds),
+        Some(builder.selected_seed_index),
+    );
+
+    draw_selectable_list(
+        f,
+        app,
+        left[1],
+        "Genres",
+        &builder.genres,
+        highlight_state(BuilderSection::Genres),
+        Some(builder.selected_genre_index),
+    );
+
+    let tunables_highlight = highlight_state(BuilderSection::Tunables);
+    let is_tunables_active = tunables_highlight.0;
+    let rows =
+        TUNABLES
+            .iter()
+            .zip(builder.values.iter())
+            .enumerate()
+            .map(|(index, (tunable, values))| {
+                let is_selected_row = index == builder.selected_tunable_index;
+                let mut cells = vec![tunable.title().to_string()];
+                cells.extend(BOUNDS.iter().map(|bound| {
+                    let value = values
+                        .get(*bound)
+                        .map_or_else(|| "-".to_string(), |value| tunable.format(value));
+                    if is_selected_row && is_tunables_active && *bound == builder.selected_bound {
+                        format!("[{}]", value)
+                    } else {
+                        value
+                    }
+                }));
+
+                let style = if is_selected_row && is_tunables_active {
+                    with_modifier(
+                        get_color(tunables_highlight, app.user_config.theme),
+                        Modifier::BOLD,
+                    )
+                } else {
+                    app.user_config.theme.text
+                };
+                Row::StyledData(cells.into_iter(), style)
+            });
+
+    let mut header = vec!["Attribute"];
+    header.extend(BOUNDS.iter().map(|bound| bound.title()));
+    Table::new(header.into_iter(), rows)
+        .block(
+            Block::default()
+                .title("Tune")
+                .borders(Borders::ALL)
+                .title_style(get_color(tunables_highlight, app.user_config.theme))
+                .border_style(get_color(tunables_highlight, app.user_config.theme)),
+        )
+        .style(app.user_config.theme.text)
+        .widths(&[
+            Constraint::Percentage(40),
+            Constraint::Percentage(20),
+            Constraint::Percentage(20),
+            Constraint::Percentage(20),
+        ])
+        .render(f, right[0]);
+
+    let hints = [
+        Text::styled("<Tab>", app.user_config.theme.hint),
+        Text::raw(" switch section  "),
+        Text::styled("<Enter>", app.user_config.theme.hint),
+        Text::raw(" add genre  "),
+        Text::styled("x", app.user_config.theme.hint),
+        Text::raw(" remove seed or clear value  "),
+        Text::styled("[ ]", app.user_config.theme.hint),
+        Text::raw(" adjust value  "),
+        Text::styled("g", app.user_config.theme.hint),
+        Text::raw(" preview recommendations"),
+    ];
+    Paragraph::new(hints.iter())
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(app.user_config.theme.inactive),
+        )
+        .style(app.user_config.theme.text)
+        .wrap(true)
+        .render(f, right[1]);
+}
@@ -0,0 +1,245 @@
+//! Renders scripted sessions with `TestBackend` and compares the screen with the text snapshots
+//! in `src/ui/snapshots`. After an intended layout change, run
+//! `UPDATE_SNAPSHOTS=1 cargo test snapshot` and review the snapshot diff along with the code.
+use super::draw;
+use crate::{
+    app::{ActiveBlock, App},
+    event::Key,
+    handlers,
+    history::{History, Play},
+    spotify_api::fake::{self, FakeSpotify},
+};
+use serde_json::json;
+use std::{env, fs, path::PathBuf, rc::Rc};
+use tui::{backend::TestBackend, buffer::Buffer, layout::Rect, Terminal};
+
+const WIDTH: u16 = 100;
+const HEIGHT: u16 = 30;
+
+// A logged in user with two playlists, listening to a track on their laptop
+fn fixture_app() -> (App, Rc<FakeSpotify>) {
+    let (mut app, spotify) = fake::app_with_fake();
+    spotify
+        .respond(
+            "device",
+            json!({ "devices": [
+                fake::device("laptop", "Laptop", true),
+                fake::device("phone", "Phone", false),
+            ] }),
+        )
+        .respond(
+            "search_track",
+            json!({ "tracks": fake::page(vec![
+                fake::track("1", "Song", "Band"),
+                fake::track("2", "Another Song", "Other Band"),
+            ]) }),
+        )
+        .respond("search_artist", json!({ "artists": fake::page(vec![]) }))
+        .respond(
+            "search_album",
+            json!({ "albums": fake::page(vec![fake::album("a", "Record", "Band")]) }),
+        )
+        .respond(
+            "user_playlist_tracks",
+            fake::page(vec![
+                json!({
+                    "added_at": "2020-01-01T00:00:00Z",
+                    "added_by": null,
+                    "is_local": false,
+                    "track": fake::track("1", "Song", "Band"),
+                }),
+                json!({
+                    "added_at": "2020-01-02T00:00:00Z",
+                    "added_by": null,
+                    "is_local": false,
+                    "track": fake::track("3", "Slow Song", "Quiet Band"),
+                }),
+            ]),
+        )
+        .respond(
+            "search_playlist",
+            json!({ "playlists": fake::page(vec![fake::playlist("p3", "Songs", "bob")]) }),
+        );
+
+    app.size = Rect::new(0, 0, WIDTH, HEIGHT);
+    app.user = serde_json::from_value(fake::user("alice")).ok();
+    app.playlists = serde_json::from_value(fake::page(vec![
+        fake::playlist("p1", "Chill", "alice"),
+        fake::playlist("p2", "Workout", "alice"),
+    ]))
+    .ok();
+    app.selected_playlist_index = Some(0);
+    app.current_playback_context = serde_json::from_value(fake::playing_context(
+        fake::track("1", "Song", "Band"),
+        fake::device("laptop", "Laptop", true),
+        true,
+    ))
+    .ok();
+    app.song_progress_ms = 60_000;
+
+    (app, spotify)
+}
+
+// Dispatch keys like the main loop does, which sends everything to the search input while it
+// is focused
+fn press(app: &mut App, keys: &[Key]) {
+    for key in keys {
+        if app.get_current_route().active_block == ActiveBlock::Input {
+            handlers::input_handler(*key, app);
+        } else {
+            handlers::handle_app(*key, app);
+        }
+    }
+}
+
+fn type_text(app: &mut App, text: &str) {
+    let keys: Vec<Key> = text.chars().map(Key::Char).collect();
+    press(app, &keys);
+}
+
+fn render(app: &App) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+    terminal.draw(|mut f| draw(&mut f, app)).unwrap();
+    buffer_to_string(terminal.backend().buffer())
+}
+
+fn buffer_to_string(buffer: &Buffer) -> String {
+    let area = buffer.area();
+    let mut output = String::new();
+    for y in area.top()..area.bottom() {
+        let line: String = (area.left()..area.right())
+            .map(|x| buffer.get(x, y).symbol.as_str())
+            .collect();
+        output.push_str(line.trim_end());
+        output.push('\n');
+    }
+    output
+}
+
+fn assert_snapshot(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("src/ui/snapshots")
+        .join(format!("{}.txt", name));
+
+    if env::var("UPDATE_SNAPSHOTS").is_ok() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "Missing snapshot {}, run with UPDATE_SNAPSHOTS=1 to create it",
+            path.display()
+        )
+    });
+    if expected != actual {
+        let expected_lines: Vec<&str> = expected.lines().collect();
+        let actual_lines: Vec<&str> = actual.lines().collect();
+        let mut diff = String::new();
+        for i in 0..expected_lines.len().max(actual_lines.len()) {
+            let (old, new) = (expected_lines.get(i), actual_lines.get(i));
+            if old != new {
+                diff.push_str(&format!(
+                    "line {}\n- {}\n+ {}\n",
+                    i + 1,
+                    old.unwrap_or(&""),
+                    new.unwrap_or(&"")
+                ));
+            }
+        }
+        panic!(
+            "Snapshot {} does not match, run with UPDATE_SNAPSHOTS=1 if the change is intended\n{}",
+            name, diff
+        );
+    }
+}
+
+#[test]
+fn snapshot_playlist_tracks() {
+    let (mut app, spotify) = fixture_app();
+    // From the library down to the playlists, then open the first one
+    press(&mut app, &[Key::Down, Key::Enter, Key::Enter]);
+
+    assert_eq!(spotify.calls_to("user_playlist_tracks").len(), 1);
+    assert_eq!(
+        app.get_current_route().active_block,
+        ActiveBlock::TrackTable
+    );
+    assert_snapshot("playlist_tracks", &render(&app));
+}
+
+#[test]
+fn snapshot_help_menu() {
+    let (mut app, _) = fixture_app();
+    press(&mut app, &[Key::Char('?')]);
+    assert_eq!(app.get_current_route().active_block, ActiveBlock::HelpMenu);
+    assert_snapshot("help_menu", &render(&app));
+}
+
+#[test]
+fn snapshot_search_results() {
+    let (mut app, spotify) = fixture_app();
+    press(&mut app, &[Key::Char('/')]);
+    type_text(&mut app, "song");
+    press(&mut app, &[Key::Enter]);
+
+    assert_eq!(spotify.calls_to("search_track").len(), 1);
+    assert_snapshot("search_results", &render(&app));
+}
+
+#[test]
+fn snapshot_device_list() {
+    let (mut app, _) = fixture_app();
+    press(&mut app, &[Key::Char('d'), Key::Down]);
+    assert_eq!(
+        app.get_current_route().active_block,
+        ActiveBlock::SelectDevice
+    );
+    assert_snapshot("device_list", &render(&app));
+}
+
+#[test]
+fn snapshot_error_screen() {
+    let (mut app, _) = fixture_app();
+    app.handle_error(failure::err_msg("Something went wrong"));
+    assert_snapshot("error_screen", &render(&app));
+}
+
+#[test]
+fn snapshot_stats() {
+    let (mut app, _) = fixture_app();
+    let history = History::open_in_memory().unwrap();
+    // Around midday UTC, so the day is the same in every time zone the tests run in
+    let day = 1_600_000_000;
+    for (track_id, name, started_at, skipped) in &[
+        ("1", "Song", day, false),
+        ("1", "Song", day + 600, false),
+        ("2", "Other Song", day + 1200, true),
+        ("1", "Song", day + 86_400, false),
+    ] {
+        history
+            .record(&Play {
+                track_id: track_id.to_string(),
+                track_name: name.to_string(),
+                artist_id: None,
+                artist_name: "Band".to_string(),
+                album_id: None,
+                album_name: "Album".to_string(),
+                context_uri: None,
+                device_name: "Laptop".to_string(),
+                started_at: *started_at,
+                duration_ms: 180_000,
+                listened_ms: if *skipped { 20_000 } else { 180_000 },
+                skipped: *skipped,
+            })
+            .unwrap();
+    }
+    app.history = Some(history);
+    app.get_stats();
+    // All time, as the plays are in the past
+    press(&mut app, &[Key::Tab, Key::Tab]);
+
+    assert_eq!(app.get_current_route().active_block, ActiveBlock::Stats);
+    assert_snapshot("stats", &render(&app));
+}
@@ -0,0 +1,158 @@
+use super::util::{get_color, millis_to_minutes};
+use crate::{
+    app::{ActiveBlock, App},
+    history::TopItem,
+};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    widgets::{BarChart, Block, Borders, Paragraph, Text, Widget},
+    Frame,
+};
+
+// Wide enough for the MM-DD labels under the bars
+const DAY_BAR_WIDTH: u16 = 5;
+
+pub fn draw<B>(f: &mut Frame<B>, app: &App, layout_chunk: Rect)
+where
+    B: Backend,
+{
+    let current_route = app.get_current_route();
+    let highlight_state = (
+        current_route.active_block == ActiveBlock::Stats,
+        current_route.hovered_block == ActiveBlock::Stats,
+    );
+    let title = format!("Stats - {} (<Tab> to switch)", app.stats_period.title());
+    let mut block = Block::default()
+        .title(&title)
+        .borders(Borders::ALL)
+        .title_style(get_color(highlight_state, app.user_config.theme))
+        .border_style(get_color(highlight_state, app.user_config.theme));
+
+    let stats = match (&app.history, &app.stats) {
+        (Some(_), Some(stats)) if stats.plays > 0 => stats,
+        (history, _) => {
+            let text = if history.is_some() {
+                "Nothing played yet, plays show up here once they finish"
+            } else {
+                "The listening history is turned off, see `listening_history` in config.yml"
+            };
+            Paragraph::new([Text::raw(text)].iter())
+                .block(block)
+                .style(app.user_config.theme.inactive)
+                .wrap(true)
+                .render(f, layout_chunk);
+            return;
+        }
+    };
+
+    let inner = block.inner(layout_chunk);
+    block.render(f, layout_chunk);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Length(1),
+                Constraint::Min(4),
+                Constraint::Length(8),
+            ]
+            .as_ref(),
+        )
+        .split(inner);
+
+    let summary = format!(
+        "{} plays, {} listened, {:.0}% skipped",
+        stats.plays,
+        format_listening_time(stats.listened_ms),
+        stats.skip_rate() * 100.0
+    );
+    Paragraph::new([Text::raw(summary)].iter())
+        .style(app.user_config.theme.text)
+        .render(f, chunks[0]);
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage(34),
+                Constraint::Percentage(33),
+                Constraint::Percentage(33),
+            ]
+            .as_ref(),
+        )
+        .split(chunks[1]);
+    draw_top_items(f, app, columns[0], "Top tracks", &stats.top_tracks);
+    draw_top_items(f, app, columns[1], "Top artists", &stats.top_artists);
+    draw_top_items(f, app, columns[2], "Top albums", &stats.top_albums);
+
+    // As many of the latest days as fit
+    let days_shown = (chunks[2].width.saturating_sub(2) / (DAY_BAR_WIDTH + 1)).max(1) as usize;
+    let days =
+        &stats.daily_listening_ms[stats.daily_listening_ms.len().saturating_sub(days_shown)..];
+    let data = days
+        .iter()
+        .map(|(day, listened_ms)| (day.get(5..).unwrap_or(day), listened_ms / 60_000))
+        .collect::<Vec<(&str, u64)>>();
+    BarChart::default()
+        .block(
+            Block::default()
+                .title("Minutes per day")
+                .borders(Borders::ALL)
+                .border_style(app.user_config.theme.inactive)
+                .title_style(app.user_config.theme.text),
+        )
+        .data(&data)
+        .bar_width(DAY_BAR_WIDTH)
+        .style(app.user_config.theme.analysis_bar)
+        .value_style(
+            app.user_config
+                .theme
+                .analysis_bar_text
+                .bg(app.user_config.theme.analysis_bar.fg),
+        )
+        .render(f, chunks[2]);
+}
+
+fn draw_top_items<B>(
+    f: &mut Frame<B>,
+    app: &App,
+    layout_chunk: Rect,
+    title: &str,
+    items: &[TopItem],
+) where
+    B: Backend,
+{
+    let lines = items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let name = if item.artist_name.is_empty() {
+                item.name.clone()
+            } else {
+                format!("{} - {}", item.name, item.artist_name)
+            };
+            Text::raw(format!("{}. {} ({})\n", index + 1, name, item.plays))
+        })
+        .collect::<Vec<Text>>();
+
+    Paragraph::new(lines.iter())
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(app.user_config.theme.inactive)
+                .title_style(app.user_config.theme.text),
+        )
+        .style(app.user_config.theme.text)
+        .render(f, layout_chunk);
+}
+
+// Hours and minutes once it is over an hour, e.g. 12h 05m
+fn format_listening_time(listened_ms: u64) -> String {
+    let minutes = listened_ms / 60_000;
+    if minutes < 60 {
+        millis_to_minutes(u128::from(listened_ms))
+    } else {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    }
+}
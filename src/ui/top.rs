@@ -0,0 +1,60 @@
+use super::{draw_selectable_list, util::create_artist_string};
+use crate::app::{ActiveBlock, App, TopSection};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    Frame,
+};
+
+pub fn draw<B>(f: &mut Frame<B>, app: &App, layout_chunk: Rect)
+where
+    B: Backend,
+{
+    let current_route = app.get_current_route();
+    let highlight_state = |section| {
+        (
+            current_route.active_block == ActiveBlock::Top && app.top.section == section,
+            current_route.hovered_block == ActiveBlock::Top && app.top.section == section,
+        )
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+        .split(layout_chunk);
+
+    let artists = app
+        .top
+        .artists
+        .iter()
+        .map(|artist| artist.name.as_str())
+        .collect::<Vec<&str>>();
+    draw_selectable_list(
+        f,
+        app,
+        chunks[0],
+        &format!(
+            "Top Artists - {} (<Tab> to switch)",
+            app.top.time_range.title()
+        ),
+        &artists,
+        highlight_state(TopSection::Artists),
+        Some(app.top.selected_artist_index),
+    );
+
+    let tracks = app
+        .top
+        .tracks
+        .iter()
+        .map(|track| format!("{} - {}", track.name, create_artist_string(&track.artists)))
+        .collect::<Vec<String>>();
+    draw_selectable_list(
+        f,
+        app,
+        chunks[1],
+        "Top Tracks",
+        &tracks,
+        highlight_state(TopSection::Tracks),
+        Some(app.top.selected_track_index),
+    );
+}
@@ -0,0 +1,222 @@
+use super::util::{create_artist_string, millis_to_minutes, with_modifier, Clear};
+use crate::app::App;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Modifier,
+    widgets::{Block, Borders, Gauge, Paragraph, Text, Widget},
+    Frame,
+};
+
+const PITCH_CLASSES: [&str; 12] = [
+    "C",
+    "C♯/D♭",
+    "D",
+    "D♯/E♭",
+    "E",
+    "F",
+    "F♯/G♭",
+    "G",
+    "G♯/A♭",
+    "A",
+    "A♯/B♭",
+    "B",
+];
+
+// Tempo gauges are relative to this, anything faster shows as full
+const MAX_TEMPO: f32 = 200.0;
+
+// Markets are listed by country code, so cap how many are shown
+const MAX_MARKETS_SHOWN: usize = 30;
+
+// `key` is a pitch class (-1 when no key was detected) and `mode` is 1 for major, 0 for minor
+pub fn get_key_name(key: i32, mode: f32) -> String {
+    if key < 0 || key as usize >= PITCH_CLASSES.len() {
+        return "Unknown".to_string();
+    }
+    let mode = if mode >= 1.0 { "major" } else { "minor" };
+    format!("{} {}", PITCH_CLASSES[key as usize], mode)
+}
+
+fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_y) / 2),
+                Constraint::Percentage(percent_y),
+                Constraint::Percentage((100 - percent_y) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_x) / 2),
+                Constraint::Percentage(percent_x),
+                Constraint::Percentage((100 - percent_x) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(vertical[1])[1]
+}
+
+pub fn draw<B>(f: &mut Frame<B>, app: &App)
+where
+    B: Backend,
+{
+    let track_info = match &app.track_info {
+        Some(track_info) => track_info,
+        None => return,
+    };
+    let track = &track_info.track;
+    let theme = &app.user_config.theme;
+
+    let area = centered_rect(70, 70, f.size());
+    Clear.render(f, area);
+
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .title(&track.name)
+        .title_style(with_modifier(theme.active, Modifier::BOLD))
+        .border_style(theme.active);
+    block.render(f, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .margin(1)
+        .split(block.inner(area));
+
+    let label = |name: &'static str| Text::styled(name, theme.hint);
+    let value = |value: String| Text::styled(format!("{}\n", value), theme.text);
+
+    let mut details = vec![
+        label("Artists: "),
+        value(create_artist_string(&track.artists)),
+        label("Album: "),
+        value(track.album.name.clone()),
+        label("Release date: "),
+        value(
+            track
+                .album
+                .release_date
+                .clone()
+                .unwrap_or_else(|| "Unknown".to_string()),
+        ),
+        label("Track: "),
+        value(format!(
+            "{} on disc {}",
+            track.track_number, track.disc_number
+        )),
+        label("Duration: "),
+        value(millis_to_minutes(u128::from(track.duration_ms))),
+        label("ISRC: "),
+        value(
+            track
+                .external_ids
+                .get("isrc")
+                .cloned()
+                .unwrap_or_else(|| "Unknown".to_string()),
+        ),
+        label("Popularity: "),
+        value(format!("{}/100", track.popularity)),
+        label("Explicit: "),
+        value(if track.explicit { "Yes" } else { "No" }.to_string()),
+    ];
+
+    if let Some(recommended_from) = &track_info.recommended_from {
+        details.push(label("Recommended from: "));
+        details.push(value(recommended_from.clone()));
+    }
+
+    let markets = &track.available_markets;
+    details.push(label("Available markets: "));
+    details.push(value(if markets.len() > MAX_MARKETS_SHOWN {
+        format!(
+            "{} (and {} more)",
+            markets[..MAX_MARKETS_SHOWN].join(", "),
+            markets.len() - MAX_MARKETS_SHOWN
+        )
+    } else if markets.is_empty() {
+        "None".to_string()
+    } else {
+        markets.join(", ")
+    }));
+
+    Paragraph::new(details.iter())
+        .wrap(true)
+        .style(theme.text)
+        .render(f, chunks[0]);
+
+    let features = match &track_info.audio_features {
+        Some(features) => features,
+        None => {
+            Paragraph::new([Text::styled("No audio features available", theme.hint)].iter())
+                .wrap(true)
+                .render(f, chunks[1]);
+            return;
+        }
+    };
+
+    let gauges = [
+        ("Danceability", features.danceability, None),
+        ("Energy", features.energy, None),
+        ("Valence", features.valence, None),
+        ("Acousticness", features.acousticness, None),
+        (
+            "Tempo",
+            features.tempo / MAX_TEMPO,
+            Some(format!("{:.0} BPM", features.tempo)),
+        ),
+    ];
+
+    let mut constraints = vec![Constraint::Length(2); gauges.len() + 1];
+    constraints.push(Constraint::Min(0));
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(chunks[1]);
+
+    Paragraph::new(
+        [
+            label("Key: "),
+            value(get_key_name(features.key, features.mode)),
+        ]
+        .iter(),
+    )
+    .render(f, rows[0]);
+
+    for ((title, ratio, gauge_label), row) in gauges.iter().zip(rows[1..].iter()) {
+        let percent = (ratio.clamp(0.0, 1.0) * 100.0) as u16;
+        let gauge_label = gauge_label
+            .clone()
+            .unwrap_or_else(|| format!("{}%", percent));
+        // The gauge label must fit inside the bar
+        if (row.width as usize) < gauge_label.len() {
+            continue;
+        }
+
+        Gauge::default()
+            .block(Block::default().title(title).title_style(theme.hint))
+            .style(theme.analysis_bar.bg(theme.playbar_background.fg))
+            .percent(percent)
+            .label(&gauge_label)
+            .render(f, *row);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_key_name() {
+        assert_eq!(get_key_name(0, 1.0), "C major");
+        assert_eq!(get_key_name(9, 0.0), "A minor");
+        assert_eq!(get_key_name(-1, 1.0), "Unknown");
+    }
+}
@@ -1,7 +1,15 @@
 use super::super::app::{ActiveBlock, App, ArtistBlock, SearchResultBlock};
 use crate::user_config::Theme;
-use rspotify::spotify::model::artist::SimplifiedArtist;
-use tui::style::Style;
+use rspotify::spotify::{
+    model::{artist::SimplifiedArtist, device::Device},
+    senum::DeviceType,
+};
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    widgets::Widget,
+};
 
 pub const SMALL_TERMINAL_HEIGHT: u16 = 45;
 
@@ -31,9 +39,27 @@ pub fn get_artist_highlight_state(app: &App, block_to_match: ArtistBlock) -> (bo
 
 pub fn get_color((is_active, is_hovered): (bool, bool), theme: Theme) -> Style {
     match (is_active, is_hovered) {
-        (true, _) => Style::default().fg(theme.selected),
-        (false, true) => Style::default().fg(theme.hovered),
-        _ => Style::default().fg(theme.inactive),
+        (true, _) => theme.selected,
+        (false, true) => theme.hovered,
+        _ => theme.inactive,
+    }
+}
+
+// Add modifiers on top of the ones set in the user's theme rather than replacing them
+pub fn with_modifier(style: Style, modifier: Modifier) -> Style {
+    style.modifier(style.modifier | modifier)
+}
+
+// Resets every cell in the area, e.g. under a popup
+pub struct Clear;
+
+impl Widget for Clear {
+    fn draw(&mut self, area: Rect, buf: &mut Buffer) {
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                buf.get_mut(x, y).reset();
+            }
+        }
     }
 }
 
@@ -93,10 +119,52 @@ pub fn get_main_layout_margin(app: &App) -> u16 {
     }
 }
 
+pub fn get_device_icon(device_type: &DeviceType) -> &'static str {
+    match device_type {
+        DeviceType::Computer => "💻",
+        DeviceType::Smartphone | DeviceType::Tablet => "📱",
+        DeviceType::Speaker => "🔊",
+        DeviceType::TV | DeviceType::STB | DeviceType::CastVideo => "📺",
+        DeviceType::AVR | DeviceType::AudioDongle | DeviceType::CastAudio => "📻",
+        DeviceType::GameConsole => "🎮",
+        DeviceType::Automobile => "🚗",
+        DeviceType::Unknown => "❔",
+    }
+}
+
+pub fn create_device_string(device: &Device) -> String {
+    let mut device_string = format!(
+        "{} {} ({}%)",
+        get_device_icon(&device._type),
+        device.name,
+        device.volume_percent
+    );
+    if device.is_active {
+        device_string.push_str(" [active]");
+    }
+    if device.is_restricted {
+        device_string.push_str(" [restricted]");
+    }
+    device_string
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn create_device_string_test() {
+        let device = Device {
+            id: "1".to_string(),
+            is_active: true,
+            is_restricted: false,
+            name: "Kitchen".to_string(),
+            _type: DeviceType::Speaker,
+            volume_percent: 40,
+        };
+        assert_eq!(create_device_string(&device), "🔊 Kitchen (40%) [active]");
+    }
+
     #[test]
     fn millis_to_minutes_test() {
         assert_eq!(millis_to_minutes(0), "0:00");
@@ -0,0 +1,140 @@
+//! Spotify URIs such as `spotify:album:<id>` and the open.spotify.com links shared from the
+//! Spotify apps, which both point at the same things.
+use std::fmt;
+
+const OPEN_URL: &str = "https://open.spotify.com";
+
+/// What a URI points at
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UriKind {
+    Album,
+    Artist,
+    Episode,
+    Playlist,
+    Show,
+    Track,
+    User,
+}
+
+impl UriKind {
+    fn from_str(kind: &str) -> Option<UriKind> {
+        match kind {
+            "album" => Some(UriKind::Album),
+            "artist" => Some(UriKind::Artist),
+            "episode" => Some(UriKind::Episode),
+            "playlist" => Some(UriKind::Playlist),
+            "show" => Some(UriKind::Show),
+            "track" => Some(UriKind::Track),
+            "user" => Some(UriKind::User),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UriKind::Album => "album",
+            UriKind::Artist => "artist",
+            UriKind::Episode => "episode",
+            UriKind::Playlist => "playlist",
+            UriKind::Show => "show",
+            UriKind::Track => "track",
+            UriKind::User => "user",
+        }
+    }
+}
+
+/// An album, artist, track and so on, displayed as `spotify:<kind>:<id>`
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpotifyUri {
+    pub kind: UriKind,
+    pub id: String,
+}
+
+impl SpotifyUri {
+    pub fn new(kind: UriKind, id: &str) -> SpotifyUri {
+        SpotifyUri {
+            kind,
+            id: id.to_string(),
+        }
+    }
+
+    /// Parse `spotify:track:<id>` or an open.spotify.com link, ignoring the `?si=` share
+    /// parameter, `intl-xx` locale segments and the old `user/<name>/playlist/<id>` form
+    pub fn parse(input: &str) -> Option<SpotifyUri> {
+        let input = input.trim();
+        let parts: Vec<&str> = if input.starts_with("spotify:") {
+            input.split(':').skip(1).collect()
+        } else {
+            let path = input
+                .trim_start_matches("https://")
+                .trim_start_matches("http://")
+                .strip_prefix("open.spotify.com/")?;
+            let path = path.split(['?', '#']).next().unwrap_or(path);
+            path.split('/')
+                .filter(|part| !part.is_empty() && !part.starts_with("intl-") && *part != "embed")
+                .collect()
+        };
+
+        // A playlist under a user is still just the playlist
+        let parts = match parts.as_slice() {
+            ["user", _, rest @ ..] if !rest.is_empty() => rest,
+            parts => parts,
+        };
+        match parts {
+            [kind, id] if !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric()) => {
+                Some(SpotifyUri::new(UriKind::from_str(kind)?, id))
+            }
+            _ => None,
+        }
+    }
+
+    /// The link to share, e.g. `https://open.spotify.com/track/<id>`
+    pub fn url(&self) -> String {
+        format!("{}/{}/{}", OPEN_URL, self.kind.as_str(), self.id)
+    }
+}
+
+impl fmt::Display for SpotifyUri {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "spotify:{}:{}", self.kind.as_str(), self.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let album = SpotifyUri::new(UriKind::Album, "4aawyAB9vmqN3uQ7FjRGTy");
+        for input in &[
+            "spotify:album:4aawyAB9vmqN3uQ7FjRGTy",
+            "https://open.spotify.com/album/4aawyAB9vmqN3uQ7FjRGTy",
+            "https://open.spotify.com/album/4aawyAB9vmqN3uQ7FjRGTy?si=abc123",
+            "https://open.spotify.com/intl-de/album/4aawyAB9vmqN3uQ7FjRGTy",
+            " open.spotify.com/album/4aawyAB9vmqN3uQ7FjRGTy ",
+        ] {
+            assert_eq!(SpotifyUri::parse(input), Some(album.clone()), "{}", input);
+        }
+
+        assert_eq!(
+            SpotifyUri::parse("spotify:user:bob:playlist:37i9dQZF1DX0XUsuxWHRQd"),
+            Some(SpotifyUri::new(UriKind::Playlist, "37i9dQZF1DX0XUsuxWHRQd"))
+        );
+        assert_eq!(
+            SpotifyUri::parse("https://open.spotify.com/user/bob"),
+            Some(SpotifyUri::new(UriKind::User, "bob"))
+        );
+        assert_eq!(SpotifyUri::parse("spotify:album:"), None);
+        assert_eq!(SpotifyUri::parse("spotify:concert:abc"), None);
+        assert_eq!(SpotifyUri::parse("https://example.com/album/abc"), None);
+        assert_eq!(SpotifyUri::parse("some search"), None);
+    }
+
+    #[test]
+    fn test_format() {
+        let track = SpotifyUri::new(UriKind::Track, "abc");
+        assert_eq!(track.to_string(), "spotify:track:abc");
+        assert_eq!(track.url(), "https://open.spotify.com/track/abc");
+    }
+}
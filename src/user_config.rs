@@ -1,4 +1,4 @@
-use crate::event::Key;
+use crate::{album_art::Protocol, config::ClientConfig, event::Key, export::ExportFormat};
 use dirs;
 use failure::err_msg;
 use serde::{Deserialize, Serialize};
@@ -6,63 +6,191 @@ use std::{
     fs,
     path::{Path, PathBuf},
 };
-use tui::style::Color;
+use tui::style::{Color, Modifier, Style};
 
 const FILE_NAME: &str = "config.yml";
 const CONFIG_DIR: &str = ".config";
 const APP_CONFIG_DIR: &str = "spotify-tui";
+// Theme files may point at another theme through `base`, guard against cycles
+const MAX_THEME_DEPTH: u8 = 8;
 
+pub const THEME_PRESETS: [&str; 5] = ["default", "solarized", "gruvbox", "nord", "monochrome"];
+
+// The `theme` key can either name a preset/theme file or define the colours inline
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum UserThemeConfig {
+    Named(String),
+    Custom(Box<UserTheme>),
+}
+
+#[derive(Default, Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct UserTheme {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_theme_item")]
+    pub analysis_bar: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_theme_item")]
+    pub analysis_bar_text: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_theme_item")]
     pub active: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_theme_item")]
     pub banner: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_theme_item")]
     pub error_border: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_theme_item")]
     pub error_text: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_theme_item")]
     pub hint: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_theme_item")]
     pub hovered: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_theme_item")]
     pub inactive: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_theme_item")]
     pub playbar_background: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_theme_item")]
     pub playbar_progress: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_theme_item")]
     pub playbar_text: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_theme_item")]
     pub selected: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_theme_item")]
     pub text: Option<String>,
 }
 
-#[derive(Copy, Clone, Debug)]
+// Unquoted palette indices (e.g. `hint: 208`) are read by YAML as numbers
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ThemeItem {
+    Text(String),
+    Index(u8),
+}
+
+fn deserialize_theme_item<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let item = Option::<ThemeItem>::deserialize(deserializer)?;
+    Ok(item.map(|item| match item {
+        ThemeItem::Text(text) => text,
+        ThemeItem::Index(index) => index.to_string(),
+    }))
+}
+
+// Each item is a full style so that users can set modifiers (bold, italic...) per element.
+// Only the foreground colour is used for items acting as a background (e.g. `playbar_background`)
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Theme {
-    pub analysis_bar: Color,
-    pub analysis_bar_text: Color,
-    pub active: Color,
-    pub banner: Color,
-    pub error_border: Color,
-    pub error_text: Color,
-    pub hint: Color,
-    pub hovered: Color,
-    pub inactive: Color,
-    pub playbar_background: Color,
-    pub playbar_progress: Color,
-    pub playbar_text: Color,
-    pub selected: Color,
-    pub text: Color,
+    pub analysis_bar: Style,
+    pub analysis_bar_text: Style,
+    pub active: Style,
+    pub banner: Style,
+    pub error_border: Style,
+    pub error_text: Style,
+    pub hint: Style,
+    pub hovered: Style,
+    pub inactive: Style,
+    pub playbar_background: Style,
+    pub playbar_progress: Style,
+    pub playbar_text: Style,
+    pub selected: Style,
+    pub text: Style,
+}
+
+fn fg(color: Color) -> Style {
+    Style::default().fg(color)
 }
 
 impl Default for Theme {
     fn default() -> Self {
         Theme {
-            analysis_bar: Color::LightCyan,
-            analysis_bar_text: Color::Black,
-            active: Color::Cyan,
-            banner: Color::LightCyan,
-            error_border: Color::Red,
-            error_text: Color::LightRed,
-            hint: Color::Yellow,
-            hovered: Color::Magenta,
-            inactive: Color::Gray,
-            playbar_background: Color::Black,
-            playbar_progress: Color::LightCyan,
-            playbar_text: Color::White,
-            selected: Color::LightCyan,
-            text: Color::White,
+            analysis_bar: fg(Color::LightCyan),
+            analysis_bar_text: fg(Color::Black),
+            active: fg(Color::Cyan),
+            banner: fg(Color::LightCyan),
+            error_border: fg(Color::Red),
+            error_text: fg(Color::LightRed),
+            hint: fg(Color::Yellow),
+            hovered: fg(Color::Magenta),
+            inactive: fg(Color::Gray),
+            playbar_background: fg(Color::Black),
+            playbar_progress: fg(Color::LightCyan),
+            playbar_text: fg(Color::White),
+            selected: fg(Color::LightCyan),
+            text: fg(Color::White),
+        }
+    }
+}
+
+impl Theme {
+    pub fn preset(name: &str) -> Option<Theme> {
+        match name.to_lowercase().as_str() {
+            "default" => Some(Theme::default()),
+            "solarized" => Some(Theme {
+                analysis_bar: fg(Color::Rgb(42, 161, 152)),
+                analysis_bar_text: fg(Color::Rgb(0, 43, 54)),
+                active: fg(Color::Rgb(38, 139, 210)),
+                banner: fg(Color::Rgb(42, 161, 152)),
+                error_border: fg(Color::Rgb(220, 50, 47)),
+                error_text: fg(Color::Rgb(203, 75, 22)),
+                hint: fg(Color::Rgb(181, 137, 0)),
+                hovered: fg(Color::Rgb(211, 54, 130)),
+                inactive: fg(Color::Rgb(88, 110, 117)),
+                playbar_background: fg(Color::Rgb(7, 54, 66)),
+                playbar_progress: fg(Color::Rgb(42, 161, 152)),
+                playbar_text: fg(Color::Rgb(147, 161, 161)),
+                selected: fg(Color::Rgb(42, 161, 152)),
+                text: fg(Color::Rgb(131, 148, 150)),
+            }),
+            "gruvbox" => Some(Theme {
+                analysis_bar: fg(Color::Rgb(142, 192, 124)),
+                analysis_bar_text: fg(Color::Rgb(40, 40, 40)),
+                active: fg(Color::Rgb(131, 165, 152)),
+                banner: fg(Color::Rgb(254, 128, 25)),
+                error_border: fg(Color::Rgb(251, 73, 52)),
+                error_text: fg(Color::Rgb(251, 73, 52)),
+                hint: fg(Color::Rgb(250, 189, 47)),
+                hovered: fg(Color::Rgb(211, 134, 155)),
+                inactive: fg(Color::Rgb(146, 131, 116)),
+                playbar_background: fg(Color::Rgb(60, 56, 54)),
+                playbar_progress: fg(Color::Rgb(184, 187, 38)),
+                playbar_text: fg(Color::Rgb(235, 219, 178)),
+                selected: fg(Color::Rgb(250, 189, 47)),
+                text: fg(Color::Rgb(235, 219, 178)),
+            }),
+            "nord" => Some(Theme {
+                analysis_bar: fg(Color::Rgb(136, 192, 208)),
+                analysis_bar_text: fg(Color::Rgb(46, 52, 64)),
+                active: fg(Color::Rgb(129, 161, 193)),
+                banner: fg(Color::Rgb(136, 192, 208)),
+                error_border: fg(Color::Rgb(191, 97, 106)),
+                error_text: fg(Color::Rgb(208, 135, 112)),
+                hint: fg(Color::Rgb(235, 203, 139)),
+                hovered: fg(Color::Rgb(180, 142, 173)),
+                inactive: fg(Color::Rgb(76, 86, 106)),
+                playbar_background: fg(Color::Rgb(59, 66, 82)),
+                playbar_progress: fg(Color::Rgb(136, 192, 208)),
+                playbar_text: fg(Color::Rgb(236, 239, 244)),
+                selected: fg(Color::Rgb(136, 192, 208)),
+                text: fg(Color::Rgb(216, 222, 233)),
+            }),
+            "monochrome" => Some(Theme {
+                analysis_bar: fg(Color::Gray),
+                analysis_bar_text: fg(Color::Black),
+                active: fg(Color::White).modifier(Modifier::BOLD),
+                banner: fg(Color::White),
+                error_border: fg(Color::White).modifier(Modifier::BOLD),
+                error_text: fg(Color::White).modifier(Modifier::BOLD),
+                hint: fg(Color::Gray).modifier(Modifier::ITALIC),
+                hovered: fg(Color::White).modifier(Modifier::UNDERLINED),
+                inactive: fg(Color::DarkGray),
+                playbar_background: fg(Color::Black),
+                playbar_progress: fg(Color::White),
+                playbar_text: fg(Color::Gray),
+                selected: fg(Color::White).modifier(Modifier::BOLD),
+                text: fg(Color::Gray),
+            }),
+            _ => None,
         }
     }
 }
@@ -163,6 +291,13 @@ pub struct KeyBindingsString {
     copy_song_url: Option<String>,
     copy_album_url: Option<String>,
     audio_analysis: Option<String>,
+    lyrics: Option<String>,
+    track_info: Option<String>,
+    recommendations_builder: Option<String>,
+    add_recommendation_seed: Option<String>,
+    toggle_radio: Option<String>,
+    switch_profile: Option<String>,
+    export: Option<String>,
 }
 
 pub struct KeyBindings {
@@ -185,6 +320,13 @@ pub struct KeyBindings {
     pub copy_song_url: Key,
     pub copy_album_url: Key,
     pub audio_analysis: Key,
+    pub lyrics: Key,
+    pub track_info: Key,
+    pub recommendations_builder: Key,
+    pub add_recommendation_seed: Key,
+    pub toggle_radio: Key,
+    pub switch_profile: Key,
+    pub export: Key,
 }
 
 #[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -192,27 +334,54 @@ pub struct BehaviorConfigString {
     pub seek_milliseconds: Option<u32>,
     pub volume_increment: Option<u8>,
     pub tick_rate_milliseconds: Option<u64>,
+    pub show_album_art: Option<bool>,
+    pub album_art_size: Option<u16>,
+    pub album_art_protocol: Option<String>,
+    pub lyrics_dir: Option<String>,
+    pub lyrics_command: Option<String>,
+    pub listening_history: Option<bool>,
+    pub export_dir: Option<String>,
+    pub export_format: Option<String>,
 }
 
 pub struct BehaviorConfig {
     pub seek_milliseconds: u32,
     pub volume_increment: u8,
     pub tick_rate_milliseconds: u64,
+    pub show_album_art: bool,
+    // Width in columns, the art takes half as many rows
+    pub album_art_size: u16,
+    pub album_art_protocol: Protocol,
+    // Directory of `.lrc` files and a command printing lyrics, both optional
+    pub lyrics_dir: Option<String>,
+    pub lyrics_command: Option<String>,
+    // Save every play to a local database for the Stats screen
+    pub listening_history: bool,
+    // Where `E` saves exports, the downloads directory when not set
+    pub export_dir: Option<String>,
+    pub export_format: ExportFormat,
 }
 
 #[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct UserConfigString {
     keybindings: Option<KeyBindingsString>,
     behavior: Option<BehaviorConfigString>,
-    theme: Option<UserTheme>,
+    theme: Option<UserThemeConfig>,
 }
 
+/// `config.yml`: keybindings, theme and behaviour, with the defaults for anything not set
 pub struct UserConfig {
     pub keys: KeyBindings,
     pub theme: Theme,
     pub behavior: BehaviorConfig,
 }
 
+impl Default for UserConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl UserConfig {
     pub fn new() -> UserConfig {
         UserConfig {
@@ -237,11 +406,26 @@ impl UserConfig {
                 copy_song_url: Key::Char('c'),
                 copy_album_url: Key::Char('C'),
                 audio_analysis: Key::Char('v'),
+                lyrics: Key::Char('y'),
+                track_info: Key::Char('i'),
+                recommendations_builder: Key::Char('R'),
+                add_recommendation_seed: Key::Char('S'),
+                toggle_radio: Key::Char('o'),
+                switch_profile: Key::Char('P'),
+                export: Key::Char('E'),
             },
             behavior: BehaviorConfig {
                 seek_milliseconds: 5 * 1000,
                 volume_increment: 10,
                 tick_rate_milliseconds: 250,
+                show_album_art: false,
+                album_art_size: 12,
+                album_art_protocol: Protocol::Auto,
+                lyrics_dir: None,
+                lyrics_command: None,
+                listening_history: true,
+                export_dir: None,
+                export_format: ExportFormat::Csv,
             },
         }
     }
@@ -305,6 +489,13 @@ impl UserConfig {
         to_keys!(copy_song_url);
         to_keys!(copy_album_url);
         to_keys!(audio_analysis);
+        to_keys!(lyrics);
+        to_keys!(track_info);
+        to_keys!(recommendations_builder);
+        to_keys!(add_recommendation_seed);
+        to_keys!(toggle_radio);
+        to_keys!(switch_profile);
+        to_keys!(export);
 
         Ok(())
     }
@@ -313,11 +504,13 @@ impl UserConfig {
         macro_rules! to_theme_item {
             ($name: ident) => {
                 if let Some(theme_item) = theme.$name {
-                    self.theme.$name = parse_theme_item(&theme_item)?;
+                    self.theme.$name = parse_theme_style(&theme_item)?;
                 }
             };
         };
 
+        to_theme_item!(analysis_bar);
+        to_theme_item!(analysis_bar_text);
         to_theme_item!(active);
         to_theme_item!(banner);
         to_theme_item!(error_border);
@@ -333,6 +526,103 @@ impl UserConfig {
         Ok(())
     }
 
+    pub fn load_theme_config(
+        &mut self,
+        theme_config: UserThemeConfig,
+    ) -> Result<(), failure::Error> {
+        self.load_theme_config_at_depth(theme_config, 0)
+    }
+
+    fn load_theme_config_at_depth(
+        &mut self,
+        theme_config: UserThemeConfig,
+        depth: u8,
+    ) -> Result<(), failure::Error> {
+        if depth > MAX_THEME_DEPTH {
+            return Err(err_msg("Too many nested theme `base` references"));
+        }
+
+        match theme_config {
+            UserThemeConfig::Named(name) => self.load_named_theme(&name, depth),
+            UserThemeConfig::Custom(theme) => {
+                if let Some(base) = &theme.base {
+                    self.load_named_theme(base, depth)?;
+                }
+                self.load_theme(*theme)
+            }
+        }
+    }
+
+    // A name is either one of `THEME_PRESETS` or a path to a theme file
+    fn load_named_theme(&mut self, name: &str, depth: u8) -> Result<(), failure::Error> {
+        if let Some(preset) = Theme::preset(name) {
+            self.theme = preset;
+            return Ok(());
+        }
+
+        let path = self.get_theme_file_path(name)?;
+        if !path.exists() {
+            return Err(failure::format_err!(
+                "Unknown theme \"{}\": expected one of {} or a path to a theme file",
+                name,
+                THEME_PRESETS.join(", ")
+            ));
+        }
+
+        let theme_string = fs::read_to_string(&path)?;
+        let theme: UserTheme = serde_yaml::from_str(&theme_string)
+            .map_err(|e| failure::format_err!("Invalid theme file {}: {}", path.display(), e))?;
+        self.load_theme_config_at_depth(UserThemeConfig::Custom(Box::new(theme)), depth + 1)
+    }
+
+    // Relative theme paths are resolved against the config directory
+    fn get_theme_file_path(&self, name: &str) -> Result<PathBuf, failure::Error> {
+        if let Some(relative) = name.strip_prefix("~/") {
+            if let Some(home) = dirs::home_dir() {
+                return Ok(home.join(relative));
+            }
+        }
+
+        let path = PathBuf::from(name);
+        if path.is_absolute() {
+            return Ok(path);
+        }
+
+        let paths = self.get_or_build_paths()?;
+        match paths.config_file_path.parent() {
+            Some(config_dir) => Ok(config_dir.join(path)),
+            None => Ok(path),
+        }
+    }
+
+    pub fn dump_theme(&self) -> Result<String, failure::Error> {
+        macro_rules! from_theme_item {
+            ($name: ident) => {
+                Some(style_to_string(self.theme.$name))
+            };
+        }
+
+        let theme = UserTheme {
+            base: None,
+            analysis_bar: from_theme_item!(analysis_bar),
+            analysis_bar_text: from_theme_item!(analysis_bar_text),
+            active: from_theme_item!(active),
+            banner: from_theme_item!(banner),
+            error_border: from_theme_item!(error_border),
+            error_text: from_theme_item!(error_text),
+            hint: from_theme_item!(hint),
+            hovered: from_theme_item!(hovered),
+            inactive: from_theme_item!(inactive),
+            playbar_background: from_theme_item!(playbar_background),
+            playbar_progress: from_theme_item!(playbar_progress),
+            playbar_text: from_theme_item!(playbar_text),
+            selected: from_theme_item!(selected),
+            text: from_theme_item!(text),
+        };
+
+        Ok(serde_yaml::to_string(&theme)?)
+    }
+
     pub fn load_behaviorconfig(
         &mut self,
         behavior_config: BehaviorConfigString,
@@ -359,13 +649,57 @@ impl UserConfig {
             }
         }
 
+        if let Some(show_album_art) = behavior_config.show_album_art {
+            self.behavior.show_album_art = show_album_art;
+        }
+
+        if let Some(album_art_size) = behavior_config.album_art_size {
+            if album_art_size < 2 {
+                return Err(err_msg("Album art size must be at least 2"));
+            }
+            self.behavior.album_art_size = album_art_size;
+        }
+
+        if let Some(album_art_protocol) = behavior_config.album_art_protocol {
+            self.behavior.album_art_protocol = Protocol::parse(&album_art_protocol)?;
+        }
+
+        if let Some(lyrics_dir) = behavior_config.lyrics_dir {
+            self.behavior.lyrics_dir = Some(lyrics_dir);
+        }
+
+        if let Some(lyrics_command) = behavior_config.lyrics_command {
+            self.behavior.lyrics_command = Some(lyrics_command);
+        }
+
+        if let Some(listening_history) = behavior_config.listening_history {
+            self.behavior.listening_history = listening_history;
+        }
+
+        if let Some(export_dir) = behavior_config.export_dir {
+            self.behavior.export_dir = Some(export_dir);
+        }
+
+        if let Some(export_format) = behavior_config.export_format {
+            self.behavior.export_format = ExportFormat::parse(&export_format)?;
+        }
+
         Ok(())
     }
 
     pub fn load_config(&mut self) -> Result<(), failure::Error> {
         let paths = self.get_or_build_paths()?;
-        if paths.config_file_path.exists() {
-            let config_string = fs::read_to_string(&paths.config_file_path)?;
+        self.load_config_file(&paths.config_file_path)
+    }
+
+    /// Apply a profile's own config.yml on top of the main one
+    pub fn load_profile_config(&mut self, profile_dir: &Path) -> Result<(), failure::Error> {
+        self.load_config_file(&profile_dir.join(FILE_NAME))
+    }
+
+    fn load_config_file(&mut self, config_file_path: &Path) -> Result<(), failure::Error> {
+        if config_file_path.exists() {
+            let config_string = fs::read_to_string(config_file_path)?;
             // serde fails if file is empty
             if config_string.trim().is_empty() {
                 return Ok(());
@@ -381,7 +715,7 @@ impl UserConfig {
                 self.load_behaviorconfig(behavior)?;
             }
             if let Some(theme) = config_yml.theme {
-                self.load_theme(theme)?;
+                self.load_theme_config(theme)?;
             }
 
             Ok(())
@@ -391,41 +725,152 @@ impl UserConfig {
     }
 }
 
+/// Load the main config.yml followed by the profile's own overrides
+pub fn load_user_config(client_config: &ClientConfig) -> Result<UserConfig, failure::Error> {
+    let mut user_config = UserConfig::new();
+    user_config.load_config()?;
+    if client_config.profile.is_some() {
+        let config_paths = client_config.get_or_build_paths()?;
+        user_config.load_profile_config(&config_paths.config_dir)?;
+    }
+    Ok(user_config)
+}
+
+const COLOR_NAMES: [(&str, Color); 17] = [
+    ("Reset", Color::Reset),
+    ("Black", Color::Black),
+    ("Red", Color::Red),
+    ("Green", Color::Green),
+    ("Yellow", Color::Yellow),
+    ("Blue", Color::Blue),
+    ("Magenta", Color::Magenta),
+    ("Cyan", Color::Cyan),
+    ("Gray", Color::Gray),
+    ("DarkGray", Color::DarkGray),
+    ("LightRed", Color::LightRed),
+    ("LightGreen", Color::LightGreen),
+    ("LightYellow", Color::LightYellow),
+    ("LightBlue", Color::LightBlue),
+    ("LightMagenta", Color::LightMagenta),
+    ("LightCyan", Color::LightCyan),
+    ("White", Color::White),
+];
+
+const MODIFIER_NAMES: [(&str, Modifier); 6] = [
+    ("bold", Modifier::BOLD),
+    ("dim", Modifier::DIM),
+    ("italic", Modifier::ITALIC),
+    ("underlined", Modifier::UNDERLINED),
+    ("reversed", Modifier::REVERSED),
+    ("crossed_out", Modifier::CROSSED_OUT),
+];
+
+fn parse_modifier(word: &str) -> Option<Modifier> {
+    let word = word.to_lowercase();
+    // Accept "underline" as well as the tui name
+    let word = if word == "underline" {
+        "underlined"
+    } else {
+        &word
+    };
+
+    MODIFIER_NAMES
+        .iter()
+        .find(|(name, _)| *name == word)
+        .map(|(_, modifier)| *modifier)
+}
+
+fn parse_hex_color(hex: &str) -> Result<Color, failure::Error> {
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(failure::format_err!(
+            "Hex colours must be of the form #rrggbb, got \"#{}\"",
+            hex
+        ));
+    }
+
+    Ok(Color::Rgb(
+        u8::from_str_radix(&hex[0..2], 16)?,
+        u8::from_str_radix(&hex[2..4], 16)?,
+        u8::from_str_radix(&hex[4..6], 16)?,
+    ))
+}
+
 fn parse_theme_item(theme_item: &str) -> Result<Color, failure::Error> {
-    let color = match theme_item {
-        "Reset" => Color::Reset,
-        "Black" => Color::Black,
-        "Red" => Color::Red,
-        "Green" => Color::Green,
-        "Yellow" => Color::Yellow,
-        "Blue" => Color::Blue,
-        "Magenta" => Color::Magenta,
-        "Cyan" => Color::Cyan,
-        "Gray" => Color::Gray,
-        "DarkGray" => Color::DarkGray,
-        "LightRed" => Color::LightRed,
-        "LightGreen" => Color::LightGreen,
-        "LightYellow" => Color::LightYellow,
-        "LightBlue" => Color::LightBlue,
-        "LightMagenta" => Color::LightMagenta,
-        "LightCyan" => Color::LightCyan,
-        "White" => Color::White,
-        _ => {
-            let colors = theme_item.split(',').collect::<Vec<&str>>();
-            if let (Some(r), Some(g), Some(b)) = (colors.get(0), colors.get(1), colors.get(2)) {
-                Color::Rgb(
-                    r.trim().parse::<u8>()?,
-                    g.trim().parse::<u8>()?,
-                    b.trim().parse::<u8>()?,
-                )
-            } else {
-                println!("Unexpected color {}", theme_item);
-                Color::Black
+    let theme_item = theme_item.trim();
+
+    if let Some((_, color)) = COLOR_NAMES.iter().find(|(name, _)| *name == theme_item) {
+        return Ok(*color);
+    }
+
+    if let Some(hex) = theme_item.strip_prefix('#') {
+        return parse_hex_color(hex);
+    }
+
+    // A single number is an index into the terminal's 256 colour palette
+    if let Ok(index) = theme_item.parse::<u8>() {
+        return Ok(Color::Indexed(index));
+    }
+
+    let colors = theme_item.split(',').collect::<Vec<&str>>();
+    let color = if let (Some(r), Some(g), Some(b)) = (colors.first(), colors.get(1), colors.get(2))
+    {
+        Color::Rgb(
+            r.trim().parse::<u8>()?,
+            g.trim().parse::<u8>()?,
+            b.trim().parse::<u8>()?,
+        )
+    } else {
+        println!("Unexpected color {}", theme_item);
+        Color::Black
+    };
+
+    Ok(color)
+}
+
+// A theme item is a colour optionally followed by modifiers, e.g. "#ebdbb2 bold italic"
+fn parse_theme_style(theme_item: &str) -> Result<Style, failure::Error> {
+    let mut words = theme_item.split_whitespace().collect::<Vec<&str>>();
+    let mut modifier = Modifier::empty();
+
+    while let Some(word) = words.last() {
+        match parse_modifier(word) {
+            Some(m) => {
+                modifier |= m;
+                words.pop();
             }
+            None => break,
         }
+    }
+
+    let color = if words.is_empty() {
+        Color::Reset
+    } else {
+        parse_theme_item(&words.join(" "))?
     };
 
-    Ok(color)
+    Ok(Style::default().fg(color).modifier(modifier))
+}
+
+fn color_to_string(color: Color) -> String {
+    match color {
+        Color::Rgb(r, g, b) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        Color::Indexed(index) => index.to_string(),
+        _ => COLOR_NAMES
+            .iter()
+            .find(|(_, c)| *c == color)
+            .map(|(name, _)| name.to_string())
+            .unwrap_or_else(|| "Reset".to_string()),
+    }
+}
+
+fn style_to_string(style: Style) -> String {
+    let mut words = vec![color_to_string(style.fg)];
+    for (name, modifier) in MODIFIER_NAMES.iter() {
+        if style.modifier.contains(*modifier) {
+            words.push(name.to_string());
+        }
+    }
+    words.join(" ")
 }
 
 #[cfg(test)]
@@ -471,6 +916,80 @@ mod tests {
             parse_theme_item("23, 43, 45").unwrap(),
             Color::Rgb(23, 43, 45)
         );
+        assert_eq!(
+            parse_theme_item("#ebdbb2").unwrap(),
+            Color::Rgb(235, 219, 178)
+        );
+        assert_eq!(parse_theme_item("214").unwrap(), Color::Indexed(214));
+        assert!(parse_theme_item("#ebdb").is_err());
+    }
+
+    #[test]
+    fn parse_theme_style_test() {
+        use super::parse_theme_style;
+        use tui::style::{Color, Modifier, Style};
+        assert_eq!(
+            parse_theme_style("LightCyan").unwrap(),
+            Style::default().fg(Color::LightCyan)
+        );
+        assert_eq!(
+            parse_theme_style("#88c0d0 bold underline").unwrap(),
+            Style::default()
+                .fg(Color::Rgb(136, 192, 208))
+                .modifier(Modifier::BOLD | Modifier::UNDERLINED)
+        );
+        assert_eq!(
+            parse_theme_style("23, 43, 45 Italic").unwrap(),
+            Style::default()
+                .fg(Color::Rgb(23, 43, 45))
+                .modifier(Modifier::ITALIC)
+        );
+    }
+
+    #[test]
+    fn theme_presets_test() {
+        use super::{Theme, UserConfig, UserThemeConfig, THEME_PRESETS};
+
+        for name in THEME_PRESETS.iter() {
+            assert!(Theme::preset(name).is_some(), "{} should be a preset", name);
+        }
+
+        let mut config = UserConfig::new();
+        config
+            .load_theme_config(UserThemeConfig::Named("nord".to_string()))
+            .unwrap();
+        assert_eq!(config.theme, Theme::preset("nord").unwrap());
+    }
+
+    #[test]
+    fn dump_theme_round_trip_test() {
+        use super::{Theme, UserConfig, UserTheme};
+
+        let mut config = UserConfig::new();
+        config.theme = Theme::preset("monochrome").unwrap();
+        let dumped = config.dump_theme().unwrap();
+
+        let mut reloaded = UserConfig::new();
+        reloaded
+            .load_theme(serde_yaml::from_str::<UserTheme>(&dumped).unwrap())
+            .unwrap();
+        assert_eq!(reloaded.theme, config.theme);
+    }
+
+    #[test]
+    fn hex_theme_item_yaml_test() {
+        use super::{UserConfig, UserTheme};
+        use tui::style::Color;
+
+        let mut config = UserConfig::new();
+        config
+            .load_theme(serde_yaml::from_str::<UserTheme>("hint: \"#ebdbb2\"").unwrap())
+            .unwrap();
+        assert_eq!(config.theme.hint.fg, Color::Rgb(235, 219, 178));
+
+        // Unquoted, YAML reads everything from the `#` as a comment
+        let theme = serde_yaml::from_str::<UserTheme>("hint: #ebdbb2").unwrap();
+        assert_eq!(theme.hint, None);
     }
 
     #[test]